@@ -1,4 +1,5 @@
 #import "shader_common/common_uniform.wgsl"
+#import "shader_common/audio_uniform.wgsl"
 //#import "shader_common/shapefuncs.wgsl"
 
 
@@ -10,8 +11,15 @@ struct VertexOutput {
 };
 
 
+struct ErraticWaveUniform {
+    common: CommonUniformData;
+    audio: AudioUniformData;
+    bass_swell: f32;
+};
+
+
 [[group(1), binding(0)]]
-var<uniform> u: CommonUniformData;
+var<uniform> uniform_data: ErraticWaveUniform;
 
 
 fn plot2(uv: vec2<f32>, pct: f32, top_feather: f32, bottom_feather: f32) -> f32 {
@@ -47,6 +55,7 @@ fn fragment(input: VertexOutput) -> [[location(0)]] vec4<f32> {
     var waveColor = vec3<f32>(0.043, 0.525, 0.756) * ((1.0-input.uv.y) * 0.8);
 
     // var y: f32 = adjusted_sin(input.uv.x * abs(sin(u.time % 60.0)) * 5.5 + u.time);
+    let u = uniform_data.common;
     let wave_height = 0.5;
     let max_y_shrink = 30.0;
     let min_y_shrink = 10.0;
@@ -62,6 +71,8 @@ fn fragment(input: VertexOutput) -> [[location(0)]] vec4<f32> {
     if (wave_y_shrink < min_y_shrink) {
         wave_y_shrink = min_y_shrink;
     }
+    // The bass swells the wave (without audio it stays as is)
+    wave_y_shrink = wave_y_shrink / (1.0 + uniform_data.audio.bands.x * uniform_data.bass_swell);
     var y: f32 = adjusted_sin(input.uv.x * abs(sin(u.time % 60.0)) * 5.5 + u.time, wave_y_shrink, wave_height);
     // var y: f32 = adjusted_sin(input.uv.x + u.time, wave_y_shrink, wave_height);
 
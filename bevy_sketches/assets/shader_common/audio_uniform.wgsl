@@ -0,0 +1,7 @@
+// Mirrors `AudioUniformData` in src/shader_materials/core.rs
+struct AudioUniformData {
+    // Bass, low mids, high mids and treble, 0.0 to 1.0
+    bands: vec4<f32>;
+    // 1.0 on a beat, fading to 0.0
+    beat: f32;
+};
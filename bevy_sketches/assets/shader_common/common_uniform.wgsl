@@ -1,4 +1,17 @@
+// Colors in uniforms are linear RGB with straight (not premultiplied) alpha, converted from sRGB
+// `Color`s with `color_to_shader_vec3` / `color_to_shader_vec4` in src/shader_materials/core.rs.
+// Fragment shaders return linear colors too, which the render target encodes to sRGB, so colors
+// render as specified. Convert sRGB constants (e.g. from CSS) before using them in shaders.
+
+// Mirrors `CommonUniformData` in src/shader_materials/core.rs
 struct CommonUniformData {
     time: f32;
     resolution: vec2<f32>;
+    // `pointer` in Rust (a reserved word in WGSL): pointer on the quad, (0, 0) at the bottom left
+    // to (1, 1) at the top right
+    pointer_uv: vec2<f32>;
+    pointer_world: vec2<f32>;
+    last_click: vec2<f32>;
+    // 1.0 while pressed
+    pointer_pressed: f32;
 };
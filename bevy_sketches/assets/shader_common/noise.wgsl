@@ -0,0 +1,122 @@
+// Mirrors src/noise.rs function by function, so shaders get the same values as the CPU for the
+// same seed. Change both together: the reference tables in its tests cover the hashing.
+
+let NOISE_MAX_OCTAVES: u32 = 8u;
+let NOISE_LACUNARITY: f32 = 2.0;
+let NOISE_GAIN: f32 = 0.5;
+
+
+// Mirrors `NoiseUniformData` in src/shader_materials/core.rs
+struct NoiseField {
+    seed: u32;
+    // Lattice cells per world unit
+    frequency: f32;
+    octaves: u32;
+};
+
+
+// PCG hash of a 32-bit value
+fn noise_hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+
+// Hash of a lattice cell for a seed
+fn noise_hash2(cell: vec2<i32>, seed: u32) -> u32 {
+    let inner = noise_hash(bitcast<u32>(cell.y) + noise_hash(seed));
+    return noise_hash(bitcast<u32>(cell.x) + inner);
+}
+
+
+// A hash as a float from 0.0 (included) to 1.0 (excluded)
+fn noise_unit(hash: u32) -> f32 {
+    return f32(hash >> 8u) / 16777216.0;
+}
+
+
+fn noise_fade(t: vec2<f32>) -> vec2<f32> {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+
+// One of 8 directions, along the axes or the diagonals
+fn noise_gradient(hash: u32) -> vec2<f32> {
+    let signs = vec2<f32>(
+        select(1.0, -1.0, (hash & 1u) != 0u),
+        select(1.0, -1.0, (hash & 2u) != 0u),
+    );
+    let kind = (hash >> 2u) & 3u;
+    if (kind == 1u) {
+        return vec2<f32>(signs.x, 0.0);
+    }
+    if (kind == 2u) {
+        return vec2<f32>(0.0, signs.y);
+    }
+    return signs * 0.70710678;
+}
+
+
+// Smoothly interpolated random values at the lattice points, from 0.0 to 1.0
+fn value_noise(point: vec2<f32>, seed: u32) -> f32 {
+    let cell = floor(point);
+    let corner = vec2<i32>(cell);
+    let t = noise_fade(point - cell);
+    let a = noise_unit(noise_hash2(corner, seed));
+    let b = noise_unit(noise_hash2(corner + vec2<i32>(1, 0), seed));
+    let c = noise_unit(noise_hash2(corner + vec2<i32>(0, 1), seed));
+    let d = noise_unit(noise_hash2(corner + vec2<i32>(1, 1), seed));
+    let bottom = a + (b - a) * t.x;
+    let top = c + (d - c) * t.x;
+    return bottom + (top - bottom) * t.y;
+}
+
+
+// Gradient noise, from about -1.0 to 1.0, and 0.0 at the lattice points
+fn perlin_noise(point: vec2<f32>, seed: u32) -> f32 {
+    let cell = floor(point);
+    let corner = vec2<i32>(cell);
+    let offset = point - cell;
+    let t = noise_fade(offset);
+    let a = dot(noise_gradient(noise_hash2(corner, seed)), offset);
+    let b = dot(
+        noise_gradient(noise_hash2(corner + vec2<i32>(1, 0), seed)),
+        offset - vec2<f32>(1.0, 0.0),
+    );
+    let c = dot(
+        noise_gradient(noise_hash2(corner + vec2<i32>(0, 1), seed)),
+        offset - vec2<f32>(0.0, 1.0),
+    );
+    let d = dot(
+        noise_gradient(noise_hash2(corner + vec2<i32>(1, 1), seed)),
+        offset - vec2<f32>(1.0, 1.0),
+    );
+    let bottom = a + (b - a) * t.x;
+    let top = c + (d - c) * t.x;
+    return (bottom + (top - bottom) * t.y) * 1.41421356;
+}
+
+
+// Fractal Brownian motion: `octaves` layers of Perlin noise (1 to NOISE_MAX_OCTAVES), from about
+// -1.0 to 1.0
+fn fbm(point: vec2<f32>, seed: u32, octaves: u32) -> f32 {
+    let count = clamp(octaves, 1u, NOISE_MAX_OCTAVES);
+    var sum = 0.0;
+    var total = 0.0;
+    var amplitude = 1.0;
+    var frequency = 1.0;
+    for (var octave = 0u; octave < count; octave = octave + 1u) {
+        sum = sum + perlin_noise(point * frequency, seed + octave) * amplitude;
+        total = total + amplitude;
+        amplitude = amplitude * NOISE_GAIN;
+        frequency = frequency * NOISE_LACUNARITY;
+    }
+    return sum / total;
+}
+
+
+// Same as `NoiseField::sample`, for a point in world space
+fn noise_field(field: NoiseField, point: vec2<f32>) -> f32 {
+    return fbm(point * field.frequency, field.seed, field.octaves);
+}
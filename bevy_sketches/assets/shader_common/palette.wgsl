@@ -0,0 +1,10 @@
+// Mirrors `PaletteUniformData` in src/shader_materials/core.rs: linear colors, indexed by the
+// roles below (as in `Palette::colors`, see src/palette.rs)
+struct PaletteUniformData {
+    colors: array<vec4<f32>, 4>;
+};
+
+let PALETTE_BACKGROUND: i32 = 0;
+let PALETTE_FILL: i32 = 1;
+let PALETTE_STROKE: i32 = 2;
+let PALETTE_ACCENT: i32 = 3;
@@ -1,6 +1,8 @@
 /// Demonstrates a shader that receives and uses data in addition to the CommonUniformData
 
 #import "shader_common/common_uniform.wgsl"
+#import "shader_common/noise.wgsl"
+#import "shader_common/palette.wgsl"
 #import "shader_common/shapefuncs.wgsl"
 
 /// LIGHTING_SPEED_KETCHUP compensates for the delay in lights starting up that is caused by 
@@ -16,13 +18,6 @@ let WINDOW_SOFTNESS = 0.015;
 // let WINDOW_SOFTNESS =0.005;
 
 
-let LIGHT_COLOR: vec3<f32> = vec3<f32>(0.005, 0.006, 0.0);
-let LIGHT_COLOR1: vec3<f32> = vec3<f32>(0.01, 0.015, 0.00);
-let LIGHT_COLOR2: vec3<f32> = vec3<f32>(0.04, 0.06, 0.02);
-let LIGHT_COLOR3: vec3<f32> = vec3<f32>(0.08, 0.1, 0.05);
-let LIGHT_COLOR4: vec3<f32> = vec3<f32>(0.9, 1.0, 0.6);
-
-
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
     [[location(0)]] world_position: vec4<f32>;
@@ -34,9 +29,14 @@ struct VertexOutput {
 /// Nesting CommonUnifromData in a CustomUniformData struct
 struct CustomUniformData {
     common: CommonUniformData;
-    background_color: vec3<f32>;
-    alpha: f32;
+    // Linear, with the alpha of the whole quad
+    background_color: vec4<f32>;
     rand_modifier: f32;
+    // Off, then from the brightest light to the dimmest (see `DEFAULT_LIGHTS` in
+    // src/shader_materials/building_lights.rs)
+    lights: PaletteUniformData;
+    // Where more windows are lit, see `busyness`
+    noise: NoiseField;
 };
 
 
@@ -44,27 +44,44 @@ struct CustomUniformData {
 var<uniform> u: CustomUniformData;
 
 
+/// Random value from 0.0 to 1.0 for the seed, different for each rand_modifier. Hashes the bits of
+/// the seed (see shader_common/noise.wgsl) rather than using fract(sin(seed) * k), which repeats
+/// and differs between GPUs.
 fn rand(seed: f32) -> f32 {
-    if (u.rand_modifier < 1.0) {
-        return fract(sin(seed) * 1.0);    
-    }
-    return fract(sin(seed) * u.rand_modifier);    
+    let key = noise_hash(u32(max(u.rand_modifier, 0.0)));
+    return noise_unit(noise_hash(bitcast<u32>(seed) ^ key));
 }
 
 
-/// Returns a random integer (as a floating point value) between min/max
+/// Returns a random integer (as a floating point value) from min to max, both included
 fn rand_int(seed: f32, min: f32, max: f32) -> f32 {
-    var result = fract(sin(seed) * 1.0) * max;
-    if (result < min) {
-        return min;
-    }
-    return round(result);
+    return min + floor(noise_unit(noise_hash(bitcast<u32>(seed))) * (max - min + 1.0));
+}
+
+
+/// How busy the city is at a point in world space, from 0.0 to 1.0. Mirrors `busyness` in
+/// src/shader_materials/building_lights.rs, so sketches can shape buildings to match.
+fn busyness(point: vec2<f32>) -> f32 {
+    return clamp(noise_field(u.noise, point) * 0.5 + 0.5, 0.0, 1.0);
+}
+
+
+/// World position of the middle of the quad's bottom edge (uv (0.5, 1.0)), the same for all of its
+/// fragments
+fn base_position(input: VertexOutput) -> vec2<f32> {
+    let size = vec2<f32>(
+        dpdx(input.world_position.x) / dpdx(input.uv.x),
+        dpdy(input.world_position.y) / dpdy(input.uv.y),
+    );
+    return input.world_position.xy + (vec2<f32>(0.5, 1.0) - input.uv) * size;
 }
 
 
 fn grid_flicker(
-    input: VertexOutput, backgroundColor: vec3<f32>, perRow: f32, perCol: f32
+    input: VertexOutput, backgroundColor: vec3<f32>, perRow: f32, perCol: f32, busy: f32
 ) -> vec3<f32> {
+    // A third of the windows get lit at average busyness, from a sixth to half of them
+    let lit_above = 4.0 - 2.0 * busy;
     var outColor: vec3<f32> = backgroundColor;
     var windowWidth = 1.0 / (perRow * 2.0);
     var windowHeight = 1.0 / (perCol * 2.0);
@@ -79,7 +96,7 @@ fn grid_flicker(
             var colorIndex = 0.0;
 
             // Fave lighting randomization variation (see tut_shader3_building_lights.wgsl for others
-            if (floor(rand(1.0-i) * rand(1.0-j) * (u.common.time + 120.0) % 120.0) % 6.0 > 3.0) {
+            if (floor(rand(1.0-i) * rand(1.0-j) * (u.common.time + 120.0) % 120.0) % 6.0 > lit_above) {
                 colorIndex = floor(
                     rand(1.0-i) * 
                     rand(1.0-j) * 
@@ -90,28 +107,23 @@ fn grid_flicker(
                 colorIndex = 0.0;
             }
 
-            // Passing lightColor as a param to the function throws an error in WASM for some reason, 
-            // so declaring it here for now. It's probably some kind of spec incompatibility.
-            //
-            // let lightColors = array<vec3<f32>, 5>(
-            //     LIGHT_COLOR, LIGHT_COLOR1, LIGHT_COLOR2, LIGHT_COLOR3, LIGHT_COLOR4
-            // );
-            // This arrangement works better, with lights starting black, but then first waves being 
-            // the brightest color (instead of a long time with darker colors first).
-            let lightColors = array<vec3<f32>, 5>(
-                LIGHT_COLOR, LIGHT_COLOR4, LIGHT_COLOR3, LIGHT_COLOR2, LIGHT_COLOR1
-            );
-
+            // Lights start off, then the first waves are the brightest color (instead of a long
+            // time with darker colors first).
             if (colorIndex == 0.0) {
-                outColor = mix(outColor, lightColors[0], window);
+                outColor = mix(outColor, u.lights.colors[PALETTE_BACKGROUND].rgb, window);
             } else if (colorIndex == 1.0) {
-                outColor = mix(outColor, lightColors[1], window);
+                outColor = mix(outColor, u.lights.colors[PALETTE_ACCENT].rgb, window);
             } else if (colorIndex == 2.0) {
-                outColor = mix(outColor, lightColors[2], window);
+                outColor = mix(outColor, u.lights.colors[PALETTE_FILL].rgb, window);
             } else if (colorIndex == 3.0) {
-                outColor = mix(outColor, lightColors[3], window);
+                outColor = mix(outColor, u.lights.colors[PALETTE_STROKE].rgb, window);
             } else {
-                outColor = mix(outColor, lightColors[4], window);
+                let dimmest = mix(
+                    u.lights.colors[PALETTE_BACKGROUND].rgb,
+                    u.lights.colors[PALETTE_STROKE].rgb,
+                    0.25,
+                );
+                outColor = mix(outColor, dimmest, window);
             }
         }
     }
@@ -121,14 +133,16 @@ fn grid_flicker(
 
 [[stage(fragment)]]
 fn fragment(input: VertexOutput) -> [[location(0)]] vec4<f32> {
-    var mixedColor: vec3<f32> = u.background_color;
+    var mixedColor: vec3<f32> = u.background_color.rgb;
 
     // let lights_per_row = rand_int(u.rand_modifier, 1.0, 1.0);
     let lights_per_row = rand_int(u.rand_modifier, 2.0, 5.0);
     let lights_per_col = rand_int(u.rand_modifier, 10.0, 20.0);
-    mixedColor = grid_flicker(input, mixedColor, lights_per_row, lights_per_col);
+    // Once for the whole building, so no window is split between lit and unlit
+    let busy = busyness(base_position(input));
+    mixedColor = grid_flicker(input, mixedColor, lights_per_row, lights_per_col, busy);
 
-    return vec4<f32>(mixedColor, u.alpha);
+    return vec4<f32>(mixedColor, u.background_color.a);
 
 
 }
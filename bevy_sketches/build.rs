@@ -0,0 +1,129 @@
+/*
+ * build
+ *
+ * Lists the preset files in `assets/presets/` for the crate, since the browser can't list a
+ * directory at runtime (see `src/presets.rs`). Writes two tables to `OUT_DIR`:
+ *
+ * - `bundled_presets.rs`, every preset file with its sketch directory, name and contents, as
+ *   `crate::presets::BUNDLED_PRESETS`
+ * - `shifty_variations.rs`, one `ShiftySketch` per shifty preset, named `shifty<preset>`, as
+ *   `crate::shifty_circle::SHIFTY_VARIATIONS`
+ *
+ * The description of a shifty variation is the `//` comment at the top of its preset, if any.
+ * build-sketches gives each variation a page of the same name, loading the `shiftyc` wasm with
+ * `preset=<preset>` (see `variations` in the crate's Cargo.toml).
+ *
+ * Instructed by:
+ * https://doc.rust-lang.org/cargo/reference/build-scripts.html
+ * https://doc.rust-lang.org/cargo/reference/build-script-examples.html#code-generation
+ */
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+
+const PRESETS_DIR: &str = "assets/presets";
+const PRESET_EXTENSION: &str = ".preset.ron";
+/// Presets of this sketch directory are sketches of their own too
+const SHIFTY_DIR: &str = "shifty";
+
+
+struct PresetFile {
+    dir: String,
+    name: String,
+    description: Option<String>,
+    path: PathBuf,
+}
+
+
+/// The `//` comment lines at the top of a preset, joined with spaces
+fn description(source: &str) -> Option<String> {
+    let lines: Vec<&str> = source
+        .lines()
+        .map_while(|line| line.trim().strip_prefix("//"))
+        .map(|line| line.trim())
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    return Some(lines.join(" "));
+}
+
+
+/// All preset files, sorted by directory and name
+fn preset_files(presets_dir: &Path) -> Vec<PresetFile> {
+    let mut files = Vec::new();
+    for dir in fs::read_dir(presets_dir).expect("Unable to read presets dir") {
+        let dir = dir.unwrap().path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let dir_name = dir.file_name().unwrap().to_string_lossy().to_string();
+        for file in fs::read_dir(&dir).expect("Unable to read presets sub-dir") {
+            let path = file.unwrap().path();
+            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+            let name = match file_name.strip_suffix(PRESET_EXTENSION) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let source = fs::read_to_string(&path).expect("Unable to read preset");
+            files.push(PresetFile {
+                dir: dir_name.clone(),
+                name: name,
+                description: description(&source),
+                path: path,
+            });
+        }
+    }
+    files.sort_by(|a, b| (&a.dir, &a.name).cmp(&(&b.dir, &b.name)));
+    return files;
+}
+
+
+fn bundled_presets(files: &[PresetFile]) -> String {
+    let mut out = String::from("&[\n");
+    for file in files {
+        out += &format!(
+            "    BundledPreset {{ dir: {:?}, name: {:?}, source: include_str!({:?}) }},\n",
+            file.dir, file.name, file.path
+        );
+    }
+    out += "]\n";
+    return out;
+}
+
+
+fn shifty_variations(files: &[PresetFile]) -> String {
+    let mut out = String::from("&[\n");
+    for file in files.iter().filter(|file| file.dir == SHIFTY_DIR) {
+        let name = format!("shifty{}", file.name);
+        let description = match &file.description {
+            Some(description) => description.clone(),
+            None => format!("Shifty circles with the {} preset", file.name),
+        };
+        out += &format!(
+            "    ShiftySketch {{ name: {:?}, title: {:?}, description: {:?}, preset: {:?} }},\n",
+            name, name, description, file.name
+        );
+    }
+    out += "]\n";
+    return out;
+}
+
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let presets_dir = manifest_dir.join(PRESETS_DIR);
+    // Rerun when any file in it is added, removed or changed
+    println!("cargo:rerun-if-changed={}", PRESETS_DIR);
+
+    let files = preset_files(&presets_dir);
+    fs::write(out_dir.join("bundled_presets.rs"), bundled_presets(&files))
+        .expect("Unable to write bundled presets");
+    fs::write(
+        out_dir.join("shifty_variations.rs"),
+        shifty_variations(&files),
+    )
+    .expect("Unable to write shifty variations");
+}
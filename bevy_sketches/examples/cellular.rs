@@ -1,5 +1,3 @@
-use bevy_sketches;
-
 fn main() {
-    bevy_sketches::cellular::quadratic::app();
+    bevy_sketches::sketch::run(&bevy_sketches::cellular::quadratic::CellularSketch);
 }
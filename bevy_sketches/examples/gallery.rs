@@ -0,0 +1,3 @@
+fn main() {
+    bevy_sketches::gallery::run(&bevy_sketches::sketch::sketches());
+}
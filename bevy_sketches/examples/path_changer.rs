@@ -1,5 +1,3 @@
-use bevy_sketches;
-
 fn main() {
-    bevy_sketches::path_changer::app();
+    bevy_sketches::sketch::run(&bevy_sketches::path_changer::PathChangerSketch);
 }
@@ -1,7 +1,3 @@
-// Looks like `extern crate` is older syntax and no longer needed
-// extern crate wasm_sketches;
-use bevy_sketches;
-
 fn main() {
-    bevy_sketches::shifty_circle::app("circle");
+    bevy_sketches::sketch::run(&bevy_sketches::shifty_circle::SHIFTY);
 }
@@ -1,5 +0,0 @@
-use bevy_sketches;
-
-fn main() {
-    bevy_sketches::shifty_circle::app("rect");
-}
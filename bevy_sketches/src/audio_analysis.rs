@@ -0,0 +1,576 @@
+/*
+ * audio_analysis
+ *
+ * Makes sketches react to music. A WAV or OGG file from the assets is decoded and analyzed up
+ * front: for every frame of `FRAME_SIZE` samples (every `HOP_SIZE` samples) the energy in each
+ * of `BANDS`, and the beat onsets found in the rise of those energies (spectral flux). The track
+ * then plays, and `AudioLevels` follows it: the levels of the frame being played and whether a
+ * beat just started. Materials get the same in their uniform if they carry `AudioUniformData`
+ * (see `crate::shader_materials::core`).
+ *
+ * The analysis only depends on the samples, so a file always gives the same levels and beats,
+ * and playback is followed on the sketch clock: pausing or speeding up the sketch does the same
+ * to the track. Single steps of a paused sketch leave the track where it is, as a paused sink
+ * can't play a frame's worth of sound.
+ *
+ * Decoding and analyzing a long track takes seconds, so it runs on the `AsyncComputeTaskPool`
+ * and the track starts playing once it's done. On the web, where there are no threads, it still
+ * runs on the main thread, but between frames rather than inside one.
+ *
+ * Add it with `SketchBuilder::audio_reactive` and choose the file with the `audio` runtime option
+ * (`shiftyc.html?audio=music/track.ogg`, a path in `assets/`). Without one the levels stay at
+ * zero, so sketches look as they do without audio. Browsers only start audio after the page has
+ * been interacted with.
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/audio/audio_control.rs
+ * https://www.badlogicgames.com/wordpress/?p=161 (onset detection with spectral flux)
+ * https://en.wikipedia.org/wiki/Hann_function
+ */
+use crate::runtime_opts::{get_opt, AUDIO_OPT};
+use crate::sketch_clock::SketchClock;
+use anyhow::{anyhow, Result};
+use bevy::audio::AudioSink;
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::f32::consts::PI;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+
+/// Samples per analysis frame (about 23ms at 44.1kHz)
+pub const FRAME_SIZE: usize = 1024;
+/// Samples between the starts of two frames
+pub const HOP_SIZE: usize = 512;
+pub const BAND_COUNT: usize = 4;
+/// Frequency range in Hz of each band: bass, low mids, high mids, treble
+pub const BANDS: [(f32, f32); BAND_COUNT] = [
+    (20.0, 250.0),
+    (250.0, 1000.0),
+    (1000.0, 4000.0),
+    (4000.0, 16000.0),
+];
+/// Seconds of spectral flux averaged for the onset threshold
+const ONSET_WINDOW: f32 = 0.5;
+/// How far the flux has to rise above its recent average to be a beat
+const ONSET_SENSITIVITY: f32 = 1.5;
+/// Flux below this (relative to the loudest frame) is never a beat, e.g. noise in silence
+const ONSET_MIN_FLUX: f32 = 0.05;
+/// Shortest time in seconds between two beats
+const BEAT_MIN_INTERVAL: f32 = 0.1;
+/// Seconds for `AudioLevels::beat_pulse` to fade out
+const BEAT_FADE: f32 = 0.25;
+
+
+pub struct AudioAnalysisPlugin;
+
+
+impl Plugin for AudioAnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioLevels>()
+            .add_startup_system(load_audio_track)
+            .add_system(start_audio_track)
+            .add_system(follow_audio_track.after(start_audio_track));
+    }
+}
+
+
+/// The music at the current playback position
+#[derive(Clone, Debug)]
+pub struct AudioLevels {
+    /// Energy of each of `BANDS`, relative to the loudest band of the track (0.0 to 1.0)
+    pub bands: [f32; BAND_COUNT],
+    /// A beat started since the last frame
+    pub beat: bool,
+    /// Seconds since the last beat
+    pub since_beat: f32,
+}
+
+
+impl Default for AudioLevels {
+    fn default() -> Self {
+        Self {
+            bands: [0.0; BAND_COUNT],
+            beat: false,
+            since_beat: f32::INFINITY,
+        }
+    }
+}
+
+
+impl AudioLevels {
+    pub fn bass(&self) -> f32 {
+        self.bands[0]
+    }
+
+    /// 1.0 on a beat, fading linearly to 0.0 over `BEAT_FADE` seconds
+    pub fn beat_pulse(&self) -> f32 {
+        (1.0 - self.since_beat / BEAT_FADE).max(0.0)
+    }
+}
+
+
+/// Band energies and beats of a whole track
+#[derive(Debug)]
+pub struct AudioAnalysis {
+    sample_rate: u32,
+    /// Band energies of each frame, relative to the loudest band of any frame
+    frames: Vec<[f32; BAND_COUNT]>,
+    /// Indexes of the frames where a beat starts
+    beats: Vec<usize>,
+}
+
+
+impl AudioAnalysis {
+    /// Analyze mono samples (-1.0 to 1.0)
+    pub fn new(samples: &[f32], sample_rate: u32) -> Self {
+        let mut frames = band_energies(samples, sample_rate);
+        let loudest = frames
+            .iter()
+            .flat_map(|bands| bands.iter().copied())
+            .fold(0.0, f32::max);
+        if loudest > 0.0 {
+            for bands in frames.iter_mut() {
+                for band in bands.iter_mut() {
+                    *band /= loudest;
+                }
+            }
+        }
+        let frame_secs = HOP_SIZE as f32 / sample_rate as f32;
+        let beats = find_onsets(&frames, frame_secs);
+        return Self {
+            sample_rate: sample_rate,
+            frames: frames,
+            beats: beats,
+        };
+    }
+
+    /// Decode a WAV or OGG (Vorbis) file and analyze it
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (samples, sample_rate) = decode(bytes)?;
+        return Ok(Self::new(&samples, sample_rate));
+    }
+
+    fn frame_secs(&self) -> f64 {
+        HOP_SIZE as f64 / self.sample_rate as f64
+    }
+
+    /// Length of the track in seconds
+    pub fn duration(&self) -> f64 {
+        self.frames.len() as f64 * self.frame_secs()
+    }
+
+    /// Band energies of the frame playing at `secs`, or `None` past the end
+    pub fn bands_at(&self, secs: f64) -> Option<[f32; BAND_COUNT]> {
+        if secs < 0.0 {
+            return None;
+        }
+        let frame = (secs / self.frame_secs()) as usize;
+        return self.frames.get(frame).copied();
+    }
+
+    /// Start times of the beats, in seconds
+    pub fn beat_times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.beats
+            .iter()
+            .map(move |frame| *frame as f64 * self.frame_secs())
+    }
+
+    /// Time of the last beat at or before `secs`
+    pub fn last_beat(&self, secs: f64) -> Option<f64> {
+        self.beat_times().take_while(|beat| *beat <= secs).last()
+    }
+}
+
+
+/// Energy of each band for every frame: the root of the summed squared magnitudes, scaled so a
+/// full scale sine wave gives about 1.0
+fn band_energies(samples: &[f32], sample_rate: u32) -> Vec<[f32; BAND_COUNT]> {
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+    let bin_hz = sample_rate as f32 / FRAME_SIZE as f32;
+    // A windowed sine wave of amplitude 1.0 has (N/4)^2 * 1.5 of energy over its bins
+    let scale = 4.0 / (FRAME_SIZE as f32 * 1.5_f32.sqrt());
+
+    let frame_count = samples.len().saturating_sub(FRAME_SIZE) / HOP_SIZE + 1;
+    let mut buffer = vec![Complex::new(0.0, 0.0); FRAME_SIZE];
+    let mut frames = Vec::with_capacity(frame_count);
+    for frame in 0..frame_count {
+        let start = frame * HOP_SIZE;
+        for (i, value) in buffer.iter_mut().enumerate() {
+            let sample = samples.get(start + i).copied().unwrap_or(0.0);
+            *value = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+
+        let mut bands = [0.0; BAND_COUNT];
+        // Only the first half of the bins, the second half mirrors it
+        for (bin, value) in buffer.iter().enumerate().take(FRAME_SIZE / 2) {
+            let hz = bin as f32 * bin_hz;
+            if let Some(band) = BANDS
+                .iter()
+                .position(|(low, high)| hz >= *low && hz < *high)
+            {
+                bands[band] += value.norm_sqr();
+            }
+        }
+        frames.push(bands.map(|energy| energy.sqrt() * scale));
+    }
+    return frames;
+}
+
+
+/// Frames where the spectral flux (the summed rise of the band energies) peaks above its recent
+/// average
+fn find_onsets(frames: &[[f32; BAND_COUNT]], frame_secs: f32) -> Vec<usize> {
+    let flux: Vec<f32> = frames
+        .iter()
+        .enumerate()
+        .map(|(i, bands)| {
+            let previous = if i > 0 {
+                frames[i - 1]
+            } else {
+                [0.0; BAND_COUNT]
+            };
+            bands
+                .iter()
+                .zip(previous)
+                .map(|(energy, previous)| (energy - previous).max(0.0))
+                .sum()
+        })
+        .collect();
+
+    let window = ((ONSET_WINDOW / frame_secs) as usize).max(1);
+    let min_gap = (BEAT_MIN_INTERVAL / frame_secs).ceil() as usize;
+    let mut beats: Vec<usize> = Vec::new();
+    for (i, value) in flux.iter().enumerate() {
+        let recent = &flux[i.saturating_sub(window)..i];
+        let average = recent.iter().sum::<f32>() / recent.len().max(1) as f32;
+        let is_peak = *value >= flux.get(i + 1).copied().unwrap_or(0.0);
+        let is_onset = *value > ONSET_MIN_FLUX && *value > average * ONSET_SENSITIVITY && is_peak;
+        let too_close = matches!(beats.last(), Some(last) if i - last < min_gap);
+        if is_onset && !too_close {
+            beats.push(i);
+        }
+    }
+    return beats;
+}
+
+
+/// Mono samples (-1.0 to 1.0) and sample rate of a WAV or OGG (Vorbis) file
+pub fn decode(bytes: &[u8]) -> Result<(Vec<f32>, u32)> {
+    if bytes.starts_with(b"RIFF") {
+        return decode_wav(bytes);
+    }
+    if bytes.starts_with(b"OggS") {
+        return decode_ogg(bytes);
+    }
+    Err(anyhow!("not a WAV or OGG file"))
+}
+
+
+fn decode_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32)> {
+    let mut reader = hound::WavReader::new(Cursor::new(bytes))?;
+    let spec = reader.spec();
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let full_scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / full_scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+    return Ok((to_mono(&interleaved, spec.channels), spec.sample_rate));
+}
+
+
+fn decode_ogg(bytes: &[u8]) -> Result<(Vec<f32>, u32)> {
+    let mut reader = lewton::inside_ogg::OggStreamReader::new(Cursor::new(bytes))?;
+    let channels = reader.ident_hdr.audio_channels as u16;
+    let mut interleaved = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl()? {
+        interleaved.extend(packet.iter().map(|sample| *sample as f32 / 32768.0));
+    }
+    return Ok((
+        to_mono(&interleaved, channels),
+        reader.ident_hdr.audio_sample_rate,
+    ));
+}
+
+
+fn to_mono(interleaved: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+
+/// Where the analysis task leaves its result. A shared slot rather than the task's output, as
+/// tasks spawned on the web can't be polled.
+type PendingAnalysis = Arc<Mutex<Option<Result<AudioAnalysis>>>>;
+
+
+/// The track chosen with the `audio` runtime option, analyzed and playing once loaded
+struct AudioTrack {
+    source: Handle<AudioSource>,
+    /// Set while the track is being analyzed
+    pending: Option<PendingAnalysis>,
+    analysis: Option<AudioAnalysis>,
+    sink: Handle<AudioSink>,
+    /// Sketch time when playback started
+    started: f64,
+    /// Playback position at the last update, for catching the beats in between
+    position: f64,
+}
+
+
+fn load_audio_track(mut commands: Commands, asset_server: Res<AssetServer>) {
+    if let Some(path) = get_opt(AUDIO_OPT) {
+        info!("AUDIO: loading {}", path);
+        commands.insert_resource(AudioTrack {
+            source: asset_server.load(&path),
+            pending: None,
+            analysis: None,
+            sink: Handle::default(),
+            started: 0.0,
+            position: 0.0,
+        });
+    }
+}
+
+
+fn start_audio_track(
+    track: Option<ResMut<AudioTrack>>,
+    sources: Res<Assets<AudioSource>>,
+    sinks: Res<Assets<AudioSink>>,
+    audio: Res<Audio>,
+    clock: Res<SketchClock>,
+    task_pool: Res<AsyncComputeTaskPool>,
+) {
+    let mut track = match track {
+        Some(track) if track.analysis.is_none() => track,
+        _ => return,
+    };
+    let pending = match &track.pending {
+        Some(pending) => pending.clone(),
+        None => {
+            if let Some(source) = sources.get(&track.source) {
+                let bytes = source.bytes.clone();
+                let pending = PendingAnalysis::default();
+                let result = pending.clone();
+                task_pool
+                    .spawn(async move {
+                        let analysis = AudioAnalysis::from_bytes(&bytes);
+                        if let Ok(mut result) = result.lock() {
+                            *result = Some(analysis);
+                        }
+                    })
+                    .detach();
+                track.pending = Some(pending);
+            }
+            return;
+        }
+    };
+    let result = match pending.lock().ok().and_then(|mut result| result.take()) {
+        Some(result) => result,
+        None => return,
+    };
+    track.pending = None;
+    match result {
+        Ok(analysis) => {
+            info!(
+                "AUDIO: {:.1}s, {} beats",
+                analysis.duration(),
+                analysis.beats.len()
+            );
+            track.analysis = Some(analysis);
+        }
+        Err(err) => {
+            error!("AUDIO: can't analyze the track: {}", err);
+            // Keep the levels at zero rather than trying again every frame
+            track.analysis = Some(AudioAnalysis::new(&[], 1));
+            return;
+        }
+    }
+    track.sink = sinks.get_handle(audio.play(track.source.clone()));
+    track.started = clock.seconds_since_startup();
+}
+
+
+/// Set `AudioLevels` from the playback position, and keep playback in step with the sketch clock
+fn follow_audio_track(
+    track: Option<ResMut<AudioTrack>>,
+    sinks: Res<Assets<AudioSink>>,
+    clock: Res<SketchClock>,
+    mut levels: ResMut<AudioLevels>,
+) {
+    let mut track = match track {
+        Some(track) => track,
+        None => return,
+    };
+    // Steps while paused don't move the paused sink, so they don't move the track either
+    if clock.is_paused() {
+        track.started += clock.delta_seconds();
+    }
+    let previous = track.position;
+    track.position = clock.seconds_since_startup() - track.started;
+    let analysis = match &track.analysis {
+        Some(analysis) => analysis,
+        None => return,
+    };
+
+    if let Some(sink) = sinks.get(&track.sink) {
+        if clock.is_paused() != sink.is_paused() {
+            if clock.is_paused() {
+                sink.pause();
+            } else {
+                sink.play();
+            }
+        }
+        if (sink.speed() as f64 - clock.speed()).abs() > f64::EPSILON {
+            sink.set_speed(clock.speed() as f32);
+        }
+    }
+
+    levels.bands = analysis
+        .bands_at(track.position)
+        .unwrap_or([0.0; BAND_COUNT]);
+    let last_beat = analysis.last_beat(track.position);
+    levels.beat = matches!(last_beat, Some(beat) if beat > previous);
+    levels.since_beat = last_beat.map_or(f32::INFINITY, |beat| (track.position - beat) as f32);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// A 16-bit WAV file of `secs` seconds, `signal` giving the sample at each time
+    fn wav_file(secs: f32, signal: impl Fn(f32) -> f32) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        for i in 0..(secs * SAMPLE_RATE as f32) as usize {
+            let sample = signal(i as f32 / SAMPLE_RATE as f32);
+            writer
+                .write_sample((sample * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+        return bytes.into_inner();
+    }
+
+    fn sine(hz: f32, secs: f32) -> f32 {
+        (2.0 * PI * hz * secs).sin()
+    }
+
+    #[test]
+    fn sine_lands_in_its_band() {
+        for (hz, band) in [(100.0, 0), (500.0, 1), (2000.0, 2), (8000.0, 3)] {
+            let analysis =
+                AudioAnalysis::from_bytes(&wav_file(1.0, |secs| 0.8 * sine(hz, secs))).unwrap();
+            let bands = analysis.bands_at(0.5).unwrap();
+            assert!(bands[band] > 0.99, "{}Hz: {:?}", hz, bands);
+            for (other, energy) in bands.iter().enumerate() {
+                if other != band {
+                    assert!(*energy < 0.05, "{}Hz leaks into {}: {:?}", hz, other, bands);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn beats_follow_bursts() {
+        // A 0.1s bass burst every 0.5s
+        let file = wav_file(3.0, |secs| {
+            if secs % 0.5 < 0.1 {
+                sine(80.0, secs)
+            } else {
+                0.0
+            }
+        });
+        let analysis = AudioAnalysis::from_bytes(&file).unwrap();
+        let beats: Vec<f64> = analysis.beat_times().collect();
+        assert_eq!(beats.len(), 6, "{:?}", beats);
+        for (i, beat) in beats.iter().enumerate() {
+            // Within a frame of the burst's start
+            let burst = i as f64 * 0.5;
+            let frame = FRAME_SIZE as f64 / SAMPLE_RATE as f64;
+            assert!((beat - burst).abs() <= frame, "beat {} at {}", i, beat);
+        }
+
+        // The same file gives the same analysis
+        let again = AudioAnalysis::from_bytes(&file).unwrap();
+        assert_eq!(again.frames, analysis.frames);
+        assert_eq!(again.beats, analysis.beats);
+    }
+
+    #[test]
+    fn analyzes_in_the_background_and_ignores_steps() {
+        use crate::headless::HeadlessSketch;
+        use std::time::{Duration, Instant};
+
+        let mut sketch = HeadlessSketch::new(0)
+            .asset::<AudioSource>()
+            .asset::<AudioSink>()
+            .resource(Audio::<AudioSource>::default())
+            .with_sketch(|sketch| sketch.audio_reactive());
+        let source = sketch
+            .world()
+            .resource_mut::<Assets<AudioSource>>()
+            .add(AudioSource {
+                bytes: wav_file(2.0, |secs| sine(100.0, secs)).into(),
+            });
+        sketch.world().insert_resource(AudioTrack {
+            source: source,
+            pending: None,
+            analysis: None,
+            sink: Handle::default(),
+            started: 0.0,
+            position: 0.0,
+        });
+
+        // The frame that starts the analysis doesn't wait for it
+        sketch.update(0.1);
+        assert!(sketch.read_resource::<AudioTrack>().pending.is_some());
+        let timeout = Instant::now() + Duration::from_secs(10);
+        while sketch.read_resource::<AudioTrack>().analysis.is_none() {
+            assert!(Instant::now() < timeout, "analysis never finished");
+            std::thread::sleep(Duration::from_millis(5));
+            sketch.update(0.1);
+        }
+        assert!(sketch.read_resource::<AudioLevels>().bass() > 0.9);
+
+        let position = sketch.read_resource::<AudioTrack>().position;
+        sketch.world().resource_mut::<SketchClock>().step();
+        sketch.update(0.1);
+        assert_eq!(sketch.read_resource::<AudioTrack>().position, position);
+
+        sketch
+            .world()
+            .resource_mut::<SketchClock>()
+            .set_paused(false);
+        sketch.update(0.1);
+        let played = sketch.read_resource::<AudioTrack>().position - position;
+        assert!((played - 0.1).abs() < 1e-9, "played {}", played);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(decode(b"not audio").is_err());
+    }
+}
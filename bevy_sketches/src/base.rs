@@ -1,32 +1,483 @@
-#[cfg(feature = "framestats")]
+use crate::audio_analysis::AudioAnalysisPlugin;
+use crate::gallery::{is_shown, while_shown, GallerySketch, PersistentEntity};
+use crate::morph::MorphPlugin;
+use crate::params::{on_params_changed, ParamsPlugin};
+use crate::perf_overlay::PerfOverlayPlugin;
+use crate::pointer::PointerPlugin;
+use crate::presets::{Presets, PresetsPlugin};
+use crate::rng::SketchRng;
+use crate::runtime_opts::{get_flag, get_opt, LOG_FILTER_OPT, LOG_OPT, STATS_OPT};
+use crate::shader_materials::core::{BaseShaderTrait, ShaderMaterialPlugin};
+use crate::shader_materials::layout::{spawn_display_camera, QuadLayoutPlugin};
+use crate::sketch_clock::{
+    add_fixed_system, clock_step, ClockStepState, SketchClock, SketchClockPlugin,
+};
+use crate::svg_export::SvgExportPlugin;
+use crate::svg_import::SvgImportPlugin;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
-#[cfg(feature = "debuglog")]
+use bevy::ecs::schedule::ShouldRun;
 use bevy::log::LogSettings;
 use bevy::prelude::*;
-#[cfg(feature = "debuglog")]
+use bevy::reflect::GetTypeRegistration;
 use bevy::utils::tracing::Level;
+use bevy_prototype_lyon::prelude::*;
 use bevy_web_extras::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::any::TypeId;
+use std::collections::HashSet;
 
 
-/// Build on web_app to provide some additional logging stuff
-pub fn sketch(webcfg: WebExtrasCfg) -> App {
-    let mut app = web_app(webcfg);
+const DEFAULT_MSAA_SAMPLES: u32 = 4;
+const DEFAULT_LOG_FILTER: &str = "wgpu=error,bevy_render=error";
 
-    #[cfg(feature = "debuglog")]
-    app.insert_resource(LogSettings {
-        level: Level::DEBUG,
-        filter: "wgpu=error,bevy_render=error".to_string(),
-    });
-    app.insert_resource(Msaa { samples: 4 })
+
+type AppStep = Box<dyn FnOnce(&mut App)>;
+
+
+/// Fluent setup for a sketch app, wrapping `web_app` and the wiring every sketch repeats
+/// (clear color, MSAA, diagnostics, lyon, shader materials, fixed-step and resize systems).
+///
+/// Log level, log filter and diagnostics can also be chosen at runtime (`?log=debug&stats=1`
+/// on the web), see `crate::runtime_opts`. Every sketch also gets the performance overlay (see
+/// `crate::perf_overlay`) unless it is turned off with `perf_overlay(false)`.
+///
+/// The same builder sets up several sketches in the gallery (see `crate::gallery`), where
+/// `scope` makes the systems of each sketch run only while it is shown.
+///
+/// ```ignore
+/// SketchBuilder::new("cellular")
+///     .clear_color(CELL_CLEAR_CLR)
+///     .lyon()
+///     .startup_system(cell_setup)
+///     .fixed_system(mutate_cell, CELL_STEP)
+///     .run();
+/// ```
+pub struct SketchBuilder {
+    webcfg: WebExtrasCfg,
+    clear_color: Option<Color>,
+    msaa_samples: u32,
+    diagnostics: bool,
+    log_level: Level,
+    perf_overlay: bool,
+    lyon: bool,
+    /// Name of the gallery sketch the next steps belong to
+    scope: Option<&'static str>,
+    /// Plugins added by type (materials, params, presets), so sketches can share them
+    plugins: HashSet<TypeId>,
+    // Steps are deferred until `build()` because things like material plugins need to be added
+    // after `DefaultPlugins`.
+    steps: Vec<AppStep>,
+}
+
+
+impl SketchBuilder {
+    pub fn new(title: &str) -> Self {
+        Self::from_webcfg(WebExtrasCfg {
+            title: String::from(title),
+            match_element: Some(String::from("content")),
+            match_clear_color: true,
+            ..Default::default()
+        })
+    }
+
+    pub fn from_webcfg(webcfg: WebExtrasCfg) -> Self {
+        Self {
+            webcfg: webcfg,
+            clear_color: None,
+            msaa_samples: DEFAULT_MSAA_SAMPLES,
+            // Example of "feature-flipping".
+            // See https://doc.rust-lang.org/cargo/reference/features.html
+            diagnostics: cfg!(feature = "framestats"),
+            log_level: if cfg!(feature = "debuglog") {
+                Level::DEBUG
+            } else {
+                Level::INFO
+            },
+            perf_overlay: true,
+            lyon: false,
+            scope: None,
+            plugins: HashSet::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// The web config the app will be built with (e.g. to read `max_x` before building)
+    pub fn webcfg(&self) -> &WebExtrasCfg {
+        &self.webcfg
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.webcfg.title = String::from(title);
+        self
+    }
+
+    pub fn clear_color(mut self, color: Color) -> Self {
+        if let Some(scope) = self.scope {
+            return self.with_app(move |app| {
+                app.add_system_set(
+                    SystemSet::on_enter(GallerySketch(scope)).with_system(
+                        move |mut clear_color: ResMut<ClearColor>| clear_color.0 = color,
+                    ),
+                );
+            });
+        }
+        self.clear_color = Some(color);
+        self
+    }
+
+    /// Clear color taken from the parameter resource `T` (e.g. the background of a generated
+    /// palette, see `crate::palette`), kept up to date as the params are edited
+    pub fn clear_color_from<T: Send + Sync + 'static>(self, color: fn(&T) -> Color) -> Self {
+        let scope = self.scope;
+        self.with_app(move |app| {
+            app.init_resource::<ClearColor>().add_system(
+                move |shown: Option<Res<State<GallerySketch>>>,
+                      params: Res<T>,
+                      mut clear_color: ResMut<ClearColor>| {
+                    // The clear color also changes when the gallery switches sketches
+                    let stale = params.is_changed() || clear_color.is_changed();
+                    if !stale || !is_shown(scope, shown.as_deref()) {
+                        return;
+                    }
+                    let color = color(&params);
+                    if clear_color.0 != color {
+                        clear_color.0 = color;
+                    }
+                },
+            );
+        })
+    }
+
+    pub fn msaa(mut self, samples: u32) -> Self {
+        self.msaa_samples = samples;
+        self
+    }
+
+    /// Log frame time diagnostics (defaults to on with the `framestats` feature).
+    /// Overridden at runtime by the `stats` option.
+    pub fn diagnostics(mut self, enabled: bool) -> Self {
+        self.diagnostics = enabled;
+        self
+    }
+
+    /// Log level (defaults to DEBUG with the `debuglog` feature, INFO otherwise).
+    /// Overridden at runtime by the `log` option.
+    pub fn log_level(mut self, level: Level) -> Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Make the performance overlay available (on by default)
+    pub fn perf_overlay(mut self, enabled: bool) -> Self {
+        self.perf_overlay = enabled;
+        self
+    }
+
+    /// Add bevy_prototype_lyon's `ShapePlugin` (and SVG import and export of the shapes, and
+    /// morphing between them, see `crate::morph`)
+    pub fn lyon(mut self) -> Self {
+        self.lyon = true;
+        self
+    }
+
+    /// Add the steps that follow to the gallery sketch `scope` (see `crate::gallery`), or to the
+    /// whole app with `None`
+    pub fn scope(mut self, scope: Option<&'static str>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Add a parameter resource that can be tweaked at runtime (see `crate::params`)
+    pub fn params<T: Reflect + GetTypeRegistration + Default>(self) -> Self {
+        self.plugin_once::<ParamsPlugin<T>>(|app| {
+            app.add_plugin(ParamsPlugin::<T>::default());
+        })
+    }
+
+    /// Add presets for the parameter resource `T`, named after the files in
+    /// `assets/presets/<dir>/` (see `crate::presets`)
+    pub fn presets<T: Serialize + DeserializeOwned + Default + Send + Sync + 'static>(
+        self,
+        dir: &'static str,
+    ) -> Self {
+        self.plugin_once::<PresetsPlugin<T>>(move |app| {
+            app.add_plugin(PresetsPlugin::<T>::new(dir));
+        })
+    }
+
+    /// The preset of `T` to start with, unless chosen with the `preset` runtime option (in the
+    /// gallery, the preset to switch to whenever the sketch is shown). Call after `presets`.
+    pub fn preset<T: Send + Sync + 'static>(self, name: &'static str) -> Self {
+        match self.scope {
+            Some(scope) => self.with_app(move |app| {
+                app.add_system_set(SystemSet::on_enter(GallerySketch(scope)).with_system(
+                    move |mut presets: ResMut<Presets<T>>| {
+                        presets.select(name);
+                    },
+                ));
+            }),
+            None => self.with_app(move |app| {
+                app.world.resource_mut::<Presets<T>>().set_initial(name);
+            }),
+        }
+    }
+
+    /// Add a system that runs whenever the parameter resource `T` is edited, e.g. to redraw
+    /// things that only read the params when spawned
+    pub fn on_params_change<T: Reflect, Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        let scope = self.scope;
+        self.with_app(move |app| {
+            app.add_system(system.with_run_criteria(
+                move |shown: Option<Res<State<GallerySketch>>>, params: Res<T>| {
+                    if !is_shown(scope, shown.as_deref()) {
+                        return ShouldRun::No;
+                    }
+                    on_params_changed(params)
+                },
+            ));
+        })
+    }
+
+    /// Add a `ShaderMaterialPlugin` for the material
+    pub fn material<T: BaseShaderTrait>(self) -> Self {
+        self.plugin_once::<ShaderMaterialPlugin<T>>(|app| {
+            app.add_plugin(ShaderMaterialPlugin::<T>::default());
+        })
+    }
+
+    /// Levels and beats of the music chosen with the `audio` runtime option (see
+    /// `crate::audio_analysis`)
+    pub fn audio_reactive(self) -> Self {
+        self.plugin_once::<AudioAnalysisPlugin>(|app| {
+            app.add_plugin(AudioAnalysisPlugin);
+        })
+    }
+
+    /// A 2d camera and the `DisplayQuad`s spawned once by `setup`, each with a `QuadLayout` that
+    /// keeps it fitted to the canvas (see `crate::shader_materials::layout`)
+    pub fn display_quads<Params: 'static>(
+        self,
+        setup: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        self.plugin_once::<QuadLayoutPlugin>(|app| {
+            app.add_plugin(QuadLayoutPlugin);
+        })
+        .startup_system(spawn_display_camera)
+        .startup_system(setup)
+    }
+
+    pub fn resource<R: Send + Sync + 'static>(self, resource: R) -> Self {
+        self.with_app(move |app| {
+            app.insert_resource(resource);
+        })
+    }
+
+    pub fn startup_system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        let scope = self.scope;
+        self.with_app(move |app| match scope {
+            Some(scope) => {
+                app.add_system_set(SystemSet::on_enter(GallerySketch(scope)).with_system(system));
+            }
+            None => {
+                app.add_startup_system(system);
+            }
+        })
+    }
+
+    pub fn system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        let scope = self.scope;
+        self.with_app(move |app| match scope {
+            Some(_) => {
+                app.add_system(system.with_run_criteria(while_shown(scope)));
+            }
+            None => {
+                app.add_system(system);
+            }
+        })
+    }
+
+    /// Add a system that runs every `step` seconds of sketch time, so it stops while the sketch
+    /// is paused and follows its speed (see `crate::sketch_clock`).
+    ///
+    /// In the gallery the steps start over whenever the sketch is shown, counting from the frame
+    /// after, once the entities of its startup systems have been spawned.
+    pub fn fixed_system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+        step: f64,
+    ) -> Self {
+        let scope = self.scope;
+        self.with_app(move |app| match scope {
+            Some(_) => {
+                app.add_system(system.with_run_criteria(
+                    move |shown: Option<Res<State<GallerySketch>>>,
+                          clock: Res<SketchClock>,
+                          mut state: Local<ClockStepState>,
+                          mut was_shown: Local<bool>| {
+                        let is_shown = is_shown(scope, shown.as_deref());
+                        let just_shown = is_shown && !*was_shown;
+                        *was_shown = is_shown;
+                        if !is_shown || just_shown {
+                            *state = ClockStepState::default();
+                            return ShouldRun::No;
+                        }
+                        clock_step(step, &clock, &mut state)
+                    },
+                ));
+            }
+            None => add_fixed_system(app, system, step),
+        })
+    }
+
+    /// Add a system that (re)draws things sized to the canvas.
+    ///
+    /// On wasm32 it runs whenever the browser is resized (which includes the first sizing of the
+    /// canvas). Otherwise it runs once at startup. In the gallery it also runs whenever the
+    /// sketch is shown.
+    pub fn resize_system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        if let Some(scope) = self.scope {
+            return self.with_app(move |app| {
+                #[cfg(not(target_arch = "wasm32"))]
+                app.add_system_set(SystemSet::on_enter(GallerySketch(scope)).with_system(system));
+
+                // Resizing also happens while other sketches are shown, so the size is set on
+                // entering too
+                #[cfg(target_arch = "wasm32")]
+                {
+                    let mut entered = false;
+                    app.add_system(system.with_run_criteria(
+                        move |resized: EventReader<BrowserResized>,
+                              shown: Res<State<GallerySketch>>| {
+                            let was_entered = entered;
+                            entered = is_shown(Some(scope), Some(&shown));
+                            if !entered {
+                                return ShouldRun::No;
+                            }
+                            if !was_entered {
+                                return ShouldRun::Yes;
+                            }
+                            on_browser_resized(resized)
+                        },
+                    ));
+                }
+            });
+        }
+        self.with_app(move |app| {
+            #[cfg(not(target_arch = "wasm32"))]
+            app.add_startup_system(system);
+
+            #[cfg(target_arch = "wasm32")]
+            app.add_system(system.with_run_criteria(on_browser_resized));
+        })
+    }
+
+    /// Escape hatch for anything the builder doesn't cover
+    pub fn with_app(mut self, step: impl FnOnce(&mut App) + 'static) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub fn build(self) -> App {
+        let log_level = get_opt(LOG_OPT)
+            .and_then(|level| level.parse::<Level>().ok())
+            .unwrap_or(self.log_level);
+        let log_filter = get_opt(LOG_FILTER_OPT).unwrap_or_else(|| DEFAULT_LOG_FILTER.to_string());
+        let diagnostics = get_flag(STATS_OPT).unwrap_or(self.diagnostics);
+
+        let mut app = web_app(self.webcfg);
+
+        app.insert_resource(LogSettings {
+            level: log_level,
+            filter: log_filter,
+        });
+        app.insert_resource(Msaa {
+            samples: self.msaa_samples,
+        })
         .add_plugins(DefaultPlugins);
-    debug!("SKETCH: debug log level enabled");
-    info!("SKETCH: info log level enabled");
+        debug!("SKETCH: debug log level enabled");
+        info!("SKETCH: info log level enabled");
+
+        let rng = SketchRng::from_runtime_opts();
+        info!(
+            "SKETCH: seed {} (rerun with the `seed` option to recreate)",
+            rng.seed()
+        );
+        app.insert_resource(rng);
+        app.add_plugin(SketchClockPlugin);
+        app.add_plugin(PointerPlugin);
+
+        // Shared by the overlays (performance overlay, params editor)
+        app.add_startup_system(spawn_ui_camera);
+        if self.perf_overlay {
+            app.add_plugin(PerfOverlayPlugin);
+        }
+        if diagnostics {
+            app.add_plugin(LogDiagnosticsPlugin::default());
+            // The overlay already adds this one
+            if !self.perf_overlay {
+                app.add_plugin(FrameTimeDiagnosticsPlugin::default());
+            }
+        }
+        if let Some(color) = self.clear_color {
+            app.insert_resource(ClearColor(color));
+        }
+        if self.lyon {
+            app.add_plugin(ShapePlugin)
+                .add_plugin(SvgImportPlugin)
+                .add_plugin(SvgExportPlugin)
+                .add_plugin(MorphPlugin);
+        }
+        for step in self.steps {
+            step(&mut app);
+        }
+        return app;
+    }
+
+    /// Only the sketch's own steps, without the default plugins (see `crate::headless`)
+    pub(crate) fn add_steps(self, app: &mut App) {
+        for step in self.steps {
+            step(app);
+        }
+    }
+
+    /// Add a plugin through `step` unless a plugin of type `P` was added already
+    fn plugin_once<P: 'static>(mut self, step: impl FnOnce(&mut App) + 'static) -> Self {
+        if !self.plugins.insert(TypeId::of::<P>()) {
+            return self;
+        }
+        self.with_app(step)
+    }
+
+    pub fn run(self) {
+        self.build().run();
+    }
+}
+
+
+fn spawn_ui_camera(mut commands: Commands) {
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(PersistentEntity);
+}
 
-    // Example of "feature-flipping".
-    // See https://doc.rust-lang.org/cargo/reference/features.html
-    #[cfg(feature = "framestats")]
-    app.add_plugin(LogDiagnosticsPlugin::default())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default());
 
-    return app;
+#[cfg(target_arch = "wasm32")]
+fn on_browser_resized(mut resize_event_reader: EventReader<BrowserResized>) -> ShouldRun {
+    // Drain all pending events so a burst of resizes only triggers one run
+    if resize_event_reader.iter().last().is_some() {
+        return ShouldRun::Yes;
+    }
+    ShouldRun::No
 }
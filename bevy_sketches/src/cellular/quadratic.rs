@@ -1,49 +1,159 @@
-use crate::base::sketch;
-use bevy::core::FixedTimestep;
+use crate::base::SketchBuilder;
+use crate::geometry::{flatten, offset, polygons_builder, Bounds};
+use crate::palette::{mix_oklab, with_alpha, PaletteKind};
+use crate::placement::Placer;
+use crate::rng::SketchRng;
+use crate::sketch::{Sketch, SketchParams};
+use crate::sketch_clock::SketchClock;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 use bevy_web_extras::prelude::*;
-use rand::prelude::thread_rng;
-use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 /*
  * cellular
  *
+ * The inner cell is the outer one inset by `inner_inset` (see `crate::geometry`), so it keeps
+ * the same distance to the outline however irregular the cell gets. The cells start spread out
+ * over the canvas (see `crate::placement`), as many as fit up to `CELL_COUNT`, far enough apart
+ * that they don't overlap even at their largest. They may reach past the edges of the canvas.
+ * Their colors are hand-picked or come from a generated palette (see `crate::palette`).
+ *
  * Instructed by:
  * https://github.com/Nilirad/bevy_prototype_lyon/blob/master/src/path.rs
  */
 
 pub const CELL_CLEAR_CLR: Color = Color::rgb(0.58, 0.71, 0.87);
-const CELL_FILL_CLR: Color = Color::rgba(0.95, 0.85, 0.62, 0.1);
-const CELL_STROKE_CLR: Color = Color::rgba(0.95, 0.91, 0.81, 0.08);
-const CELL_STROKE: f32 = 5.0;
-const CELL_INNER_FILL_CLR: Color = Color::rgba(1.0, 0.79, 0.69, 0.2);
-const CELL_INNER_STROKE_CLR: Color = Color::rgba(0.41, 0.1, 0.03, 0.1);
-const CELL_INNER_STROKE: f32 = 2.0;
-const CELL_INNER_SIZE: f32 = 0.9;
-const CELL_CTRL_MIN: f32 = 100.0;
-const CELL_CTRL_MAX: f32 = 250.0;
-/// Radius to curve intersection
-/// Setting the CELL_MIN_RADIUS closer to CELL_CTRL_MIN lessens the valleys in the shape
-const CELL_MIN_RADIUS: f32 = 100.0;
-/// It seems that keeping radius size between 100-125% of **the smaller** of ctrl.x or
-/// ctrl.y keeps the shape from getting too sharp, at least on the concave "surfaces".
-const CELL_MAX_RADIUS_MODIFIER: f32 = 1.10;
-/// Less tight causes more convex / peaks (used only if max_radius_looser)
-const CELL_MAX_RADIUS_TIGHTNESS: f32 = 1.5;
 const CELL_SEG_RT: usize = 0;
 const CELL_SEG_RB: usize = 1;
 const CELL_SEG_LB: usize = 2;
 const CELL_SEG_LT: usize = 3;
-/// Although these speed values are used for both radius and ctrl speeds, the ctrl max speed is
-/// nerfed in the `mutate_cell` system
-const CELL_MIN_SPEED: f32 = 1.0;
-const CELL_MAX_SPEED: f32 = 4.0;
 pub const CELL_STEP: f64 = 0.3;
+const CELL_COUNT: usize = 3;
+/// Largest distance between the cell's curves and the lines they are flattened into for the inset
+const CELL_FLATTEN_TOLERANCE: f32 = 0.25;
+/// Speeds are in units per frame at this frame rate of sketch time (see `crate::sketch_clock`)
+const CELL_SPEED_FPS: f32 = 60.0;
 // pub const CELL_STEP: f64 = 1.0;
 
 
-#[derive(Component)]
+/// Look of the sketch, editable at runtime (see `crate::params`) and kept as presets in
+/// `assets/presets/cellular/` (see `crate::presets`)
+#[derive(Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CellParams {
+    /// `Custom` for the colors below, or a palette generated from `palette_seed`, which keeps only
+    /// the alphas of the colors below
+    pub palette: PaletteKind,
+    pub palette_seed: u32,
+    pub fill_color: Color,
+    pub stroke_color: Color,
+    pub stroke: f32,
+    pub inner_fill_color: Color,
+    pub inner_stroke_color: Color,
+    pub inner_stroke: f32,
+    /// Distance from the outline of the cell to the inner cell
+    pub inner_inset: f32,
+    pub ctrl_min: f32,
+    pub ctrl_max: f32,
+    /// Radius to curve intersection
+    /// Setting the min_radius closer to ctrl_min lessens the valleys in the shape
+    pub min_radius: f32,
+    /// It seems that keeping radius size between 100-125% of **the smaller** of ctrl.x or
+    /// ctrl.y keeps the shape from getting too sharp, at least on the concave "surfaces".
+    pub max_radius_modifier: f32,
+    /// Let the max radius grow with both ctrl.x and ctrl.y instead of the smaller of them
+    pub max_radius_looser: bool,
+    /// Less tight causes more convex / peaks (used only if max_radius_looser)
+    pub max_radius_tightness: f32,
+    /// Although these speed values are used for both radius and ctrl speeds, the ctrl max speed
+    /// is nerfed in the `mutate_cell` system. In units per 1/60th of a second of sketch time.
+    pub min_speed: f32,
+    pub max_speed: f32,
+}
+
+
+impl Default for CellParams {
+    fn default() -> Self {
+        Self {
+            palette: PaletteKind::Custom,
+            palette_seed: 0,
+            fill_color: Color::rgba(0.95, 0.85, 0.62, 0.1),
+            stroke_color: Color::rgba(0.95, 0.91, 0.81, 0.08),
+            stroke: 5.0,
+            inner_fill_color: Color::rgba(1.0, 0.79, 0.69, 0.2),
+            inner_stroke_color: Color::rgba(0.41, 0.1, 0.03, 0.1),
+            inner_stroke: 2.0,
+            inner_inset: 15.0,
+            ctrl_min: 100.0,
+            ctrl_max: 250.0,
+            min_radius: 100.0,
+            max_radius_modifier: 1.10,
+            max_radius_looser: true,
+            max_radius_tightness: 1.5,
+            min_speed: 1.0,
+            max_speed: 4.0,
+        }
+    }
+}
+
+
+/// Colors of the sketch, see `CellParams::palette`
+struct CellColors {
+    clear: Color,
+    fill: Color,
+    stroke: Color,
+    inner_fill: Color,
+    inner_stroke: Color,
+}
+
+
+impl CellParams {
+    /// Largest radius a segment can grow to (see `CellSegment::get_max_radius`), with its control
+    /// point at `ctrl_max`
+    fn max_radius(&self) -> f32 {
+        let max_radius = if self.max_radius_looser {
+            self.ctrl_max * 2.0 / self.max_radius_tightness
+        } else {
+            self.ctrl_max * self.max_radius_modifier
+        };
+        return max_radius.max(self.min_radius + 1.0);
+    }
+
+    /// Farthest the outline of a cell gets from its center. A quadratic curve gets at most
+    /// halfway from the distance of its ends to that of its control point, so control points
+    /// further out than `max_radius` add half the difference.
+    fn max_reach(&self) -> f32 {
+        let max_radius = self.max_radius();
+        let ctrl_distance = self.ctrl_max * std::f32::consts::SQRT_2;
+        return max_radius.max((max_radius + ctrl_distance) / 2.0);
+    }
+
+    fn colors(&self) -> CellColors {
+        match self.palette.generate(self.palette_seed) {
+            Some(palette) => CellColors {
+                clear: palette.background,
+                fill: with_alpha(palette.fill, self.fill_color.a()),
+                stroke: with_alpha(palette.stroke, self.stroke_color.a()),
+                inner_fill: with_alpha(palette.accent, self.inner_fill_color.a()),
+                inner_stroke: with_alpha(
+                    mix_oklab(palette.accent, palette.background, 0.5),
+                    self.inner_stroke_color.a(),
+                ),
+            },
+            None => CellColors {
+                clear: CELL_CLEAR_CLR,
+                fill: self.fill_color,
+                stroke: self.stroke_color,
+                inner_fill: self.inner_fill_color,
+                inner_stroke: self.inner_stroke_color,
+            },
+        }
+    }
+}
+
+
+#[derive(Component, Clone, Debug, PartialEq)]
 pub struct CellSegment {
     ctrl: Vec2,
     ctrl_target: Vec2,
@@ -51,54 +161,49 @@ pub struct CellSegment {
     radius: f32,
     radius_target: f32,
     radius_speed: f32,
-    max_radius_looser: bool,
 }
 
 
 impl CellSegment {
-    fn get_max_radius(&self) -> f32 {
-        if self.max_radius_looser {
-            return self.get_looser_max_radius();
+    fn new(params: &CellParams) -> Self {
+        Self {
+            ctrl: Vec2::new(params.ctrl_min, params.ctrl_min),
+            ctrl_speed: params.min_speed,
+            ctrl_target: Vec2::new(params.ctrl_min, params.ctrl_min),
+            radius: params.min_radius,
+            radius_target: params.min_radius,
+            radius_speed: params.min_speed,
+        }
+    }
+
+    fn get_max_radius(&self, params: &CellParams) -> f32 {
+        if params.max_radius_looser {
+            return self.get_looser_max_radius(params);
         }
 
         let max_radius;
         if self.ctrl.x > self.ctrl.y {
-            max_radius = self.ctrl.y * CELL_MAX_RADIUS_MODIFIER;
+            max_radius = self.ctrl.y * params.max_radius_modifier;
         } else {
-            max_radius = self.ctrl.x * CELL_MAX_RADIUS_MODIFIER;
+            max_radius = self.ctrl.x * params.max_radius_modifier;
         }
-        if max_radius <= CELL_MIN_RADIUS {
-            return CELL_MIN_RADIUS + 1.0;
+        if max_radius <= params.min_radius {
+            return params.min_radius + 1.0;
         }
         return max_radius;
     }
 
     /// Get a max radius that is not as gated by the smaller of ctrl.x and ctrl.y
-    fn get_looser_max_radius(&self) -> f32 {
-        let max_radius = (self.ctrl.x + self.ctrl.y) / CELL_MAX_RADIUS_TIGHTNESS;
-        if max_radius <= CELL_MIN_RADIUS {
-            return CELL_MIN_RADIUS + 1.0;
+    fn get_looser_max_radius(&self, params: &CellParams) -> f32 {
+        let max_radius = (self.ctrl.x + self.ctrl.y) / params.max_radius_tightness;
+        if max_radius <= params.min_radius {
+            return params.min_radius + 1.0;
         }
         return max_radius;
     }
 }
 
 
-impl Default for CellSegment {
-    fn default() -> Self {
-        Self {
-            ctrl: Vec2::new(CELL_CTRL_MIN, CELL_CTRL_MIN),
-            ctrl_speed: CELL_MIN_SPEED,
-            ctrl_target: Vec2::new(CELL_CTRL_MIN, CELL_CTRL_MIN),
-            radius: CELL_MIN_RADIUS,
-            radius_target: CELL_MIN_RADIUS,
-            radius_speed: CELL_MIN_SPEED,
-            max_radius_looser: true,
-        }
-    }
-}
-
-
 #[derive(Component)]
 pub struct Cell {
     segments: [CellSegment; 4],
@@ -106,39 +211,13 @@ pub struct Cell {
 
 
 impl Cell {
-    fn tight() -> Self {
+    fn new(params: &CellParams) -> Self {
         Self {
             segments: [
-                CellSegment {
-                    max_radius_looser: false,
-                    ..Default::default()
-                },
-                CellSegment {
-                    max_radius_looser: false,
-                    ..Default::default()
-                },
-                CellSegment {
-                    max_radius_looser: false,
-                    ..Default::default()
-                },
-                CellSegment {
-                    max_radius_looser: false,
-                    ..Default::default()
-                },
-            ],
-        }
-    }
-}
-
-
-impl Default for Cell {
-    fn default() -> Self {
-        Self {
-            segments: [
-                CellSegment::default(),
-                CellSegment::default(),
-                CellSegment::default(),
-                CellSegment::default(),
+                CellSegment::new(params),
+                CellSegment::new(params),
+                CellSegment::new(params),
+                CellSegment::new(params),
             ],
         }
     }
@@ -195,7 +274,40 @@ fn gen_cell_path(cell: &Cell) -> PathBuilder {
 }
 
 
-fn spawn_cell(commands: &mut Commands, cell: Cell, translation: Vec3) -> Entity {
+/// The cell's outline inset by `inner_inset`
+fn gen_inner_cell_path(cell_path: &Path, params: &CellParams) -> Path {
+    let outlines: Vec<Vec<Vec2>> = flatten(cell_path, CELL_FLATTEN_TOLERANCE)
+        .iter()
+        .flat_map(|outline| offset(outline, -params.inner_inset))
+        .collect();
+    return polygons_builder(&outlines).build();
+}
+
+
+fn cell_draw_mode(params: &CellParams) -> DrawMode {
+    let colors = params.colors();
+    DrawMode::Outlined {
+        fill_mode: FillMode::color(colors.fill),
+        outline_mode: StrokeMode::new(colors.stroke, params.stroke),
+    }
+}
+
+
+fn cell_inner_draw_mode(params: &CellParams) -> DrawMode {
+    let colors = params.colors();
+    DrawMode::Outlined {
+        fill_mode: FillMode::color(colors.inner_fill),
+        outline_mode: StrokeMode::new(colors.inner_stroke, params.inner_stroke),
+    }
+}
+
+
+fn spawn_cell(
+    commands: &mut Commands,
+    params: &CellParams,
+    cell: Cell,
+    translation: Vec3,
+) -> Entity {
     let path_builder = gen_cell_path(&cell);
     let path = path_builder.build();
 
@@ -203,10 +315,7 @@ fn spawn_cell(commands: &mut Commands, cell: Cell, translation: Vec3) -> Entity
         .spawn_bundle(GeometryBuilder::build_as(
             &path,
             // DrawMode::Stroke(StrokeMode::new(Color::BLACK, 10.0)),
-            DrawMode::Outlined {
-                fill_mode: FillMode::color(CELL_FILL_CLR),
-                outline_mode: StrokeMode::new(CELL_STROKE_CLR, CELL_STROKE),
-            },
+            cell_draw_mode(params),
             Transform {
                 translation: translation,
                 ..Default::default()
@@ -217,17 +326,10 @@ fn spawn_cell(commands: &mut Commands, cell: Cell, translation: Vec3) -> Entity
 
     commands
         .spawn_bundle(GeometryBuilder::build_as(
-            &path,
+            &gen_inner_cell_path(&path, params),
             // DrawMode::Stroke(StrokeMode::new(Color::BLACK, 10.0)),
-            DrawMode::Outlined {
-                fill_mode: FillMode::color(CELL_INNER_FILL_CLR),
-                outline_mode: StrokeMode::new(CELL_INNER_STROKE_CLR, CELL_INNER_STROKE),
-            },
-            Transform {
-                translation: Vec3::new(0.0, 0.0, translation.z - 1.0),
-                scale: Vec3::new(CELL_INNER_SIZE, CELL_INNER_SIZE, 1.0),
-                ..Default::default()
-            },
+            cell_inner_draw_mode(params),
+            Transform::from_translation(Vec3::new(0.0, 0.0, translation.z - 1.0)),
         ))
         .insert(CellInner)
         .insert(Parent(cell_bundle));
@@ -236,16 +338,42 @@ fn spawn_cell(commands: &mut Commands, cell: Cell, translation: Vec3) -> Entity
 }
 
 
-fn cell_setup(mut commands: Commands) {
+fn cell_setup(
+    mut commands: Commands,
+    webcfg: Res<WebExtrasCfg>,
+    params: Res<CellParams>,
+    mut rng: ResMut<SketchRng>,
+) {
     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
 
-    spawn_cell(&mut commands, Cell::default(), Vec3::ONE);
-    spawn_cell(
-        &mut commands,
-        Cell::default(),
-        Vec3::new(-350.0, 320.0, 3.0),
-    );
-    spawn_cell(&mut commands, Cell::tight(), Vec3::new(-200.0, -250.0, 2.0));
+    // Far enough apart that the cells don't overlap at their largest, with their centers on the
+    // canvas
+    let reach = params.max_reach();
+    let placer = Placer::new(Bounds {
+        min: Vec2::new(-webcfg.max_x - reach, -webcfg.max_y - reach),
+        max: Vec2::new(webcfg.max_x + reach, webcfg.max_y + reach),
+    });
+    let positions = placer.place(rng.stream("cells"), &[reach; CELL_COUNT]);
+    for (index, position) in positions.into_iter().enumerate() {
+        // Each cell (and its inner cell just below) on its own layer
+        let translation = position.extend(1.0 + 2.0 * index as f32);
+        spawn_cell(&mut commands, &params, Cell::new(&params), translation);
+    }
+}
+
+
+/// Apply edited params to the colors and strokes of the cells (`redraw_cell` picks up the inset)
+fn restyle_cells(
+    params: Res<CellParams>,
+    mut cell_q: Query<&mut DrawMode, With<Cell>>,
+    mut inner_cell_q: Query<&mut DrawMode, (With<CellInner>, Without<Cell>)>,
+) {
+    for mut draw_mode in cell_q.iter_mut() {
+        *draw_mode = cell_draw_mode(&params);
+    }
+    for mut draw_mode in inner_cell_q.iter_mut() {
+        *draw_mode = cell_inner_draw_mode(&params);
+    }
 }
 
 
@@ -274,14 +402,22 @@ fn get_next_location(current_location: f32, target_location: f32, speed: f32) ->
 // https://bevy-cheatbook.github.io/features/parent-child.html
 // https://github.com/bevyengine/bevy/blob/main/examples/ecs/hierarchy.rs
 fn redraw_cell(
+    clock: Res<SketchClock>,
+    params: Res<CellParams>,
     mut query: Query<(&mut Path, &mut Cell, &Children)>,
     mut inner_cell_query: Query<(&mut Path, With<CellInner>), Without<Cell>>,
 ) {
+    let frames = clock.delta_seconds() as f32 * CELL_SPEED_FPS;
+    // Frozen while paused, but still redrawn for edited params (e.g. the inset)
+    if frames == 0.0 && !params.is_changed() {
+        return;
+    }
     for (mut path, mut cell, children) in query.iter_mut() {
         for seg in &mut cell.segments {
-            seg.ctrl.x = get_next_location(seg.ctrl.x, seg.ctrl_target.x, seg.ctrl_speed);
-            seg.ctrl.y = get_next_location(seg.ctrl.y, seg.ctrl_target.y, seg.ctrl_speed);
-            seg.radius = get_next_location(seg.radius, seg.radius_target, seg.radius_speed);
+            let (ctrl_speed, radius_speed) = (seg.ctrl_speed * frames, seg.radius_speed * frames);
+            seg.ctrl.x = get_next_location(seg.ctrl.x, seg.ctrl_target.x, ctrl_speed);
+            seg.ctrl.y = get_next_location(seg.ctrl.y, seg.ctrl_target.y, ctrl_speed);
+            seg.radius = get_next_location(seg.radius, seg.radius_target, radius_speed);
         }
         let path_builder = gen_cell_path(&cell);
         let new_path = path_builder.build();
@@ -289,40 +425,204 @@ fn redraw_cell(
 
         let &inner_cell_entity = children.iter().next().unwrap();
         if let Ok((mut inner_path, _)) = inner_cell_query.get_mut(inner_cell_entity) {
-            *inner_path = ShapePath::build_as(&new_path);
+            *inner_path = gen_inner_cell_path(&new_path, &params);
         }
     }
 }
 
 
-fn mutate_cell(mut query: Query<&mut Cell>) {
-    let mut rng = thread_rng();
-
+fn mutate_cell(params: Res<CellParams>, mut rng: ResMut<SketchRng>, mut query: Query<&mut Cell>) {
+    let rng = rng.stream("cell targets");
     for mut cell in query.iter_mut() {
         for seg in &mut cell.segments {
-            seg.radius_speed = rng.gen_range(CELL_MIN_SPEED..CELL_MAX_SPEED);
-            seg.ctrl_speed = rng.gen_range(CELL_MIN_SPEED..CELL_MAX_SPEED / 2.0);
-            seg.ctrl_target.x = rng.gen_range(CELL_CTRL_MIN..CELL_CTRL_MAX);
-            seg.ctrl_target.y = rng.gen_range(CELL_CTRL_MIN..CELL_CTRL_MAX);
-            seg.radius_target = rng.gen_range(CELL_MIN_RADIUS..seg.get_max_radius());
+            seg.radius_speed = rng.range(params.min_speed, params.max_speed);
+            seg.ctrl_speed = rng.range(params.min_speed, params.max_speed / 2.0);
+            seg.ctrl_target.x = rng.range(params.ctrl_min, params.ctrl_max);
+            seg.ctrl_target.y = rng.range(params.ctrl_min, params.ctrl_max);
+            seg.radius_target = rng.range(params.min_radius, seg.get_max_radius(&params));
         }
     }
 }
 
 
-pub fn app() {
-    let webcfg = WebExtrasCfg {
-        title: String::from("cellular"),
-        match_element: Some(String::from("content")),
-        match_clear_color: true,
-        ..Default::default()
-    };
-    let mut app = sketch(webcfg);
-    app.insert_resource(ClearColor(CELL_CLEAR_CLR))
-        .add_plugin(ShapePlugin)
-        .add_startup_system(cell_setup)
-        .add_system(redraw_cell)
-        .add_system(mutate_cell.with_run_criteria(FixedTimestep::step(CELL_STEP)));
-
-    app.run();
+pub struct CellularSketch;
+
+
+impl Sketch for CellularSketch {
+    fn name(&self) -> &'static str {
+        "cellular"
+    }
+
+    fn title(&self) -> &'static str {
+        "cellular"
+    }
+
+    fn description(&self) -> &'static str {
+        "Wobbling cells drawn with quadratic curves"
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["lyon"]
+    }
+
+    fn params(&self) -> Option<SketchParams> {
+        Some(SketchParams::of::<CellParams>("CellParams"))
+    }
+
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        sketch
+            .clear_color_from::<CellParams>(|params| params.colors().clear)
+            .lyon()
+            .params::<CellParams>()
+            .presets::<CellParams>("cellular")
+            .startup_system(cell_setup)
+            .system(redraw_cell)
+            .on_params_change::<CellParams, _>(restyle_cells)
+            .fixed_system(mutate_cell, CELL_STEP)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessSketch;
+
+    const SEED: u64 = 7;
+
+    fn assert_radii_within_limits(params: CellParams) {
+        let max_radius = params.max_radius();
+        let max_reach = params.max_reach();
+        let ctrl_range = params.ctrl_min..=params.ctrl_max;
+        let radius_range = params.min_radius..=max_radius;
+        let min_radius = params.min_radius;
+
+        // Big enough for all of the cells at their largest
+        let mut sketch = HeadlessSketch::new(SEED)
+            .canvas(1920.0, 1080.0)
+            .resource(params)
+            .startup_system(cell_setup)
+            .system(redraw_cell)
+            .fixed_system(mutate_cell, CELL_STEP);
+        let mut moved = false;
+        for _ in 0..50 {
+            // A few frames per step, so the cells get to move towards their targets
+            sketch.run_steps(CELL_STEP / 4.0, 4);
+            let cells = sketch.components::<Cell>();
+            assert_eq!(cells.len(), CELL_COUNT);
+            for seg in cells.iter().flat_map(|cell| &cell.segments) {
+                assert!(ctrl_range.contains(&seg.ctrl.x) && ctrl_range.contains(&seg.ctrl.y));
+                assert!(radius_range.contains(&seg.radius), "radius {}", seg.radius);
+                assert!(radius_range.contains(&seg.radius_target));
+                moved |= seg.radius > min_radius;
+            }
+            // Outlines in the cells' own space, so centered on the origin
+            for path in sketch.components::<Path>() {
+                for point in flatten(path, 0.1).iter().flatten() {
+                    assert!(point.length() <= max_reach + 0.1, "{:?}", point);
+                }
+            }
+        }
+        // Otherwise the checks above would pass trivially
+        assert!(moved);
+    }
+
+    #[test]
+    fn radii_stay_within_limits() {
+        assert_radii_within_limits(CellParams::default());
+    }
+
+    #[test]
+    fn tight_radii_stay_within_limits() {
+        assert_radii_within_limits(CellParams {
+            max_radius_looser: false,
+            ..default()
+        });
+    }
+
+    #[test]
+    fn cells_stay_apart_at_their_largest() {
+        for params in [
+            CellParams::default(),
+            CellParams {
+                max_radius_looser: false,
+                ..default()
+            },
+        ] {
+            let reach = params.max_reach();
+            let mut sketch = HeadlessSketch::new(SEED)
+                .canvas(1280.0, 720.0)
+                .resource(params)
+                .startup_system(cell_setup);
+            sketch.update(0.0);
+            let world = sketch.world();
+            let mut query = world.query_filtered::<&Transform, With<Cell>>();
+            let centers: Vec<Vec2> = query
+                .iter(world)
+                .map(|t| t.translation.truncate())
+                .collect();
+            assert!(centers.len() > 1, "{} cells", centers.len());
+            for (i, a) in centers.iter().enumerate() {
+                assert!(
+                    a.x.abs() <= 640.0 && a.y.abs() <= 360.0,
+                    "off the canvas: {:?}",
+                    a
+                );
+                for b in centers[i + 1..].iter() {
+                    assert!(a.distance(*b) >= reach * 2.0, "{:?} and {:?} overlap", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn paused_clock_freezes_cells() {
+        let mut sketch = HeadlessSketch::new(SEED)
+            .params::<CellParams>()
+            .startup_system(cell_setup)
+            .system(redraw_cell)
+            .fixed_system(mutate_cell, CELL_STEP);
+        sketch.run_steps(CELL_STEP / 4.0, 6);
+        let segments = |sketch: &mut HeadlessSketch| -> Vec<CellSegment> {
+            let cells = sketch.components::<Cell>();
+            return cells
+                .iter()
+                .flat_map(|cell| cell.segments.clone())
+                .collect();
+        };
+        let before = segments(&mut sketch);
+
+        sketch
+            .world()
+            .resource_mut::<SketchClock>()
+            .set_paused(true);
+        sketch.run_steps(CELL_STEP / 4.0, 20);
+        assert_eq!(segments(&mut sketch), before);
+
+        // A single step moves them on
+        sketch.world().resource_mut::<SketchClock>().step();
+        sketch.update(CELL_STEP / 4.0);
+        assert_ne!(segments(&mut sketch), before);
+    }
+
+    #[test]
+    fn clear_color_follows_the_palette() {
+        let mut app = App::new();
+        app.init_resource::<CellParams>();
+        SketchBuilder::new("cellular")
+            .clear_color_from::<CellParams>(|params| params.colors().clear)
+            .add_steps(&mut app);
+        app.update();
+        assert_eq!(app.world.resource::<ClearColor>().0, CELL_CLEAR_CLR);
+
+        let mut params = app.world.resource_mut::<CellParams>();
+        params.palette = PaletteKind::Triadic;
+        params.palette_seed = 4;
+        app.update();
+        let palette = PaletteKind::Triadic.generate(4).unwrap();
+        assert_eq!(app.world.resource::<ClearColor>().0, palette.background);
+        // The hand-picked alphas stay
+        let colors = app.world.resource::<CellParams>().colors();
+        assert_eq!(colors.fill, with_alpha(palette.fill, 0.1));
+    }
 }
@@ -0,0 +1,388 @@
+/*
+ * gallery
+ *
+ * All the sketches in a single app, switching between them at runtime. Each sketch is a value of
+ * the `GallerySketch` state: its startup systems run when it is shown, its other systems only
+ * while it is shown, and when it is switched away from all its entities are despawned (which
+ * also frees the meshes and materials they held). Entities that outlive a switch (the UI camera,
+ * the overlays) are marked `PersistentEntity`.
+ *
+ * Material plugins, parameter resources and presets of all sketches are added once, and the
+ * shared ones (e.g. the params of the shifty variations) stay shared.
+ *
+ * Navigation:
+ * - `NEXT_SKETCH_KEY` / `PREVIOUS_SKETCH_KEY`
+ * - swiping left / right on a touch screen
+ * - from JavaScript, `gallery_next()`, `gallery_previous()` and `gallery_show("cellular")`
+ * - the first sketch can be chosen with the `sketch` runtime option (`gallery.html?sketch=cellular`)
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/ecs/state.rs
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/input/touch_input.rs
+ */
+use crate::base::SketchBuilder;
+use crate::runtime_opts::{get_opt, SKETCH_OPT};
+use crate::sketch::Sketch;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use std::cell::RefCell;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+
+pub const NEXT_SKETCH_KEY: KeyCode = KeyCode::PageDown;
+pub const PREVIOUS_SKETCH_KEY: KeyCode = KeyCode::PageUp;
+const GALLERY_TITLE: &str = "sketches";
+/// Horizontal distance in pixels a touch has to travel to count as a swipe
+const SWIPE_MIN_DISTANCE: f32 = 80.0;
+
+
+/// The sketch shown in the gallery, by `Sketch::name`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct GallerySketch(pub &'static str);
+
+
+/// Entities that are kept when the gallery switches sketches
+#[derive(Component)]
+pub struct PersistentEntity;
+
+
+/// Names of the sketches in the gallery, in navigation order
+pub struct Gallery {
+    names: Vec<&'static str>,
+}
+
+
+impl Gallery {
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    /// The name `offset` places after (or before, if negative) `current`, wrapping around
+    pub fn step(&self, current: &str, offset: isize) -> &'static str {
+        let len = self.names.len() as isize;
+        let index = self
+            .names
+            .iter()
+            .position(|name| *name == current)
+            .unwrap_or(0) as isize;
+        return self.names[(index + offset).rem_euclid(len) as usize];
+    }
+}
+
+
+#[derive(Debug)]
+enum GalleryRequest {
+    Step(isize),
+    // Only requested from JavaScript
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    Show(String),
+}
+
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    /// Requests made from JavaScript, picked up by `navigate_gallery`
+    static JS_REQUESTS: RefCell<Vec<GalleryRequest>> = RefCell::new(Vec::new());
+}
+
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn gallery_next() {
+    JS_REQUESTS.with(|requests| requests.borrow_mut().push(GalleryRequest::Step(1)));
+}
+
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn gallery_previous() {
+    JS_REQUESTS.with(|requests| requests.borrow_mut().push(GalleryRequest::Step(-1)));
+}
+
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn gallery_show(name: &str) {
+    JS_REQUESTS.with(|requests| {
+        requests
+            .borrow_mut()
+            .push(GalleryRequest::Show(name.to_string()))
+    });
+}
+
+
+/// Build the gallery app for the sketches (e.g. `crate::sketch::sketches()`)
+pub fn build(sketches: &[&dyn Sketch]) -> App {
+    return builder(sketches).build();
+}
+
+
+pub fn run(sketches: &[&dyn Sketch]) {
+    build(sketches).run();
+}
+
+
+/// Each sketch's setup, scoped to its `GallerySketch` state
+pub fn builder(sketches: &[&dyn Sketch]) -> SketchBuilder {
+    let names: Vec<&'static str> = sketches.iter().map(|sketch| sketch.name()).collect();
+    let mut builder = SketchBuilder::new(GALLERY_TITLE).with_app(move |app| {
+        app.add_plugin(GalleryPlugin { names: names });
+    });
+    for sketch in sketches {
+        builder = sketch.build(builder.scope(Some(sketch.name())));
+    }
+    return builder.scope(None);
+}
+
+
+/// The `GallerySketch` state and navigation between the sketches. Use `SketchBuilder::scope` to
+/// add the sketches' systems.
+pub struct GalleryPlugin {
+    pub names: Vec<&'static str>,
+}
+
+
+impl Plugin for GalleryPlugin {
+    fn build(&self, app: &mut App) {
+        let requested = get_opt(SKETCH_OPT);
+        let first = match requested {
+            Some(name) => match self.names.iter().find(|n| **n == name) {
+                Some(first) => *first,
+                None => {
+                    warn!("SKETCH: no sketch '{}' in the gallery", name);
+                    self.names[0]
+                }
+            },
+            None => self.names[0],
+        };
+
+        app.insert_resource(Gallery {
+            names: self.names.clone(),
+        })
+        .add_state(GallerySketch(first))
+        .add_system(navigate_gallery);
+        for name in &self.names {
+            app.add_system_set(
+                SystemSet::on_exit(GallerySketch(*name)).with_system(despawn_sketch_entities),
+            );
+        }
+    }
+}
+
+
+/// Whether the sketch `scope` is shown, always true outside the gallery (no scope)
+pub fn is_shown(scope: Option<&str>, shown: Option<&State<GallerySketch>>) -> bool {
+    match (scope, shown) {
+        (Some(scope), Some(shown)) => shown.current().0 == scope,
+        _ => true,
+    }
+}
+
+
+/// Run criteria for a system of the sketch `scope`
+pub fn while_shown(
+    scope: Option<&'static str>,
+) -> impl FnMut(Option<Res<State<GallerySketch>>>) -> ShouldRun {
+    move |shown: Option<Res<State<GallerySketch>>>| {
+        if is_shown(scope, shown.as_deref()) {
+            return ShouldRun::Yes;
+        }
+        ShouldRun::No
+    }
+}
+
+
+fn navigate_gallery(
+    keys: Res<Input<KeyCode>>,
+    touches: Res<Touches>,
+    gallery: Res<Gallery>,
+    mut shown: ResMut<State<GallerySketch>>,
+) {
+    let mut requests = Vec::new();
+    if keys.just_pressed(NEXT_SKETCH_KEY) {
+        requests.push(GalleryRequest::Step(1));
+    }
+    if keys.just_pressed(PREVIOUS_SKETCH_KEY) {
+        requests.push(GalleryRequest::Step(-1));
+    }
+    for touch in touches.iter_just_released() {
+        let swipe = touch.position().x - touch.start_position().x;
+        if swipe.abs() >= SWIPE_MIN_DISTANCE {
+            // Swiping left brings in the next sketch from the right
+            requests.push(GalleryRequest::Step(if swipe < 0.0 { 1 } else { -1 }));
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    JS_REQUESTS.with(|js_requests| requests.append(&mut js_requests.borrow_mut()));
+
+    // Only the last request counts, the state can change once per frame
+    let next = match requests.pop() {
+        Some(GalleryRequest::Step(offset)) => gallery.step(shown.current().0, offset),
+        Some(GalleryRequest::Show(name)) => match gallery.names().iter().find(|n| **n == name) {
+            Some(next) => *next,
+            None => {
+                warn!("SKETCH: no sketch '{}' in the gallery", name);
+                return;
+            }
+        },
+        None => return,
+    };
+    if next != shown.current().0 {
+        info!("SKETCH: showing {}", next);
+        if let Err(err) = shown.set(GallerySketch(next)) {
+            warn!("SKETCH: can't switch to {}: {:?}", next, err);
+        }
+    }
+}
+
+
+/// Despawn everything but the `PersistentEntity` entities (and their children)
+fn despawn_sketch_entities(
+    mut commands: Commands,
+    root_q: Query<Entity, (Without<Parent>, Without<PersistentEntity>)>,
+) {
+    for entity in root_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sketch_clock::SketchClock;
+    use bevy::asset::AssetPlugin;
+    use bevy::hierarchy::HierarchyPlugin;
+    use bevy::input::InputPlugin;
+    use bevy::transform::TransformPlugin;
+
+    #[derive(Component)]
+    struct Spawned(&'static str);
+
+    #[derive(Default)]
+    struct Runs(Vec<&'static str>);
+
+    /// Fixed steps, by sketch and whether its setup had spawned its entity
+    #[derive(Default)]
+    struct Steps(Vec<(&'static str, bool)>);
+
+    struct TestSketch(&'static str);
+
+    impl Sketch for TestSketch {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn title(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            ""
+        }
+
+        fn tags(&self) -> &'static [&'static str] {
+            &[]
+        }
+
+        fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+            let name = self.0;
+            sketch
+                .startup_system(
+                    move |mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>| {
+                        let mesh = meshes.add(Mesh::from(shape::Quad::default()));
+                        commands.spawn().insert(Spawned(name)).insert(mesh);
+                    },
+                )
+                .system(move |mut runs: ResMut<Runs>| runs.0.push(name))
+                .fixed_system(
+                    move |spawned: Query<&Spawned>, mut steps: ResMut<Steps>| {
+                        steps.0.push((name, spawned.iter().any(|s| s.0 == name)));
+                    },
+                    0.1,
+                )
+        }
+    }
+
+    fn test_app(sketches: &[&dyn Sketch]) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .init_resource::<SketchClock>()
+            .init_resource::<Runs>()
+            .init_resource::<Steps>();
+        app.world.spawn().insert(PersistentEntity);
+        builder(sketches).add_steps(&mut app);
+        return app;
+    }
+
+    fn show(app: &mut App, name: &'static str) {
+        app.world
+            .resource_mut::<State<GallerySketch>>()
+            .set(GallerySketch(name))
+            .unwrap();
+        app.update();
+    }
+
+    #[test]
+    fn steps_wrap_around() {
+        let gallery = Gallery {
+            names: vec!["a", "b", "c"],
+        };
+        assert_eq!(gallery.step("a", 1), "b");
+        assert_eq!(gallery.step("c", 1), "a");
+        assert_eq!(gallery.step("a", -1), "c");
+    }
+
+    #[test]
+    fn switching_cleans_up() {
+        let mut app = test_app(&[&TestSketch("a"), &TestSketch("b")]);
+        app.update();
+        let spawned = |app: &mut App| {
+            let mut query = app.world.query::<&Spawned>();
+            query.iter(&app.world).map(|s| s.0).collect::<Vec<_>>()
+        };
+        assert_eq!(spawned(&mut app), vec!["a"]);
+        assert_eq!(app.world.resource::<Runs>().0, vec!["a"]);
+
+        for _ in 0..3 {
+            show(&mut app, "b");
+            show(&mut app, "a");
+        }
+        show(&mut app, "b");
+        // Dropped handles are freed over the next frames
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(spawned(&mut app), vec!["b"]);
+        assert_eq!(app.world.resource::<Runs>().0.last(), Some(&"b"));
+        // The meshes of the despawned entities are freed
+        assert_eq!(app.world.resource::<Assets<Mesh>>().len(), 1);
+        let mut persistent = app.world.query::<&PersistentEntity>();
+        assert_eq!(persistent.iter(&app.world).count(), 1);
+    }
+
+    #[test]
+    fn fixed_steps_wait_for_setup() {
+        let mut app = test_app(&[&TestSketch("a"), &TestSketch("b")]);
+        // Every frame takes more than a step, and time left over while hidden must not carry
+        app.world.resource_mut::<SketchClock>().tick(0.25);
+        app.update();
+        app.update();
+        for _ in 0..3 {
+            show(&mut app, "b");
+            app.update();
+            show(&mut app, "a");
+            app.update();
+        }
+        let steps = &app.world.resource::<Steps>().0;
+        assert!(steps.iter().any(|step| step.0 == "b"));
+        assert!(steps.iter().all(|step| step.1), "{:?}", steps);
+    }
+}
@@ -0,0 +1,666 @@
+/*
+ * geometry
+ *
+ * Measurements and operations on polygons, for sketches that need real geometry rather than
+ * transforms: area, centroid, bounds, perimeter, point-in-polygon, offsetting (growing or
+ * insetting an outline by a fixed distance) and boolean union, intersection and difference.
+ *
+ * Polygons are closed outlines given as their vertices (`&[Vec2]`), without repeating the first
+ * vertex at the end. Lyon paths are turned into polygons with `flatten`, and polygons back into
+ * paths with `polygons_builder`. Operations that can produce several outlines (holes, or shapes
+ * falling apart) return a `Vec` of polygons: outer outlines counterclockwise and holes clockwise,
+ * which lyon's default even-odd fill draws as expected.
+ *
+ * Boolean operations and offsets split the edges wherever they cross, keep the pieces that belong
+ * to the result and link them back into outlines. They expect simple polygons (see
+ * `crate::shapegen::is_simple`) of either orientation, and work in `f64` inside.
+ *
+ * Instructed by:
+ * https://en.wikipedia.org/wiki/Shoelace_formula
+ * https://en.wikipedia.org/wiki/Centroid#Of_a_polygon
+ * https://en.wikipedia.org/wiki/Point_in_polygon#Ray_casting_algorithm
+ * https://www.angusj.com/clipper2/Docs/Overview.htm
+ * https://docs.rs/lyon_path/0.17.7/lyon_path/iterator/index.html
+ */
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use std::f64::consts::PI;
+use tess::path::{iterator::PathIterator, PathEvent};
+
+
+/// Largest angle between two points of a round offset corner
+const ROUND_JOIN_STEP: f64 = PI / 12.0;
+/// Offset pieces closer to the original outline than this fraction of the offset distance are
+/// trimmed. Round corners stay above it (the middle of a chord is at cos(ROUND_JOIN_STEP / 2)).
+const OFFSET_TRIM_RATIO: f64 = 0.98;
+/// Points closer than this fraction of the size of the shapes are the same point
+const EPSILON_RATIO: f64 = 1e-7;
+
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+
+impl Bounds {
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+
+/// Bounding box of the points, `None` without points
+pub fn bounds(points: &[Vec2]) -> Option<Bounds> {
+    let first = *points.first()?;
+    return Some(points.iter().fold(
+        Bounds {
+            min: first,
+            max: first,
+        },
+        |bounds, point| Bounds {
+            min: bounds.min.min(*point),
+            max: bounds.max.max(*point),
+        },
+    ));
+}
+
+
+/// Area of the polygon, positive if counterclockwise
+pub fn signed_area(polygon: &[Vec2]) -> f32 {
+    return polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum::<f32>()
+        / 2.0;
+}
+
+
+pub fn area(polygon: &[Vec2]) -> f32 {
+    signed_area(polygon).abs()
+}
+
+
+/// Center of mass of the polygon's surface, or the average of its vertices if it has no area
+pub fn centroid(polygon: &[Vec2]) -> Vec2 {
+    let polygon: Vec<DVec2> = polygon.iter().map(|point| point.as_dvec2()).collect();
+    if polygon.is_empty() {
+        return Vec2::ZERO;
+    }
+    // Relative to the first vertex, so far away polygons keep their precision
+    let origin = polygon[0];
+    let mut twice_area = 0.0;
+    let mut sum = DVec2::ZERO;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        let (a, b) = (*a - origin, *b - origin);
+        let cross = a.perp_dot(b);
+        twice_area += cross;
+        sum += (a + b) * cross;
+    }
+    if twice_area.abs() <= f64::EPSILON * sum.length().max(1.0) {
+        let average = polygon.iter().sum::<DVec2>() / polygon.len() as f64;
+        return average.as_vec2();
+    }
+    return (origin + sum / (3.0 * twice_area)).as_vec2();
+}
+
+
+/// Length of the closed outline
+pub fn perimeter(polygon: &[Vec2]) -> f32 {
+    return polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.distance(*b))
+        .sum();
+}
+
+
+/// Whether the point is inside the polygon (even-odd rule, points on the outline may go either
+/// way)
+pub fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let polygon: Vec<DVec2> = polygon.iter().map(|point| point.as_dvec2()).collect();
+    return contains_f64(&polygon, point.as_dvec2());
+}
+
+
+/// Distance from the point to the closest edge of the polygon, inside or out
+pub fn outline_distance(polygon: &[Vec2], point: Vec2) -> f32 {
+    let polygon: Vec<DVec2> = polygon.iter().map(|point| point.as_dvec2()).collect();
+    return polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| distance_to_edge(point.as_dvec2(), (*a, *b)))
+        .fold(f64::INFINITY, f64::min) as f32;
+}
+
+
+/// The polygon grown by `distance`, or inset for negative distances, with rounded corners where
+/// it grows. Returns no polygon when an inset swallows the whole shape, and several when it cuts
+/// through narrow parts.
+pub fn offset(polygon: &[Vec2], distance: f32) -> Vec<Vec<Vec2>> {
+    let polygon = counterclockwise(polygon);
+    if polygon.len() < 3 {
+        return Vec::new();
+    }
+    if distance == 0.0 {
+        return vec![to_vec2(&polygon)];
+    }
+    let distance = distance as f64;
+    let count = polygon.len();
+    let normal = |i: usize| {
+        let edge = polygon[(i + 1) % count] - polygon[i];
+        DVec2::new(edge.y, -edge.x).normalize_or_zero()
+    };
+
+    // The raw offset outline: each edge moved out along its normal, joined where the moved edges
+    // meet. Where they part, an arc goes around the corner; where they cross close enough to the
+    // corner, the crossing is the join, otherwise they are linked back through the corner and
+    // the loop this makes is trimmed below.
+    let mut raw: Vec<DVec2> = Vec::new();
+    for i in 0..count {
+        let (previous, corner, next) = (
+            polygon[(i + count - 1) % count],
+            polygon[i],
+            polygon[(i + 1) % count],
+        );
+        let (before, after) = (normal((i + count - 1) % count), normal(i));
+        let turn = before.perp_dot(after);
+        let miter = corner + (before + after) * (distance / (1.0 + before.dot(after)));
+        if turn * distance > 0.0 {
+            let sweep = turn.atan2(before.dot(after));
+            if sweep.abs() <= ROUND_JOIN_STEP {
+                raw.push(miter);
+                continue;
+            }
+            let steps = (sweep.abs() / ROUND_JOIN_STEP).ceil() as usize;
+            raw.push(corner + before * distance);
+            for step in 1..steps {
+                let angle = sweep * step as f64 / steps as f64;
+                let (sin, cos) = angle.sin_cos();
+                let rotated = DVec2::new(
+                    before.x * cos - before.y * sin,
+                    before.x * sin + before.y * cos,
+                );
+                raw.push(corner + rotated * distance);
+            }
+            raw.push(corner + after * distance);
+        } else {
+            // How far back along the moved edges they cross
+            let reach = miter.distance(corner + before * distance);
+            if reach <= corner.distance(previous).min(corner.distance(next)) {
+                raw.push(miter);
+            } else {
+                raw.push(corner + before * distance);
+                raw.push(corner);
+                raw.push(corner + after * distance);
+            }
+        }
+    }
+
+    let eps = epsilon(&[&polygon, &raw]);
+    let raw_edges = edges(&raw, eps);
+    let original_edges = edges(&polygon, eps);
+    let pieces: Vec<Edge> = split(&raw_edges, &raw_edges, eps)
+        .into_iter()
+        .filter(|piece| {
+            let middle = (piece.0 + piece.1) / 2.0;
+            let clearance = original_edges
+                .iter()
+                .map(|edge| distance_to_edge(middle, *edge))
+                .fold(f64::MAX, f64::min);
+            let outside = !contains_f64(&polygon, middle);
+            clearance >= distance.abs() * OFFSET_TRIM_RATIO && outside == (distance > 0.0)
+        })
+        .collect();
+    return link(pieces, eps);
+}
+
+
+/// Everything in either polygon
+pub fn union(a: &[Vec2], b: &[Vec2]) -> Vec<Vec<Vec2>> {
+    return boolean(a, b, Operation::Union);
+}
+
+
+/// Everything in both polygons
+pub fn intersection(a: &[Vec2], b: &[Vec2]) -> Vec<Vec<Vec2>> {
+    return boolean(a, b, Operation::Intersection);
+}
+
+
+/// Everything in `a` but not in `b`
+pub fn difference(a: &[Vec2], b: &[Vec2]) -> Vec<Vec<Vec2>> {
+    return boolean(a, b, Operation::Difference);
+}
+
+
+/// Each subpath of the path as a polygon, with curves flattened to lines no further than
+/// `tolerance` from them
+pub fn flatten(path: &Path, tolerance: f32) -> Vec<Vec<Vec2>> {
+    let mut polygons = Vec::new();
+    let mut current: Vec<Vec2> = Vec::new();
+    for event in path.0.iter().flattened(tolerance) {
+        match event {
+            PathEvent::Begin { at } => current = vec![Vec2::new(at.x, at.y)],
+            PathEvent::Line { to, .. } => current.push(Vec2::new(to.x, to.y)),
+            PathEvent::End { .. } => {
+                // Outlines closed by a last line back to the start would repeat it
+                if current.len() > 1 && current.first() == current.last() {
+                    current.pop();
+                }
+                polygons.push(std::mem::take(&mut current));
+            }
+            // Flattening leaves only lines
+            _ => {}
+        }
+    }
+    return polygons;
+}
+
+
+/// A path of closed outlines through the polygons
+pub fn polygons_builder(polygons: &[Vec<Vec2>]) -> PathBuilder {
+    let mut path_builder = PathBuilder::new();
+    for polygon in polygons {
+        if let Some((first, rest)) = polygon.split_first() {
+            path_builder.move_to(*first);
+            for point in rest {
+                path_builder.line_to(*point);
+            }
+            path_builder.close();
+        }
+    }
+    return path_builder;
+}
+
+
+type Edge = (DVec2, DVec2);
+
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+
+/// Where a piece of an outline is relative to the other polygon
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Inside,
+    Outside,
+    /// On the other outline, going the same way
+    Same,
+    /// On the other outline, going the opposite way
+    Opposite,
+}
+
+
+fn boolean(a: &[Vec2], b: &[Vec2], operation: Operation) -> Vec<Vec<Vec2>> {
+    let (a, b) = (counterclockwise(a), counterclockwise(b));
+    let eps = epsilon(&[&a, &b]);
+    let (a_edges, b_edges) = (edges(&a, eps), edges(&b, eps));
+
+    let mut kept = Vec::new();
+    for piece in split(&a_edges, &b_edges, eps) {
+        let keep = match (operation, side(piece, &b, &b_edges, eps)) {
+            (Operation::Union, side) => side == Side::Outside || side == Side::Same,
+            (Operation::Intersection, side) => side == Side::Inside || side == Side::Same,
+            (Operation::Difference, side) => side == Side::Outside || side == Side::Opposite,
+        };
+        if keep {
+            kept.push(piece);
+        }
+    }
+    // Shared outline pieces were kept from `a` already
+    for piece in split(&b_edges, &a_edges, eps) {
+        match (operation, side(piece, &a, &a_edges, eps)) {
+            (Operation::Union, Side::Outside) => kept.push(piece),
+            (Operation::Intersection, Side::Inside) => kept.push(piece),
+            // Reversed, as the outline of a hole in `a`
+            (Operation::Difference, Side::Inside) => kept.push((piece.1, piece.0)),
+            _ => {}
+        }
+    }
+    return link(kept, eps);
+}
+
+
+fn counterclockwise(polygon: &[Vec2]) -> Vec<DVec2> {
+    let mut polygon: Vec<DVec2> = polygon.iter().map(|point| point.as_dvec2()).collect();
+    polygon.dedup();
+    if polygon.len() > 1 && polygon.first() == polygon.last() {
+        polygon.pop();
+    }
+    let twice_area: f64 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    if twice_area < 0.0 {
+        polygon.reverse();
+    }
+    return polygon;
+}
+
+
+fn to_vec2(polygon: &[DVec2]) -> Vec<Vec2> {
+    polygon.iter().map(|point| point.as_vec2()).collect()
+}
+
+
+/// Distance under which points are the same, for shapes of the size of the polygons
+fn epsilon(polygons: &[&[DVec2]]) -> f64 {
+    let extent = polygons
+        .iter()
+        .flat_map(|polygon| polygon.iter())
+        .fold(0.0f64, |extent, point| {
+            extent.max(point.abs().max_element())
+        });
+    return (extent * EPSILON_RATIO).max(f64::MIN_POSITIVE);
+}
+
+
+/// The edges of the closed outline, leaving out the ones shorter than `eps`
+fn edges(polygon: &[DVec2], eps: f64) -> Vec<Edge> {
+    return polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+        .filter(|(a, b)| a.distance(*b) > eps)
+        .collect();
+}
+
+
+fn contains_f64(polygon: &[DVec2], point: DVec2) -> bool {
+    let mut inside = false;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    return inside;
+}
+
+
+fn distance_to_edge(point: DVec2, (a, b): Edge) -> f64 {
+    let edge = b - a;
+    let t = ((point - a).dot(edge) / edge.length_squared()).clamp(0.0, 1.0);
+    return point.distance(a + edge * t);
+}
+
+
+/// Points of `edge` where `cutter` crosses or touches it
+fn crossings((a, b): Edge, (c, d): Edge, eps: f64) -> Vec<DVec2> {
+    let (r, s) = (b - a, d - c);
+    let denominator = r.perp_dot(s);
+    if denominator.abs() > f64::EPSILON * r.length() * s.length() {
+        let t = (c - a).perp_dot(s) / denominator;
+        let u = (c - a).perp_dot(r) / denominator;
+        let (t_margin, u_margin) = (eps / r.length(), eps / s.length());
+        let on_edge = (-t_margin..=1.0 + t_margin).contains(&t);
+        let on_cutter = (-u_margin..=1.0 + u_margin).contains(&u);
+        if on_edge && on_cutter {
+            return vec![a + r * t.clamp(0.0, 1.0)];
+        }
+        return Vec::new();
+    }
+    // Parallel: only overlapping edges touch, where each one's ends lie on the other
+    return [c, d, a, b]
+        .into_iter()
+        .filter(|point| distance_to_edge(*point, (a, b)) <= eps)
+        .filter(|point| distance_to_edge(*point, (c, d)) <= eps)
+        .collect();
+}
+
+
+/// The edges cut into pieces wherever the cutters cross them
+fn split(edges: &[Edge], cutters: &[Edge], eps: f64) -> Vec<Edge> {
+    let boxes = |edges: &[Edge]| -> Vec<(DVec2, DVec2)> {
+        edges
+            .iter()
+            .map(|(a, b)| (a.min(*b) - eps, a.max(*b) + eps))
+            .collect()
+    };
+    let cutter_boxes = boxes(cutters);
+    let mut pieces = Vec::new();
+    for (edge, (min, max)) in edges.iter().zip(boxes(edges)) {
+        let direction = edge.1 - edge.0;
+        let mut cuts: Vec<(f64, DVec2)> = cutters
+            .iter()
+            .zip(cutter_boxes.iter())
+            // Only the cutters whose bounding boxes overlap the edge's can cross it
+            .filter(|(_, (cut_min, cut_max))| cut_min.cmple(max).all() && cut_max.cmpge(min).all())
+            .map(|(cutter, _)| cutter)
+            .filter(|cutter| *cutter != edge)
+            .flat_map(|cutter| crossings(*edge, *cutter, eps))
+            .map(|point| ((point - edge.0).dot(direction), point))
+            .collect();
+        cuts.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut start = edge.0;
+        for (_, point) in cuts.into_iter().chain([(0.0, edge.1)]) {
+            if start.distance(point) > eps {
+                pieces.push((start, point));
+                start = point;
+            }
+        }
+    }
+    return pieces;
+}
+
+
+fn side(piece: Edge, polygon: &[DVec2], polygon_edges: &[Edge], eps: f64) -> Side {
+    let middle = (piece.0 + piece.1) / 2.0;
+    for edge in polygon_edges {
+        if distance_to_edge(middle, *edge) <= eps {
+            if (piece.1 - piece.0).dot(edge.1 - edge.0) > 0.0 {
+                return Side::Same;
+            }
+            return Side::Opposite;
+        }
+    }
+    if contains_f64(polygon, middle) {
+        return Side::Inside;
+    }
+    return Side::Outside;
+}
+
+
+/// Chain the pieces end to start into closed outlines, dropping the ones that don't close
+fn link(pieces: Vec<Edge>, eps: f64) -> Vec<Vec<Vec2>> {
+    let mut used = vec![false; pieces.len()];
+    let mut polygons = Vec::new();
+    for first in 0..pieces.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut outline = vec![pieces[first].0];
+        let mut end = pieces[first].1;
+        let closed = loop {
+            if end.distance(outline[0]) <= eps {
+                break true;
+            }
+            let next = (0..pieces.len()).find(|i| !used[*i] && pieces[*i].0.distance(end) <= eps);
+            match next {
+                Some(next) => {
+                    used[next] = true;
+                    outline.push(end);
+                    end = pieces[next].1;
+                }
+                None => break false,
+            }
+        };
+        if closed && outline.len() >= 3 {
+            polygons.push(to_vec2(&outline));
+        }
+    }
+    return polygons;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: Vec2, size: f32) -> Vec<Vec2> {
+        vec![
+            min,
+            min + Vec2::new(size, 0.0),
+            min + Vec2::splat(size),
+            min + Vec2::new(0.0, size),
+        ]
+    }
+
+    fn total_area(polygons: &[Vec<Vec2>]) -> f32 {
+        polygons.iter().map(|polygon| signed_area(polygon)).sum()
+    }
+
+    #[test]
+    fn measures_polygons() {
+        let mut polygon = square(Vec2::new(1.0, 2.0), 2.0);
+        assert_eq!(signed_area(&polygon), 4.0);
+        assert_eq!(perimeter(&polygon), 8.0);
+        assert_eq!(centroid(&polygon), Vec2::new(2.0, 3.0));
+        let square_bounds = bounds(&polygon).unwrap();
+        assert_eq!(square_bounds.size(), Vec2::splat(2.0));
+        assert_eq!(square_bounds.center(), Vec2::new(2.0, 3.0));
+
+        polygon.reverse();
+        assert_eq!(signed_area(&polygon), -4.0);
+        assert_eq!(area(&polygon), 4.0);
+        assert_eq!(centroid(&polygon), Vec2::new(2.0, 3.0));
+
+        // An L: the centroid is off its bounding box's center
+        let l_shape = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(3.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ];
+        assert_eq!(area(&l_shape), 5.0);
+        assert!(centroid(&l_shape).abs_diff_eq(Vec2::splat(1.1), 1e-6));
+        assert!(contains(&l_shape, Vec2::new(0.5, 2.5)));
+        assert!(!contains(&l_shape, Vec2::new(2.0, 2.0)));
+        assert!(bounds(&[]).is_none());
+    }
+
+    #[test]
+    fn offsets_by_a_uniform_distance() {
+        let polygon = square(Vec2::ZERO, 10.0);
+        let inset = offset(&polygon, -1.0);
+        assert_eq!(inset.len(), 1);
+        assert!((area(&inset[0]) - 64.0).abs() < 1e-3);
+        assert!(signed_area(&inset[0]) > 0.0);
+
+        // Grown with round corners: the sides plus a circle
+        let grown = offset(&polygon, 1.0);
+        assert_eq!(grown.len(), 1);
+        let expected = 100.0 + 4.0 * 10.0 + std::f32::consts::PI;
+        assert!(
+            (area(&grown[0]) - expected).abs() < 0.05,
+            "{}",
+            area(&grown[0])
+        );
+
+        // Around the inner corner of the L, the inset keeps its distance with an arc
+        let l_shape = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(6.0, 0.0),
+            Vec2::new(6.0, 2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 6.0),
+            Vec2::new(0.0, 6.0),
+        ];
+        let inset = offset(&l_shape, -0.5);
+        assert_eq!(inset.len(), 1);
+        let expected = 9.0 + 0.25 - std::f32::consts::PI * 0.25 / 4.0;
+        assert!(
+            (area(&inset[0]) - expected).abs() < 0.01,
+            "{}",
+            area(&inset[0])
+        );
+        for point in &inset[0] {
+            assert!(contains(&l_shape, *point));
+        }
+
+        // Inset through the narrow part of a dumbbell, and past the whole shape
+        let dumbbell = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(4.0, 1.5),
+            Vec2::new(6.0, 1.5),
+            Vec2::new(6.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 4.0),
+            Vec2::new(6.0, 4.0),
+            Vec2::new(6.0, 2.5),
+            Vec2::new(4.0, 2.5),
+            Vec2::new(4.0, 4.0),
+            Vec2::new(0.0, 4.0),
+        ];
+        assert_eq!(offset(&dumbbell, -1.0).len(), 2);
+        assert!(offset(&polygon, -6.0).is_empty());
+    }
+
+    #[test]
+    fn combines_polygons() {
+        let a = square(Vec2::ZERO, 2.0);
+        let b = square(Vec2::ONE, 2.0);
+        assert!((total_area(&union(&a, &b)) - 7.0).abs() < 1e-5);
+        assert!((total_area(&intersection(&a, &b)) - 1.0).abs() < 1e-5);
+        assert!((total_area(&difference(&a, &b)) - 3.0).abs() < 1e-5);
+
+        // Shared edges, and one polygon inside the other
+        let c = square(Vec2::new(2.0, 0.0), 2.0);
+        let joined = union(&a, &c);
+        assert_eq!(joined.len(), 1);
+        assert!((total_area(&joined) - 8.0).abs() < 1e-5);
+        assert!(intersection(&a, &c).is_empty());
+        let inner = square(Vec2::splat(0.5), 1.0);
+        assert!((total_area(&union(&a, &inner)) - 4.0).abs() < 1e-5);
+        // A hole: the outline and the reversed inner square
+        let holed = difference(&a, &inner);
+        assert_eq!(holed.len(), 2);
+        assert!((total_area(&holed) - 3.0).abs() < 1e-5);
+        assert!(difference(&inner, &a).is_empty());
+
+        // Apart
+        let far = square(Vec2::splat(5.0), 1.0);
+        assert_eq!(union(&a, &far).len(), 2);
+        assert!(intersection(&a, &far).is_empty());
+    }
+
+    #[test]
+    fn flattens_paths() {
+        let mut path_builder = polygons_builder(&[square(Vec2::ZERO, 2.0)]);
+        path_builder.move_to(Vec2::new(5.0, 0.0));
+        path_builder.quadratic_bezier_to(Vec2::new(6.0, 2.0), Vec2::new(7.0, 0.0));
+        path_builder.close();
+        let polygons = flatten(&path_builder.build(), 0.01);
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0], square(Vec2::ZERO, 2.0));
+        assert!(polygons[1].len() > 5);
+        // The area under the parabola, less the slivers between the curve and the lines
+        let flattened_area = area(&polygons[1]);
+        assert!(flattened_area < 4.0 / 3.0 && flattened_area > 4.0 / 3.0 - 0.03);
+    }
+}
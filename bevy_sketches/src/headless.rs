@@ -0,0 +1,211 @@
+/*
+ * headless
+ *
+ * Runs a sketch's simulation systems in an app without a window or rendering (`MinimalPlugins`),
+ * so tests can advance a sketch by a number of fixed steps and check its components.
+ *
+ * Sketch time only moves when the test says so, and randomness comes from a fixed seed, so runs
+ * are repeatable. Shapes can still be spawned (their bundles are plain components), they just
+ * aren't tessellated or drawn.
+ *
+ * ```ignore
+ * let mut sketch = HeadlessSketch::new(SEED)
+ *     .params::<CellParams>()
+ *     .startup_system(cell_setup)
+ *     .fixed_system(mutate_cell, CELL_STEP);
+ * sketch.run_steps(CELL_STEP, 100);
+ * for cell in sketch.components::<Cell>() { ... }
+ * ```
+ *
+ * The sketch's own steps go through `SketchBuilder`, the same as in the real app, so only the
+ * plugins that stand in for rendering and the window (assets, input, the canvas size) are set up
+ * here.
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/tests/how_to_test_systems.rs
+ */
+use crate::base::SketchBuilder;
+use crate::rng::SketchRng;
+use crate::sketch_clock::SketchClock;
+use bevy::asset::{Asset, AssetPlugin};
+use bevy::hierarchy::HierarchyPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::reflect::GetTypeRegistration;
+use bevy::transform::TransformPlugin;
+use bevy_web_extras::prelude::*;
+
+
+/// Canvas size the sketch sees unless set with `HeadlessSketch::canvas`
+const HEADLESS_WIDTH: f32 = 1280.0;
+const HEADLESS_HEIGHT: f32 = 720.0;
+const HEADLESS_TITLE: &str = "headless";
+
+
+/// A `SketchBuilder` for the sketch's steps, added to an app without rendering
+pub struct HeadlessSketch {
+    app: App,
+    /// Steps not added to `app` yet, until it first runs or is looked into
+    sketch: Option<SketchBuilder>,
+}
+
+
+impl HeadlessSketch {
+    pub fn new(seed: u64) -> Self {
+        let mut app = App::new();
+        // Transforms and the parent/children hierarchy are simulation too. Assets and input
+        // stand in for the ones of `DefaultPlugins` (e.g. for the params editor), without any
+        // files being loaded or keys pressed.
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(AssetPlugin)
+            .add_plugin(InputPlugin)
+            .insert_resource(SketchRng::new(seed))
+            .init_resource::<SketchClock>();
+        let sketch = Self {
+            app: app,
+            sketch: None,
+        };
+        return sketch.canvas(HEADLESS_WIDTH, HEADLESS_HEIGHT);
+    }
+
+    /// Size of the (pretend) canvas, as read by the sketch from `WebExtrasCfg`
+    pub fn canvas(mut self, width: f32, height: f32) -> Self {
+        self.app.insert_resource(WebExtrasCfg {
+            width: width,
+            height: height,
+            max_x: width / 2.0,
+            max_y: height / 2.0,
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Make assets of type `T` available, e.g. for systems that add meshes or materials
+    pub fn asset<T: Asset>(mut self) -> Self {
+        self.app.add_asset::<T>();
+        self
+    }
+
+    /// Add steps with the `SketchBuilder` of the sketch, e.g. `|sketch| sketch.audio_reactive()`
+    pub fn with_sketch(mut self, step: impl FnOnce(SketchBuilder) -> SketchBuilder) -> Self {
+        let sketch = self
+            .sketch
+            .take()
+            .unwrap_or_else(|| SketchBuilder::new(HEADLESS_TITLE));
+        self.sketch = Some(step(sketch));
+        self
+    }
+
+    pub fn resource<R: Send + Sync + 'static>(self, resource: R) -> Self {
+        self.with_sketch(move |sketch| sketch.resource(resource))
+    }
+
+    /// Add a parameter resource with its default values, see `SketchBuilder::params`
+    pub fn params<T: Reflect + GetTypeRegistration + Default>(self) -> Self {
+        self.with_sketch(|sketch| sketch.params::<T>())
+    }
+
+    pub fn startup_system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        self.with_sketch(move |sketch| sketch.startup_system(system))
+    }
+
+    pub fn system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+    ) -> Self {
+        self.with_sketch(move |sketch| sketch.system(system))
+    }
+
+    /// See `SketchBuilder::fixed_system`, running every `step` seconds of sketch time
+    pub fn fixed_system<Params: 'static>(
+        self,
+        system: impl IntoSystem<(), (), Params> + 'static,
+        step: f64,
+    ) -> Self {
+        self.with_sketch(move |sketch| sketch.fixed_system(system, step))
+    }
+
+    /// The app, with the steps given so far added
+    fn app(&mut self) -> &mut App {
+        if let Some(sketch) = self.sketch.take() {
+            sketch.add_steps(&mut self.app);
+        }
+        &mut self.app
+    }
+
+    /// Run a single frame that advances sketch time by `seconds`. Startup systems run with the
+    /// first frame.
+    pub fn update(&mut self, seconds: f64) {
+        let app = self.app();
+        app.world.resource_mut::<SketchClock>().tick(seconds);
+        app.update();
+    }
+
+    /// Run `count` frames of `step` seconds each, so a fixed system with that step runs once per
+    /// frame
+    pub fn run_steps(&mut self, step: f64, count: usize) {
+        for _ in 0..count {
+            self.update(step);
+        }
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.app().world
+    }
+
+    pub fn read_resource<R: Send + Sync + 'static>(&mut self) -> &R {
+        self.app().world.resource::<R>()
+    }
+
+    /// All the `C` components currently in the world
+    pub fn components<C: Component>(&mut self) -> Vec<&C> {
+        let world = &mut self.app().world;
+        let mut query = world.query::<&C>();
+        return query.iter(world).collect();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Counter(u32);
+
+    fn count(mut q: Query<&mut Counter>) {
+        for mut counter in q.iter_mut() {
+            counter.0 += 1;
+        }
+    }
+
+    fn spawn_counter(mut commands: Commands) {
+        commands.spawn().insert(Counter(0));
+    }
+
+    #[test]
+    fn fixed_systems_follow_sketch_time() {
+        let mut sketch = HeadlessSketch::new(1)
+            .startup_system(spawn_counter)
+            .fixed_system(count, 0.5);
+
+        sketch.run_steps(0.5, 4);
+        assert_eq!(sketch.components::<Counter>()[0].0, 4);
+
+        // Two frames for each step
+        sketch.run_steps(0.25, 4);
+        assert_eq!(sketch.components::<Counter>()[0].0, 6);
+
+        sketch
+            .world()
+            .resource_mut::<SketchClock>()
+            .set_paused(true);
+        sketch.run_steps(0.5, 4);
+        assert_eq!(sketch.components::<Counter>()[0].0, 6);
+    }
+}
@@ -1,16 +1,43 @@
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        let result = 2 + 2;
-        assert_eq!(result, 4);
-    }
-}
-
 pub mod base;
 
+pub mod sketch;
+
+pub mod gallery;
+
+pub mod runtime_opts;
+
+pub mod perf_overlay;
+
+pub mod params;
+
+pub mod presets;
+
+pub mod rng;
+
+pub mod sketch_clock;
+
+pub mod pointer;
+
+pub mod audio_analysis;
+
+pub mod headless;
+
+pub mod svg_export;
+
+pub mod svg_import;
+
+pub mod geometry;
+
+pub mod placement;
+
+pub mod noise;
+
+pub mod palette;
+
 pub mod shapegen;
 
+pub mod morph;
+
 pub mod shifty_circle;
 
 pub mod cellular;
@@ -18,3 +45,5 @@ pub mod cellular;
 pub mod path_changer;
 
 pub mod shader_materials;
+
+pub mod shader_sketches;
@@ -0,0 +1,343 @@
+/*
+ * morph
+ *
+ * Smooth transitions between lyon shapes. Insert a `Morph` on any entity with a `Path` and
+ * `morph_paths` eases the path from one shape to the other over sketch time (see
+ * `crate::sketch_clock`, so morphs pause and speed up with the clock), then removes the `Morph`.
+ *
+ * Both shapes are flattened into polygons and resampled to the same number of points, evenly
+ * spaced along their outlines, so shapes with any number of segments (random polygons, SVG
+ * shapes with curves) can morph into each other. The points are matched up by turning both
+ * outlines the same way round and starting the target where it is closest to the source.
+ *
+ * Shapes with several subpaths morph through their longest one. At the end the path is set to
+ * the exact target shape, curves included.
+ *
+ * Instructed by:
+ * https://github.com/Nilirad/bevy_prototype_lyon/blob/master/examples/dynamic_shape.rs
+ * https://easings.net/
+ * https://docs.rs/lyon_path/0.17.7/lyon_path/iterator/index.html
+ */
+use crate::geometry::{flatten, perimeter, signed_area};
+use crate::shapegen::polygon_builder;
+use crate::sketch_clock::SketchClock;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+
+/// Points along the outlines while morphing
+pub const MORPH_POINTS: usize = 128;
+/// Flattening tolerance, as a fraction of the shape's size
+const FLATTEN_TOLERANCE: f32 = 0.001;
+
+
+pub struct MorphPlugin;
+
+
+impl Plugin for MorphPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(morph_paths);
+    }
+}
+
+
+/// Easing curves for `Morph`, see https://easings.net/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect_value]
+pub enum Easing {
+    Linear,
+    QuadInOut,
+    CubicInOut,
+    SineInOut,
+}
+
+
+impl Default for Easing {
+    fn default() -> Self {
+        Self::CubicInOut
+    }
+}
+
+
+impl Easing {
+    /// Eased progress for `t` from 0.0 to 1.0 (clamped)
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Self::QuadInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Self::CubicInOut if t < 0.5 => 4.0 * t * t * t,
+            Self::CubicInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Self::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+        }
+    }
+}
+
+
+/// An ongoing transition of the entity's `Path`
+#[derive(Component, Clone)]
+pub struct Morph {
+    from: Vec<Vec2>,
+    to: Vec<Vec2>,
+    target: Path,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+
+impl Morph {
+    /// Morph from the `from` shape (usually the entity's current path) to the `to` shape over
+    /// `duration` seconds
+    pub fn new(from: &Path, to: &Path, duration: f32, easing: Easing) -> Self {
+        let from_points = resample(&path_points(from), MORPH_POINTS);
+        let to_points = align(&from_points, resample(&path_points(to), MORPH_POINTS));
+        Self {
+            from: from_points,
+            to: to_points,
+            target: to.clone(),
+            duration: duration,
+            elapsed: 0.0,
+            easing: easing,
+        }
+    }
+
+    /// From 0.0 at the start to 1.0 when done
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        return (self.elapsed / self.duration).min(1.0);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// The outline at the current progress
+    pub fn points(&self) -> Vec<Vec2> {
+        let t = self.easing.ease(self.progress());
+        return self
+            .from
+            .iter()
+            .zip(self.to.iter())
+            .map(|(from, to)| from.lerp(*to, t))
+            .collect();
+    }
+}
+
+
+/// The longest subpath of the path as a polygon, curves flattened
+pub fn path_points(path: &Path) -> Vec<Vec2> {
+    let extent = path.0.iter().fold(0.0f32, |extent, event| {
+        let to = event.to();
+        extent.max(to.x.abs()).max(to.y.abs())
+    });
+    let tolerance = (extent * FLATTEN_TOLERANCE).max(f32::EPSILON);
+    return flatten(path, tolerance)
+        .into_iter()
+        .max_by(|a, b| perimeter(a).total_cmp(&perimeter(b)))
+        .unwrap_or_default();
+}
+
+
+/// `count` points evenly spaced along the closed outline through the points, starting at the
+/// first one
+pub fn resample(points: &[Vec2], count: usize) -> Vec<Vec2> {
+    let length = perimeter(points);
+    if points.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    if length <= 0.0 {
+        return vec![points[0]; count];
+    }
+    let step = length / count as f32;
+    let mut resampled = Vec::with_capacity(count);
+    // Start of the current edge along the outline
+    let mut edge_start = 0.0;
+    let mut edge = 0;
+    for i in 0..count {
+        let distance = i as f32 * step;
+        let (mut a, mut b) = (points[edge], points[(edge + 1) % points.len()]);
+        while edge_start + a.distance(b) < distance && edge + 1 < points.len() {
+            edge_start += a.distance(b);
+            edge += 1;
+            a = points[edge];
+            b = points[(edge + 1) % points.len()];
+        }
+        let edge_length = a.distance(b);
+        let t = if edge_length > 0.0 {
+            ((distance - edge_start) / edge_length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        resampled.push(a.lerp(b, t));
+    }
+    return resampled;
+}
+
+
+/// `to` turned the same way round as `from`, and starting at the point that keeps the matched
+/// points closest to each other
+fn align(from: &[Vec2], mut to: Vec<Vec2>) -> Vec<Vec2> {
+    if from.len() != to.len() || to.is_empty() {
+        return to;
+    }
+    if (signed_area(from) < 0.0) != (signed_area(&to) < 0.0) {
+        to.reverse();
+    }
+    let count = to.len();
+    let cost = |offset: usize| -> f32 {
+        from.iter()
+            .enumerate()
+            .map(|(i, point)| point.distance_squared(to[(i + offset) % count]))
+            .sum()
+    };
+    let best = (0..count)
+        .min_by(|a, b| cost(*a).total_cmp(&cost(*b)))
+        .unwrap_or(0);
+    to.rotate_left(best);
+    return to;
+}
+
+
+/// Advance the morphs and update their paths, snapping to the target shape when done
+pub fn morph_paths(
+    mut commands: Commands,
+    clock: Res<SketchClock>,
+    mut query: Query<(Entity, &mut Morph, &mut Path)>,
+) {
+    for (entity, mut morph, mut path) in query.iter_mut() {
+        morph.elapsed += clock.delta_seconds() as f32;
+        if morph.is_done() {
+            *path = morph.target.clone();
+            commands.entity(entity).remove::<Morph>();
+        } else {
+            let shape = polygon_builder(&morph.points()).build();
+            *path = ShapePath::build_as(&shape);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_export::path_data;
+
+    fn square(half: f32) -> Vec<Vec2> {
+        vec![
+            Vec2::new(-half, -half),
+            Vec2::new(half, -half),
+            Vec2::new(half, half),
+            Vec2::new(-half, half),
+        ]
+    }
+
+    #[test]
+    fn resamples_by_arc_length() {
+        // A long edge and short ones: the points still come evenly spaced
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(6.0, 0.0),
+            Vec2::new(6.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ];
+        let expected = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(6.0, 0.0),
+            Vec2::new(5.0, 1.0),
+            Vec2::new(3.0, 1.0),
+            Vec2::new(1.0, 1.0),
+        ];
+        let resampled = resample(&points, 7);
+        assert_eq!(resampled.len(), expected.len());
+        for (point, expected) in resampled.iter().zip(expected) {
+            assert!(
+                point.abs_diff_eq(expected, 1e-5),
+                "{} != {}",
+                point,
+                expected
+            );
+        }
+        assert!((perimeter(&resample(&points, 200)) - 14.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn morphs_between_segment_counts() {
+        let from = polygon_builder(&square(10.0)).build();
+        let mut triangle = vec![
+            Vec2::new(0.0, 20.0),
+            Vec2::new(20.0, -10.0),
+            Vec2::new(-20.0, -10.0),
+        ];
+        let to = polygon_builder(&triangle).build();
+        let mut morph = Morph::new(&from, &to, 1.0, Easing::Linear);
+        assert_eq!(morph.points().len(), MORPH_POINTS);
+        assert!(signed_area(&morph.points()) > 0.0);
+
+        morph.elapsed = 1.0;
+        assert!(morph.is_done());
+        // The clockwise triangle was turned around to match the square
+        triangle.reverse();
+        for point in morph.points() {
+            assert!(point.y >= -10.0 - 1e-4 && point.y <= 20.0 + 1e-4);
+        }
+        assert!(morph
+            .points()
+            .iter()
+            .any(|point| point.abs_diff_eq(triangle[0], 1.0)));
+    }
+
+    #[test]
+    fn easings_start_and_end_in_place() {
+        for easing in [
+            Easing::Linear,
+            Easing::QuadInOut,
+            Easing::CubicInOut,
+            Easing::SineInOut,
+        ] {
+            assert!(easing.ease(0.0).abs() < 1e-6);
+            assert!((easing.ease(1.0) - 1.0).abs() < 1e-6);
+            assert!((easing.ease(0.5) - 0.5).abs() < 1e-6);
+            assert!(easing.ease(0.25) <= easing.ease(0.3));
+        }
+    }
+
+    #[test]
+    fn morph_ends_on_the_target_path() {
+        let mut app = App::new();
+        app.init_resource::<SketchClock>().add_system(morph_paths);
+        let from = polygon_builder(&square(10.0)).build();
+        let mut to_builder = PathBuilder::new();
+        to_builder.move_to(Vec2::new(-10.0, 0.0));
+        to_builder.quadratic_bezier_to(Vec2::new(0.0, 30.0), Vec2::new(10.0, 0.0));
+        to_builder.close();
+        let to = to_builder.build();
+        let entity = app
+            .world
+            .spawn()
+            .insert(from.clone())
+            .insert(Morph::new(&from, &to, 1.0, Easing::SineInOut))
+            .id();
+
+        app.world.resource_mut::<SketchClock>().tick(0.5);
+        app.update();
+        let halfway = path_data(&app.world.get::<Path>(entity).unwrap().0);
+        assert_ne!(halfway, path_data(&from.0));
+        assert!(app.world.get::<Morph>(entity).is_some());
+
+        app.world.resource_mut::<SketchClock>().tick(0.6);
+        app.update();
+        assert_eq!(
+            path_data(&app.world.get::<Path>(entity).unwrap().0),
+            path_data(&to.0)
+        );
+        assert!(app.world.get::<Morph>(entity).is_none());
+    }
+}
@@ -0,0 +1,234 @@
+/*
+ * noise
+ *
+ * Value, Perlin and fractal (fBm) noise that gives the same values on the CPU and in shaders.
+ * `assets/shader_common/noise.wgsl` mirrors every function here line by line, on top of the same
+ * integer hash (PCG), so a sketch can place things with a `NoiseField` and shade them with the
+ * same field passed as `NoiseUniformData` (see `crate::shader_materials::core`). The shifty
+ * skyline does exactly that with its building heights and window lights.
+ *
+ * Unlike the `fract(sin(x) * k)` trick, the hash only uses integer operations, which give the same
+ * bits on every GPU. The noise on top of it only adds, multiplies and floors (gradients are picked
+ * from a fixed set rather than computed with `sin` and `cos`), so the two sides agree up to float
+ * rounding.
+ *
+ * Keep the constants and this file in sync with the WGSL one: the reference tables in the tests
+ * fail on any change to the hashing.
+ *
+ * Instructed by:
+ * https://www.jcgt.org/published/0009/03/02/
+ * https://mrl.cs.nyu.edu/~perlin/noise/
+ * https://thebookofshaders.com/13/
+ */
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+
+/// Octaves of `fbm` are capped, as loops in shaders should stay short
+pub const NOISE_MAX_OCTAVES: u32 = 8;
+const NOISE_LACUNARITY: f32 = 2.0;
+const NOISE_GAIN: f32 = 0.5;
+
+
+/// PCG hash of a 32-bit value
+pub fn hash(value: u32) -> u32 {
+    let state = value.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    return (word >> 22) ^ word;
+}
+
+
+/// Hash of a lattice cell for a seed
+pub fn hash2(cell: IVec2, seed: u32) -> u32 {
+    let inner = hash((cell.y as u32).wrapping_add(hash(seed)));
+    return hash((cell.x as u32).wrapping_add(inner));
+}
+
+
+/// A hash as a float from 0.0 (included) to 1.0 (excluded), exactly the same in shaders
+pub fn unit(hash: u32) -> f32 {
+    (hash >> 8) as f32 / 16777216.0
+}
+
+
+/// Quintic smoothstep, flat at both ends so noise has no creases at cell borders
+fn fade(t: Vec2) -> Vec2 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+
+/// One of 8 directions, along the axes or the diagonals
+fn gradient(hash: u32) -> Vec2 {
+    let sign = Vec2::new(
+        if hash & 1 != 0 { -1.0 } else { 1.0 },
+        if hash & 2 != 0 { -1.0 } else { 1.0 },
+    );
+    match (hash >> 2) & 3 {
+        1 => Vec2::new(sign.x, 0.0),
+        2 => Vec2::new(0.0, sign.y),
+        _ => sign * FRAC_1_SQRT_2,
+    }
+}
+
+
+/// Smoothly interpolated random values at the lattice points, from 0.0 to 1.0
+pub fn value_noise(point: Vec2, seed: u32) -> f32 {
+    let cell = point.floor();
+    let corner = cell.as_ivec2();
+    let t = fade(point - cell);
+    let at = |x: i32, y: i32| unit(hash2(corner + IVec2::new(x, y), seed));
+    let bottom = at(0, 0) + (at(1, 0) - at(0, 0)) * t.x;
+    let top = at(0, 1) + (at(1, 1) - at(0, 1)) * t.x;
+    return bottom + (top - bottom) * t.y;
+}
+
+
+/// Gradient noise, from about -1.0 to 1.0, and 0.0 at the lattice points
+pub fn perlin_noise(point: Vec2, seed: u32) -> f32 {
+    let cell = point.floor();
+    let corner = cell.as_ivec2();
+    let offset = point - cell;
+    let t = fade(offset);
+    let at = |x: i32, y: i32| {
+        let lattice = Vec2::new(x as f32, y as f32);
+        gradient(hash2(corner + IVec2::new(x, y), seed)).dot(offset - lattice)
+    };
+    let bottom = at(0, 0) + (at(1, 0) - at(0, 0)) * t.x;
+    let top = at(0, 1) + (at(1, 1) - at(0, 1)) * t.x;
+    return (bottom + (top - bottom) * t.y) * SQRT_2;
+}
+
+
+/// Fractal Brownian motion: `octaves` layers of Perlin noise (at least 1, at most
+/// `NOISE_MAX_OCTAVES`), each at twice the frequency and half the amplitude of the one before,
+/// from about -1.0 to 1.0
+pub fn fbm(point: Vec2, seed: u32, octaves: u32) -> f32 {
+    let octaves = octaves.clamp(1, NOISE_MAX_OCTAVES);
+    let mut sum = 0.0;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for octave in 0..octaves {
+        sum += perlin_noise(point * frequency, seed.wrapping_add(octave)) * amplitude;
+        total += amplitude;
+        amplitude *= NOISE_GAIN;
+        frequency *= NOISE_LACUNARITY;
+    }
+    return sum / total;
+}
+
+
+/// An fBm noise field over world space, sampled the same way by `noise_field` in shaders
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseField {
+    pub seed: u32,
+    /// Lattice cells per world unit, e.g. 0.01 for features about 100 pixels wide
+    pub frequency: f32,
+    pub octaves: u32,
+}
+
+
+impl Default for NoiseField {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            frequency: 0.01,
+            octaves: 4,
+        }
+    }
+}
+
+
+impl NoiseField {
+    /// A field with a seed from the sketch's generator (see `crate::rng`)
+    pub fn from_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            seed: rng.gen(),
+            ..default()
+        }
+    }
+
+    /// From about -1.0 to 1.0
+    pub fn sample(&self, point: Vec2) -> f32 {
+        fbm(point * self.frequency, self.seed, self.octaves)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u32 = 42;
+    const POINTS: [(f32, f32); 6] = [
+        (0.0, 0.0),
+        (0.5, 0.5),
+        (1.25, -3.75),
+        (-7.3, 2.1),
+        (100.6, 200.2),
+        (-0.1, -0.9),
+    ];
+
+    #[test]
+    fn hashes_match_reference() {
+        let hashes: Vec<u32> = [0, 1, 2, 42, u32::MAX].into_iter().map(hash).collect();
+        assert_eq!(hashes, REFERENCE_HASHES);
+        let cells: Vec<u32> = [(0, 0), (1, 0), (0, 1), (-1, -1), (1000, -1000)]
+            .into_iter()
+            .map(|(x, y)| hash2(IVec2::new(x, y), SEED))
+            .collect();
+        assert_eq!(cells, REFERENCE_CELL_HASHES);
+        assert_eq!(unit(0), 0.0);
+        assert!(unit(u32::MAX) < 1.0);
+    }
+
+    #[test]
+    fn noise_matches_reference() {
+        for (i, (x, y)) in POINTS.into_iter().enumerate() {
+            let point = Vec2::new(x, y);
+            let samples = [
+                value_noise(point, SEED),
+                perlin_noise(point, SEED),
+                fbm(point, SEED, 4),
+            ];
+            for (sample, expected) in samples.into_iter().zip(REFERENCE_NOISE[i]) {
+                assert!(
+                    (sample - expected).abs() < 1e-6,
+                    "{:?}: {:?} != {:?}",
+                    point,
+                    samples,
+                    REFERENCE_NOISE[i]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn noise_stays_in_range() {
+        for i in 0..2000 {
+            let point = Vec2::new(i as f32 * 0.137, (i * 7 % 331) as f32 * 0.291 - 40.0);
+            assert!((0.0..1.0).contains(&value_noise(point, SEED)));
+            assert!(perlin_noise(point, SEED).abs() <= 1.0);
+            assert!(fbm(point, SEED, 6).abs() <= 1.0);
+        }
+        // Zero at the lattice points, different for other seeds
+        assert_eq!(perlin_noise(Vec2::new(3.0, -2.0), SEED), 0.0);
+        let point = Vec2::new(0.3, 0.6);
+        assert_ne!(perlin_noise(point, SEED), perlin_noise(point, SEED + 1));
+    }
+
+    // Generated once from this file. They pin the hashing and the noise math, which
+    // assets/shader_common/noise.wgsl repeats: change them only together with it.
+    const REFERENCE_HASHES: [u32; 5] = [129708002, 2831084092, 2055130248, 1223963391, 3861530882];
+    const REFERENCE_CELL_HASHES: [u32; 5] =
+        [4004461565, 686191194, 34062253, 2386483931, 2373746616];
+    const REFERENCE_NOISE: [[f32; 3]; 6] = [
+        [0.9323613, 0.0, 0.0],
+        [0.35669956, -0.4267767, -0.22761424],
+        [0.21826078, 0.30655903, 0.25777906],
+        [0.21170117, 0.10182103, 0.22272937],
+        [0.7325331, 0.46704346, 0.10874383],
+        [0.363663, 0.18850772, -0.02560253],
+    ];
+}
@@ -0,0 +1,344 @@
+/*
+ * palette
+ *
+ * Generated color palettes, instead of hand-picking every `Color::rgb`. A `Palette` hands out
+ * colors by role (background, fill, stroke, accent), generated from a seed by one of the
+ * `PaletteKind` harmonies:
+ * - Analogous: neighbouring hues
+ * - Triadic: hues a third of the color wheel apart
+ * - Cosine: `a + b * cos(TAU * (c * t + d))` per channel, as in Inigo Quilez' palettes
+ *
+ * The fill, stroke and accent are kept apart from the background in lightness, so shapes stand out
+ * whatever the seed. Sketches take a `PaletteKind` and a seed in their params, with
+ * `PaletteKind::Custom` keeping their own hand-picked colors.
+ *
+ * Colors are mixed in Oklab (perceptually even, so halfway looks halfway and blue doesn't go
+ * purple on its way to white) or in HSL (around the color wheel). Shaders get palettes as an
+ * array of linear colors in role order, see `PaletteUniformData` in
+ * `crate::shader_materials::core` and `assets/shader_common/palette.wgsl`.
+ *
+ * Instructed by:
+ * https://iquilezles.org/articles/palettes/
+ * https://bottosson.github.io/posts/oklab/
+ * https://www.tigercolor.com/color-lab/color-theory/color-harmonies.htm
+ */
+use crate::rng::SketchRng;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+
+/// Colors in a palette, one per role
+pub const PALETTE_SIZE: usize = 4;
+/// Oklab lightness kept between the background and the other roles
+const MIN_CONTRAST: f32 = 0.3;
+
+
+/// How a palette is made, see the module docs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect_value]
+pub enum PaletteKind {
+    /// The sketch's own colors
+    Custom,
+    Analogous,
+    Triadic,
+    Cosine,
+}
+
+
+impl Default for PaletteKind {
+    fn default() -> Self {
+        Self::Custom
+    }
+}
+
+
+impl PaletteKind {
+    /// The palette for the seed, or `None` for `Custom`
+    pub fn generate(self, seed: u32) -> Option<Palette> {
+        // Its own generator, so the palette doesn't depend on what else the sketch generated
+        let mut rng = SketchRng::new(seed as u64);
+        let palette = match self {
+            Self::Custom => return None,
+            Self::Analogous => Palette::analogous(&mut rng),
+            Self::Triadic => Palette::triadic(&mut rng),
+            Self::Cosine => CosinePalette::random(&mut rng).palette(),
+        };
+        return Some(palette);
+    }
+}
+
+
+/// Colors by role
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    pub fill: Color,
+    pub stroke: Color,
+    pub accent: Color,
+}
+
+
+impl Palette {
+    /// Neighbouring hues, up to 40 degrees apart
+    pub fn analogous<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let hue = rng.gen_range(0.0..360.0);
+        let spread = rng.gen_range(20.0..40.0);
+        return Self::from_hues(rng, [hue, hue - spread, hue + spread]);
+    }
+
+    /// Hues evenly spread around the color wheel
+    pub fn triadic<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let hue = rng.gen_range(0.0..360.0);
+        return Self::from_hues(rng, [hue, hue + 120.0, hue + 240.0]);
+    }
+
+    /// Fill, stroke and accent of the hues (in degrees), on a dark or light background tinted
+    /// like the fill
+    fn from_hues<R: Rng + ?Sized>(rng: &mut R, hues: [f32; 3]) -> Self {
+        let [fill, stroke, accent] = hues.map(|hue| hue.rem_euclid(360.0));
+        let dark = rng.gen_bool(0.5);
+        let (background, contrast) = if dark {
+            (rng.gen_range(0.06..0.16), rng.gen_range(0.7..0.85))
+        } else {
+            (rng.gen_range(0.85..0.95), rng.gen_range(0.2..0.35))
+        };
+        let palette = Self {
+            background: Color::hsl(fill, rng.gen_range(0.15..0.4), background),
+            fill: Color::hsl(fill, rng.gen_range(0.4..0.7), rng.gen_range(0.4..0.6)),
+            stroke: Color::hsl(stroke, rng.gen_range(0.3..0.6), contrast),
+            accent: Color::hsl(accent, rng.gen_range(0.7..0.95), rng.gen_range(0.5..0.65)),
+        };
+        return palette.with_contrast();
+    }
+
+    /// The colors in role order, as in `assets/shader_common/palette.wgsl`
+    pub fn colors(&self) -> [Color; PALETTE_SIZE] {
+        [self.background, self.fill, self.stroke, self.accent]
+    }
+
+    /// From the fill (at 0.0) through the stroke to the accent (at 1.0), mixed in Oklab
+    pub fn gradient(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0) * 2.0;
+        if t <= 1.0 {
+            return mix_oklab(self.fill, self.stroke, t);
+        }
+        return mix_oklab(self.stroke, self.accent, t - 1.0);
+    }
+
+    /// Push the fill, stroke and accent away from the background in lightness
+    fn with_contrast(mut self) -> Self {
+        let background = oklab(self.background).x;
+        let lightness = if background < 0.5 {
+            background + MIN_CONTRAST
+        } else {
+            background - MIN_CONTRAST
+        };
+        for color in [&mut self.fill, &mut self.stroke, &mut self.accent] {
+            let mut lab = oklab(*color);
+            if (lab.x - background).abs() < MIN_CONTRAST {
+                lab.x = lightness;
+                *color = from_oklab(lab, color.a());
+            }
+        }
+        self
+    }
+}
+
+
+/// `a + b * cos(TAU * (c * t + d))` for each RGB channel, for t from 0.0 to 1.0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CosinePalette {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+    pub d: Vec3,
+}
+
+
+impl CosinePalette {
+    /// Balanced around mid grey, cycling once or so with a random phase per channel
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut channels = |low: f32, high: f32| {
+            Vec3::new(
+                rng.gen_range(low..high),
+                rng.gen_range(low..high),
+                rng.gen_range(low..high),
+            )
+        };
+        Self {
+            a: channels(0.4, 0.6),
+            b: channels(0.3, 0.5),
+            c: channels(0.5, 1.5),
+            d: channels(0.0, 1.0),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Color {
+        let phase = TAU * (self.c * t + self.d);
+        let wave = Vec3::new(phase.x.cos(), phase.y.cos(), phase.z.cos());
+        let rgb = (self.a + self.b * wave).clamp(Vec3::ZERO, Vec3::ONE);
+        Color::rgb(rgb.x, rgb.y, rgb.z)
+    }
+
+    /// Fill, stroke and accent along the curve, on a darkened start of it
+    pub fn palette(&self) -> Palette {
+        let palette = Palette {
+            background: mix_oklab(self.at(0.0), Color::BLACK, 0.75),
+            fill: self.at(0.3),
+            stroke: self.at(0.6),
+            accent: self.at(0.85),
+        };
+        return palette.with_contrast();
+    }
+}
+
+
+/// Oklab lightness, green/red and blue/yellow of the color
+// The matrices as published, rather than rounded to what f32 can hold
+#[allow(clippy::excessive_precision)]
+pub fn oklab(color: Color) -> Vec3 {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+    let l = (0.41222147 * r + 0.53633254 * g + 0.05144599 * b).cbrt();
+    let m = (0.21190350 * r + 0.68069955 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.62997870 * b).cbrt();
+    Vec3::new(
+        0.21045426 * l + 0.79361778 * m - 0.00407205 * s,
+        1.97799850 * l - 2.42859221 * m + 0.45059371 * s,
+        0.02590404 * l + 0.78277177 * m - 0.80867577 * s,
+    )
+}
+
+
+/// The color of the Oklab values, clamped into the sRGB gamut
+#[allow(clippy::excessive_precision)]
+pub fn from_oklab(lab: Vec3, alpha: f32) -> Color {
+    let l = (lab.x + 0.39633778 * lab.y + 0.21580376 * lab.z).powi(3);
+    let m = (lab.x - 0.10556135 * lab.y - 0.06385417 * lab.z).powi(3);
+    let s = (lab.x - 0.08948418 * lab.y - 1.29148555 * lab.z).powi(3);
+    let rgb = Vec3::new(
+        4.07674166 * l - 3.30771159 * m + 0.23096993 * s,
+        -1.26843800 * l + 2.60975740 * m - 0.34131940 * s,
+        -0.00419609 * l - 0.70341861 * m + 1.70761470 * s,
+    )
+    .clamp(Vec3::ZERO, Vec3::ONE);
+    Color::rgba_linear(rgb.x, rgb.y, rgb.z, alpha).as_rgba()
+}
+
+
+/// From `a` (at 0.0) to `b` (at 1.0) through Oklab
+pub fn mix_oklab(a: Color, b: Color, t: f32) -> Color {
+    let lab = oklab(a).lerp(oklab(b), t);
+    from_oklab(lab, a.a() + (b.a() - a.a()) * t)
+}
+
+
+/// From `a` (at 0.0) to `b` (at 1.0) through HSL, the short way around the color wheel
+pub fn mix_hsl(a: Color, b: Color, t: f32) -> Color {
+    let [hue_a, saturation_a, lightness_a, alpha_a] = a.as_hlsa_f32();
+    let [hue_b, saturation_b, lightness_b, alpha_b] = b.as_hlsa_f32();
+    let turn = (hue_b - hue_a + 180.0).rem_euclid(360.0) - 180.0;
+    let lerp = |from: f32, to: f32| from + (to - from) * t;
+    Color::hsla(
+        (hue_a + turn * t).rem_euclid(360.0),
+        lerp(saturation_a, saturation_b),
+        lerp(lightness_a, lightness_b),
+        lerp(alpha_a, alpha_b),
+    )
+    .as_rgba()
+}
+
+
+/// The color with another alpha, e.g. to give palette colors the alpha of hand-picked ones
+pub fn with_alpha(mut color: Color, alpha: f32) -> Color {
+    color.set_a(alpha);
+    color
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!(a.abs_diff_eq(b, 1e-3), "{} != {}", a, b);
+    }
+
+    fn assert_same_color(a: Color, b: Color) {
+        let [a, b] = [a, b].map(|color| Vec4::from(color.as_rgba_f32()));
+        assert!(a.abs_diff_eq(b, 1e-3), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn converts_to_oklab() {
+        // Reference values from https://bottosson.github.io/posts/oklab/
+        assert_close(oklab(Color::WHITE), Vec3::new(1.0, 0.0, 0.0));
+        assert_close(oklab(Color::BLACK), Vec3::ZERO);
+        assert_close(oklab(Color::RED), Vec3::new(0.62796, 0.22486, 0.12585));
+        assert_close(oklab(Color::BLUE), Vec3::new(0.45201, -0.03246, -0.31153));
+        for color in [
+            Color::rgb(0.2, 0.6, 0.9),
+            Color::ORANGE,
+            Color::MIDNIGHT_BLUE,
+        ] {
+            assert_same_color(from_oklab(oklab(color), 1.0), color);
+        }
+    }
+
+    #[test]
+    fn mixes_in_oklab_and_hsl() {
+        let grey = mix_oklab(Color::BLACK, Color::WHITE, 0.5);
+        assert!((oklab(grey).x - 0.5).abs() < 1e-3);
+        assert_same_color(mix_oklab(Color::RED, Color::BLUE, 0.0), Color::RED);
+        assert_same_color(mix_oklab(Color::RED, Color::BLUE, 1.0), Color::BLUE);
+        let translucent = Color::rgba(1.0, 1.0, 1.0, 0.0);
+        assert_eq!(mix_oklab(Color::BLACK, translucent, 0.25).a(), 0.75);
+
+        // Through red rather than around through green and blue
+        let halfway = mix_hsl(Color::hsl(350.0, 1.0, 0.5), Color::hsl(10.0, 1.0, 0.5), 0.5);
+        assert!(halfway.as_hlsa_f32()[0].min(360.0 - halfway.as_hlsa_f32()[0]) < 1.0);
+        let green = mix_hsl(Color::hsl(60.0, 1.0, 0.5), Color::hsl(180.0, 1.0, 0.5), 0.5);
+        assert!((green.as_hlsa_f32()[0] - 120.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn generated_palettes_stand_out_from_the_background() {
+        for kind in [
+            PaletteKind::Analogous,
+            PaletteKind::Triadic,
+            PaletteKind::Cosine,
+        ] {
+            for seed in 0..200 {
+                let palette = kind.generate(seed).unwrap();
+                let background = oklab(palette.background).x;
+                for color in [palette.fill, palette.stroke, palette.accent] {
+                    let contrast = (oklab(color).x - background).abs();
+                    assert!(contrast > MIN_CONTRAST - 0.05, "{:?} {}", kind, seed);
+                }
+                // The same seed gives the same palette
+                assert_eq!(kind.generate(seed), Some(palette));
+            }
+        }
+        assert_eq!(PaletteKind::Custom.generate(1), None);
+    }
+
+    #[test]
+    fn harmonies_keep_their_hues() {
+        let hue = |color: Color| color.as_hlsa_f32()[0];
+        let apart = |a: f32, b: f32| (b - a + 180.0).rem_euclid(360.0) - 180.0;
+        for seed in 0..50 {
+            let mut rng = SketchRng::new(seed);
+            let triadic = Palette::triadic(&mut rng);
+            // Unless lightened or darkened for contrast, which can shift hues a little
+            assert!((apart(hue(triadic.fill), hue(triadic.stroke)).abs() - 120.0).abs() < 10.0);
+            let analogous = Palette::analogous(&mut rng);
+            assert!(apart(hue(analogous.fill), hue(analogous.accent)).abs() < 50.0);
+        }
+
+        let palette = PaletteKind::Cosine.generate(3).unwrap();
+        assert_same_color(palette.gradient(0.0), palette.fill);
+        assert_same_color(palette.gradient(0.5), palette.stroke);
+        assert_same_color(palette.gradient(1.0), palette.accent);
+    }
+}
@@ -0,0 +1,524 @@
+/*
+ * params
+ *
+ * Sketch parameters that can be tweaked while the sketch runs, instead of rebuilding after every
+ * change to a const.
+ *
+ * A sketch keeps its tunables in a resource deriving `Reflect` (with the former const values in
+ * its `Default`), adds it with `SketchBuilder::params` and reads it in its systems. Systems that
+ * only use the params when drawing something once can be re-run on every change with
+ * `SketchBuilder::on_params_change`.
+ *
+ * Press `PARAMS_EDITOR_KEY` to show the on-canvas editor listing every parameter resource:
+ * - Up / Down selects a field
+ * - Left / Right decreases / increases it (hold Shift for finer steps), or toggles a bool.
+ *   Colors are edited one channel at a time.
+ * - `PARAMS_RESET_KEY` resets the field to its default
+ *
+ * Supported field types are f32, f64, bool, u8, u32, usize, i32 and Color. Other fields are shown
+ * but can't be edited.
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/reflection/reflection.rs
+ */
+use crate::gallery::PersistentEntity;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy::reflect::{GetTypeRegistration, ReflectMut, ReflectRef, Struct};
+use std::marker::PhantomData;
+
+
+pub const PARAMS_EDITOR_KEY: KeyCode = KeyCode::F3;
+pub const PARAMS_RESET_KEY: KeyCode = KeyCode::R;
+const PANEL_FONT: &str = "fonts/DejaVuSansMono.ttf";
+const PANEL_FONT_SIZE: f32 = 14.0;
+const PANEL_TEXT_CLR: Color = Color::rgb(0.9, 0.9, 0.9);
+const PANEL_SELECTED_CLR: Color = Color::rgb(1.0, 0.8, 0.3);
+const PANEL_BG_CLR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const PANEL_PADDING: f32 = 5.0;
+/// Relative step for floats, e.g. 0.1 changes a value by 10%
+const FLOAT_STEP: f64 = 0.1;
+/// Smallest step for floats, so values at (or near) zero can still change
+const FLOAT_MIN_STEP: f64 = 0.01;
+const COLOR_STEP: f32 = 0.05;
+/// Steps are divided by this while Shift is held
+const FINE_DIVISOR: f64 = 10.0;
+const COLOR_CHANNELS: [&str; 4] = ["r", "g", "b", "a"];
+
+
+/// Register a parameter resource with the editor (and insert its default if not inserted yet)
+pub struct ParamsPlugin<T>(PhantomData<T>);
+
+
+impl<T> Default for ParamsPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+
+impl<T: Reflect + GetTypeRegistration + Default> Plugin for ParamsPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.register_type::<T>().init_resource::<T>();
+
+        // The editor is shared by all parameter resources
+        if !app.world.contains_resource::<ParamsEditor>() {
+            app.init_resource::<ParamsEditor>()
+                .add_startup_system(spawn_params_panel)
+                .add_system(edit_params.exclusive_system());
+        }
+        app.world
+            .resource_mut::<ParamsEditor>()
+            .panels
+            .push(ParamsPanel::new::<T>());
+    }
+}
+
+
+/// Run criteria for systems that should re-run when the params change (but not when added)
+pub fn on_params_changed<T: Reflect>(params: Res<T>) -> ShouldRun {
+    if params.is_changed() && !params.is_added() {
+        return ShouldRun::Yes;
+    }
+    ShouldRun::No
+}
+
+
+/// Type-erased access to one parameter resource
+struct ParamsPanel {
+    name: &'static str,
+    get: fn(&World) -> &dyn Reflect,
+    // Only used when editing, since it flags the resource as changed
+    get_mut: fn(&mut World) -> &mut dyn Reflect,
+    default: fn() -> Box<dyn Reflect>,
+}
+
+
+impl ParamsPanel {
+    fn new<T: Reflect + Default>() -> Self {
+        Self {
+            name: short_type_name(std::any::type_name::<T>()),
+            get: |world| world.resource::<T>(),
+            get_mut: |world| world.resource_mut::<T>().into_inner(),
+            default: || Box::new(T::default()),
+        }
+    }
+}
+
+
+#[derive(Default)]
+pub struct ParamsEditor {
+    pub visible: bool,
+    panels: Vec<ParamsPanel>,
+    selected: usize,
+}
+
+
+#[derive(Component)]
+struct ParamsPanelNode;
+
+
+#[derive(Component)]
+struct ParamsPanelText;
+
+
+/// An editable row of the panel: a field of a panel, and a channel for colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Row {
+    panel: usize,
+    field: usize,
+    channel: Option<usize>,
+}
+
+
+fn short_type_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+
+fn as_struct(value: &dyn Reflect) -> Option<&dyn Struct> {
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => Some(value),
+        _ => None,
+    }
+}
+
+
+fn field_mut(value: &mut dyn Reflect, field: usize) -> Option<&mut dyn Reflect> {
+    match value.reflect_mut() {
+        ReflectMut::Struct(value) => value.field_at_mut(field),
+        _ => None,
+    }
+}
+
+
+/// Rows of a parameter resource, with one row per channel for colors
+fn rows_of(panel: usize, params: &dyn Reflect) -> Vec<Row> {
+    let mut rows = Vec::new();
+    if let Some(params) = as_struct(params) {
+        for (field, value) in params.iter_fields().enumerate() {
+            if value.is::<Color>() {
+                for channel in 0..COLOR_CHANNELS.len() {
+                    rows.push(Row {
+                        panel,
+                        field,
+                        channel: Some(channel),
+                    });
+                }
+            } else {
+                rows.push(Row {
+                    panel,
+                    field,
+                    channel: None,
+                });
+            }
+        }
+    }
+    return rows;
+}
+
+
+fn format_value(value: &dyn Reflect, channel: Option<usize>) -> String {
+    if let Some(v) = value.downcast_ref::<f32>() {
+        return format!("{:.3}", v);
+    }
+    if let Some(v) = value.downcast_ref::<f64>() {
+        return format!("{:.3}", v);
+    }
+    if let Some(v) = value.downcast_ref::<bool>() {
+        return v.to_string();
+    }
+    if let Some(v) = value.downcast_ref::<u8>() {
+        return v.to_string();
+    }
+    if let Some(v) = value.downcast_ref::<u32>() {
+        return v.to_string();
+    }
+    if let Some(v) = value.downcast_ref::<usize>() {
+        return v.to_string();
+    }
+    if let Some(v) = value.downcast_ref::<i32>() {
+        return v.to_string();
+    }
+    if let (Some(color), Some(channel)) = (value.downcast_ref::<Color>(), channel) {
+        return format!("{:.2}", color.as_rgba_f32()[channel]);
+    }
+    return String::from("(not editable)");
+}
+
+
+fn float_step(value: f64, fine: bool) -> f64 {
+    let step = (value.abs() * FLOAT_STEP).max(FLOAT_MIN_STEP);
+    if fine {
+        return step / FINE_DIVISOR;
+    }
+    step
+}
+
+
+/// Change a field value one step up (`up`) or down. Returns false for unsupported types.
+fn nudge_value(value: &mut dyn Reflect, channel: Option<usize>, up: bool, fine: bool) -> bool {
+    let sign = if up { 1.0 } else { -1.0 };
+    if let Some(v) = value.downcast_mut::<f32>() {
+        *v += (sign * float_step(*v as f64, fine)) as f32;
+    } else if let Some(v) = value.downcast_mut::<f64>() {
+        *v += sign * float_step(*v, fine);
+    } else if let Some(v) = value.downcast_mut::<bool>() {
+        *v = !*v;
+    } else if let Some(v) = value.downcast_mut::<u8>() {
+        *v = if up {
+            v.saturating_add(1)
+        } else {
+            v.saturating_sub(1)
+        };
+    } else if let Some(v) = value.downcast_mut::<u32>() {
+        *v = if up {
+            v.saturating_add(1)
+        } else {
+            v.saturating_sub(1)
+        };
+    } else if let Some(v) = value.downcast_mut::<usize>() {
+        *v = if up {
+            v.saturating_add(1)
+        } else {
+            v.saturating_sub(1)
+        };
+    } else if let Some(v) = value.downcast_mut::<i32>() {
+        *v = if up {
+            v.saturating_add(1)
+        } else {
+            v.saturating_sub(1)
+        };
+    } else if let (Some(color), Some(channel)) = (value.downcast_mut::<Color>(), channel) {
+        let step = if fine {
+            COLOR_STEP / FINE_DIVISOR as f32
+        } else {
+            COLOR_STEP
+        };
+        let mut rgba = color.as_rgba_f32();
+        rgba[channel] = (rgba[channel] + sign as f32 * step).clamp(0.0, 1.0);
+        *color = Color::rgba(rgba[0], rgba[1], rgba[2], rgba[3]);
+    } else {
+        return false;
+    }
+    true
+}
+
+
+fn spawn_params_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    ..default()
+                },
+                padding: Rect::all(Val::Px(PANEL_PADDING)),
+                ..default()
+            },
+            color: PANEL_BG_CLR.into(),
+            ..default()
+        })
+        .insert(ParamsPanelNode)
+        .insert(PersistentEntity)
+        .with_children(|parent| {
+            let style = TextStyle {
+                font: asset_server.load(PANEL_FONT),
+                font_size: PANEL_FONT_SIZE,
+                color: PANEL_TEXT_CLR,
+            };
+            // Sections: lines before the selected row, the selected row, lines after it
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: String::new(),
+                                style: style.clone(),
+                            },
+                            TextSection {
+                                value: String::new(),
+                                style: TextStyle {
+                                    color: PANEL_SELECTED_CLR,
+                                    ..style.clone()
+                                },
+                            },
+                            TextSection {
+                                value: String::new(),
+                                style: style,
+                            },
+                        ],
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(ParamsPanelText);
+        });
+}
+
+
+/// Exclusive, since the parameter resources are only known by type-erased accessors
+fn edit_params(world: &mut World) {
+    let keys = world.resource::<Input<KeyCode>>();
+    let toggle = keys.just_pressed(PARAMS_EDITOR_KEY);
+    let prev = keys.just_pressed(KeyCode::Up);
+    let next = keys.just_pressed(KeyCode::Down);
+    let decrease = keys.just_pressed(KeyCode::Left);
+    let increase = keys.just_pressed(KeyCode::Right);
+    let reset = keys.just_pressed(PARAMS_RESET_KEY);
+    let fine = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    world.resource_scope(|world, mut editor: Mut<ParamsEditor>| {
+        if toggle {
+            editor.visible = !editor.visible;
+            let display = if editor.visible {
+                Display::Flex
+            } else {
+                Display::None
+            };
+            let mut node_q = world.query_filtered::<&mut Style, With<ParamsPanelNode>>();
+            for mut style in node_q.iter_mut(world) {
+                style.display = display;
+            }
+        }
+        if !editor.visible {
+            return;
+        }
+
+        let rows: Vec<Row> = editor
+            .panels
+            .iter()
+            .enumerate()
+            .flat_map(|(i, panel)| rows_of(i, (panel.get)(world)))
+            .collect();
+        if rows.is_empty() {
+            return;
+        }
+        if prev {
+            editor.selected = (editor.selected + rows.len() - 1) % rows.len();
+        }
+        if next {
+            editor.selected = (editor.selected + 1) % rows.len();
+        }
+        editor.selected = editor.selected.min(rows.len() - 1);
+
+        let selected = rows[editor.selected];
+        let panel = &editor.panels[selected.panel];
+        if increase || decrease {
+            if let Some(value) = field_mut((panel.get_mut)(world), selected.field) {
+                nudge_value(value, selected.channel, increase, fine);
+            }
+        }
+        if reset {
+            let default = (panel.default)();
+            if let Some(default) = as_struct(&*default).and_then(|d| d.field_at(selected.field)) {
+                if let Some(value) = field_mut((panel.get_mut)(world), selected.field) {
+                    value.apply(default);
+                }
+            }
+        }
+
+        let mut before = vec![format!(
+            "up/down: select, left/right: change (shift: fine), {:?}: reset",
+            PARAMS_RESET_KEY
+        )];
+        let mut current = String::new();
+        let mut after = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let panel = &editor.panels[row.panel];
+            let params = (panel.get)(world);
+            let lines = if i < editor.selected {
+                &mut before
+            } else {
+                &mut after
+            };
+            if row.field == 0 && row.channel.unwrap_or(0) == 0 {
+                lines.push(format!("[{}]", panel.name));
+            }
+            let (name, value) = match as_struct(params) {
+                Some(params) => (
+                    params.name_at(row.field).unwrap_or_default(),
+                    params.field_at(row.field),
+                ),
+                None => continue,
+            };
+            let line = match (value, row.channel) {
+                (Some(value), Some(channel)) => format!(
+                    "  {}.{}: {}",
+                    name,
+                    COLOR_CHANNELS[channel],
+                    format_value(value, row.channel)
+                ),
+                (Some(value), None) => format!("  {}: {}", name, format_value(value, None)),
+                (None, _) => continue,
+            };
+            if i == editor.selected {
+                current = line;
+            } else {
+                lines.push(line);
+            }
+        }
+
+        let mut text_q = world.query_filtered::<&mut Text, With<ParamsPanelText>>();
+        for mut text in text_q.iter_mut(world) {
+            text.sections[0].value = before.join("\n") + "\n";
+            text.sections[1].value = current.clone();
+            text.sections[2].value = if after.is_empty() {
+                String::new()
+            } else {
+                String::from("\n") + &after.join("\n")
+            };
+        }
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reflect)]
+    struct TestParams {
+        size: f32,
+        count: u8,
+        enabled: bool,
+        color: Color,
+        name: String,
+    }
+
+    impl Default for TestParams {
+        fn default() -> Self {
+            Self {
+                size: 10.0,
+                count: 0,
+                enabled: false,
+                color: Color::rgba(0.5, 0.5, 0.5, 1.0),
+                name: String::from("test"),
+            }
+        }
+    }
+
+    #[test]
+    fn colors_get_a_row_per_channel() {
+        let params = TestParams::default();
+        let rows = rows_of(0, &params);
+        assert_eq!(rows.len(), 4 + 4);
+        assert_eq!(
+            rows[4],
+            Row {
+                panel: 0,
+                field: 3,
+                channel: Some(1)
+            }
+        );
+    }
+
+    #[test]
+    fn nudges_supported_types() {
+        let mut params = TestParams::default();
+        assert!(nudge_value(
+            field_mut(&mut params, 0).unwrap(),
+            None,
+            true,
+            false
+        ));
+        assert!((params.size - 11.0).abs() < 1e-5);
+        assert!(nudge_value(
+            field_mut(&mut params, 0).unwrap(),
+            None,
+            false,
+            true
+        ));
+        assert!((params.size - 10.89).abs() < 1e-5);
+
+        // Saturates instead of wrapping
+        nudge_value(field_mut(&mut params, 1).unwrap(), None, false, false);
+        assert_eq!(params.count, 0);
+
+        nudge_value(field_mut(&mut params, 2).unwrap(), None, true, false);
+        assert!(params.enabled);
+
+        nudge_value(field_mut(&mut params, 3).unwrap(), Some(3), true, false);
+        assert_eq!(params.color.a(), 1.0);
+        nudge_value(field_mut(&mut params, 3).unwrap(), Some(0), true, false);
+        assert!((params.color.r() - 0.55).abs() < 1e-5);
+
+        assert!(!nudge_value(
+            field_mut(&mut params, 4).unwrap(),
+            None,
+            true,
+            false
+        ));
+        assert_eq!(format_value(&params.name, None), "(not editable)");
+    }
+
+    #[test]
+    fn small_floats_still_move() {
+        let mut value = 0.0_f32;
+        nudge_value(&mut value, None, true, false);
+        assert!(value > 0.0);
+    }
+}
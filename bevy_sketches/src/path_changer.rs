@@ -1,13 +1,19 @@
-use crate::base::sketch;
-use crate::shapegen::random_polygon_builder;
-use bevy::core::FixedTimestep;
+use crate::base::SketchBuilder;
+use crate::morph::{Easing, Morph};
+use crate::rng::SketchRng;
+use crate::shapegen::ShapeKind;
+use crate::sketch::{Sketch, SketchParams};
+use crate::svg_import::SvgPath;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
 use bevy_web_extras::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /*
  * path_changer
  *
+ * Each change morphs the shape into the next one (see `crate::morph`) over `morph_duration`.
+ *
  * Instructed by:
  * https://github.com/Nilirad/bevy_prototype_lyon/blob/master/examples/path.rs
  * https://github.com/Nilirad/bevy_prototype_lyon/blob/master/examples/dynamic_shape.rs
@@ -16,50 +22,180 @@ use bevy_web_extras::prelude::*;
 
 pub const CHANGER_STEP: f64 = 0.5;
 pub const CHANGER_CLEAR_CLR: Color = Color::BLUE;
-const CHANGER_FILL_CLR: Color = Color::MIDNIGHT_BLUE;
-const CHANGER_STROKE_CLR: Color = Color::BLACK;
-const CHANGER_STROKE: f32 = 5.0;
-const CHANGER_MAX_SEGMENTS: u8 = 32;
+/// Hand-drawn shapes, shown in turn between random polygons
+const CHANGER_SVG_SHAPES: [&str; 5] = [
+    "shapes/heart.svg",
+    "shapes/star.svg",
+    "shapes/moon.svg",
+    "shapes/blob.svg",
+    "shapes/wave.svg",
+];
+
+
+/// Look of the sketch, editable at runtime (see `crate::params`) and kept as presets in
+/// `assets/presets/path_changer/` (see `crate::presets`)
+#[derive(Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangerParams {
+    pub fill_color: Color,
+    pub stroke_color: Color,
+    pub stroke: f32,
+    /// Kind of the random shapes (see `crate::shapegen`)
+    pub shape: ShapeKind,
+    /// Most vertices (or points, or tips) of the random shapes, at least 3
+    pub max_segments: u8,
+    /// Size of the SVG shapes relative to the window
+    pub svg_scale: f32,
+    /// Seconds to morph into the next shape, 0.0 to snap. Above `CHANGER_STEP`, shapes change
+    /// before they are reached.
+    pub morph_duration: f32,
+    pub easing: Easing,
+}
 
 
-pub fn path_changing_eg_setup(webcfg: Res<WebExtrasCfg>, mut commands: Commands) {
-    let path_builder = random_polygon_builder(webcfg.max_x, webcfg.max_y, CHANGER_MAX_SEGMENTS);
+impl Default for ChangerParams {
+    fn default() -> Self {
+        Self {
+            fill_color: Color::MIDNIGHT_BLUE,
+            stroke_color: Color::BLACK,
+            stroke: 5.0,
+            shape: ShapeKind::Polygon,
+            max_segments: 32,
+            svg_scale: 0.8,
+            morph_duration: 0.4,
+            easing: Easing::CubicInOut,
+        }
+    }
+}
+
+
+pub struct ChangerShapes {
+    handles: Vec<Handle<SvgPath>>,
+    /// Counts changes, every other change shows the next SVG shape
+    changes: usize,
+}
+
+
+pub fn path_changing_eg_setup(
+    webcfg: Res<WebExtrasCfg>,
+    asset_server: Res<AssetServer>,
+    params: Res<ChangerParams>,
+    mut rng: ResMut<SketchRng>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(ChangerShapes {
+        handles: CHANGER_SVG_SHAPES
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect(),
+        changes: 0,
+    });
+
+    let path_builder = params.shape.generator(params.max_segments).builder(
+        rng.stream("changer shapes"),
+        webcfg.max_x,
+        webcfg.max_y,
+    );
     let shape = path_builder.build();
 
     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
     commands.spawn_bundle(GeometryBuilder::build_as(
         &shape,
         // DrawMode::Stroke(StrokeMode::new(Color::BLACK, 10.0)),
-        DrawMode::Outlined {
-            fill_mode: FillMode::color(CHANGER_FILL_CLR),
-            outline_mode: StrokeMode::new(CHANGER_STROKE_CLR, CHANGER_STROKE),
-        },
+        changer_draw_mode(&params),
         Transform::default(),
     ));
 }
 
 
-pub fn path_changer(webcfg: Res<WebExtrasCfg>, mut query: Query<&mut Path>) {
-    let path_builder = random_polygon_builder(webcfg.max_x, webcfg.max_y, CHANGER_MAX_SEGMENTS);
+fn changer_draw_mode(params: &ChangerParams) -> DrawMode {
+    DrawMode::Outlined {
+        fill_mode: FillMode::color(params.fill_color),
+        outline_mode: StrokeMode::new(params.stroke_color, params.stroke),
+    }
+}
 
-    let new_shape = path_builder.build();
 
-    let mut path = query.iter_mut().next().unwrap();
-    *path = ShapePath::build_as(&new_shape);
+/// Apply edited colors and stroke
+fn restyle_changer(params: Res<ChangerParams>, mut query: Query<&mut DrawMode>) {
+    for mut draw_mode in query.iter_mut() {
+        *draw_mode = changer_draw_mode(&params);
+    }
 }
 
 
-pub fn app() {
-    let webcfg = WebExtrasCfg {
-        title: String::from("path changer example"),
-        match_element: Some(String::from("content")),
-        match_clear_color: true,
-        ..Default::default()
+pub fn path_changer(
+    webcfg: Res<WebExtrasCfg>,
+    params: Res<ChangerParams>,
+    svg_paths: Res<Assets<SvgPath>>,
+    mut shapes: ResMut<ChangerShapes>,
+    mut rng: ResMut<SketchRng>,
+    mut commands: Commands,
+    query: Query<(Entity, &Path)>,
+) {
+    shapes.changes += 1;
+    let handle = &shapes.handles[(shapes.changes / 2) % shapes.handles.len()];
+    // Falls back to a random shape while the SVG is loading, or if it failed to load
+    let svg_path = svg_paths.get(handle).filter(|_| shapes.changes % 2 == 0);
+
+    let path_builder = match svg_path {
+        Some(svg_path) => svg_path.builder(Vec2::splat(
+            webcfg.max_x.min(webcfg.max_y) * params.svg_scale,
+        )),
+        None => params.shape.generator(params.max_segments).builder(
+            rng.stream("changer shapes"),
+            webcfg.max_x,
+            webcfg.max_y,
+        ),
     };
-    let mut app = sketch(webcfg);
-    app.insert_resource(ClearColor(CHANGER_CLEAR_CLR))
-        .add_plugin(ShapePlugin)
-        .add_startup_system(path_changing_eg_setup)
-        .add_system(path_changer.with_run_criteria(FixedTimestep::step(CHANGER_STEP)))
-        .run();
+
+    let new_shape = path_builder.build();
+
+    // Restarts from wherever an unfinished morph got to
+    let Some((entity, path)) = query.iter().next() else {
+        return;
+    };
+    commands.entity(entity).insert(Morph::new(
+        path,
+        &new_shape,
+        params.morph_duration,
+        params.easing,
+    ));
+}
+
+
+pub struct PathChangerSketch;
+
+
+impl Sketch for PathChangerSketch {
+    fn name(&self) -> &'static str {
+        "path_changer"
+    }
+
+    fn title(&self) -> &'static str {
+        "path changer example"
+    }
+
+    fn description(&self) -> &'static str {
+        "A shape that keeps morphing between random shapes and hand-drawn SVG shapes"
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["lyon"]
+    }
+
+    fn params(&self) -> Option<SketchParams> {
+        Some(SketchParams::of::<ChangerParams>("ChangerParams"))
+    }
+
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        sketch
+            .clear_color(CHANGER_CLEAR_CLR)
+            .lyon()
+            .params::<ChangerParams>()
+            .presets::<ChangerParams>("path_changer")
+            .startup_system(path_changing_eg_setup)
+            .on_params_change::<ChangerParams, _>(restyle_changer)
+            .fixed_system(path_changer, CHANGER_STEP)
+    }
 }
@@ -0,0 +1,256 @@
+/*
+ * perf_overlay
+ *
+ * An on-canvas overlay showing FPS, a frame time graph, entity count, mesh and shader material
+ * asset counts, the current window/canvas size, the random seed and the sketch clock. Meant for
+ * devices where the log is hard to get at (like a phone debugged through Chrome remote
+ * inspection).
+ *
+ * Needs a UI camera, which `SketchBuilder` spawns.
+ *
+ * Toggle it with `PERF_OVERLAY_KEY`, or show it from the start with the `overlay` runtime option
+ * (e.g. `?overlay=1`, see `crate::runtime_opts`).
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/ui/text.rs
+ */
+use crate::gallery::PersistentEntity;
+use crate::rng::SketchRng;
+use crate::runtime_opts::{get_flag, OVERLAY_OPT};
+use crate::shader_materials::core::ShaderMaterialCounts;
+use crate::sketch_clock::SketchClock;
+use bevy::diagnostic::{Diagnostics, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_web_extras::prelude::*;
+use std::collections::VecDeque;
+
+
+pub const PERF_OVERLAY_KEY: KeyCode = KeyCode::F2;
+const OVERLAY_FONT: &str = "fonts/DejaVuSansMono.ttf";
+const OVERLAY_FONT_SIZE: f32 = 14.0;
+const OVERLAY_TEXT_CLR: Color = Color::rgb(0.9, 0.9, 0.9);
+const OVERLAY_BG_CLR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const OVERLAY_PADDING: f32 = 5.0;
+const GRAPH_BAR_COUNT: usize = 60;
+const GRAPH_BAR_WIDTH: f32 = 3.0;
+const GRAPH_HEIGHT: f32 = 50.0;
+/// Frame time (in ms) that fills the graph height. 33ms is roughly 30fps.
+const GRAPH_MAX_FRAME_MS: f32 = 33.3;
+const GRAPH_OK_CLR: Color = Color::rgb(0.3, 0.8, 0.3);
+/// Frames slower than 60fps are drawn in this color
+const GRAPH_SLOW_CLR: Color = Color::rgb(0.9, 0.3, 0.2);
+const GRAPH_SLOW_FRAME_MS: f32 = 1000.0 / 60.0;
+
+
+pub struct PerfOverlayPlugin;
+
+
+/// Also adds `FrameTimeDiagnosticsPlugin`, so don't add that one separately
+impl Plugin for PerfOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin::default())
+            .add_plugin(EntityCountDiagnosticsPlugin)
+            .init_resource::<ShaderMaterialCounts>()
+            .insert_resource(PerfOverlayState {
+                visible: get_flag(OVERLAY_OPT).unwrap_or(false),
+                frame_times: VecDeque::with_capacity(GRAPH_BAR_COUNT),
+            })
+            .add_system(toggle_perf_overlay)
+            .add_system(show_perf_overlay.after(toggle_perf_overlay))
+            .add_system(record_frame_time)
+            .add_system(update_perf_overlay.after(record_frame_time));
+    }
+}
+
+
+pub struct PerfOverlayState {
+    pub visible: bool,
+    /// Most recent frame times in ms, oldest first
+    frame_times: VecDeque<f32>,
+}
+
+
+#[derive(Component)]
+pub struct PerfOverlay;
+
+
+#[derive(Component)]
+struct PerfOverlayText;
+
+
+#[derive(Component)]
+struct FrameTimeBar(usize);
+
+
+fn toggle_perf_overlay(keys: Res<Input<KeyCode>>, mut state: ResMut<PerfOverlayState>) {
+    if keys.just_pressed(PERF_OVERLAY_KEY) {
+        state.visible = !state.visible;
+    }
+}
+
+
+/// Spawn or despawn the overlay to match `PerfOverlayState.visible`
+fn show_perf_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<PerfOverlayState>,
+    overlay_q: Query<Entity, With<PerfOverlay>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in overlay_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !state.visible {
+        return;
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    ..default()
+                },
+                padding: Rect::all(Val::Px(OVERLAY_PADDING)),
+                // UI is y-up in this version of Bevy, so `ColumnReverse` stacks top to bottom
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            color: OVERLAY_BG_CLR.into(),
+            ..default()
+        })
+        .insert(PerfOverlay)
+        .insert(PersistentEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load(OVERLAY_FONT),
+                            font_size: OVERLAY_FONT_SIZE,
+                            color: OVERLAY_TEXT_CLR,
+                        },
+                        TextAlignment::default(),
+                    ),
+                    ..default()
+                })
+                .insert(PerfOverlayText);
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(
+                            Val::Px(GRAPH_BAR_WIDTH * GRAPH_BAR_COUNT as f32),
+                            Val::Px(GRAPH_HEIGHT),
+                        ),
+                        margin: Rect {
+                            top: Val::Px(OVERLAY_PADDING),
+                            ..default()
+                        },
+                        // Bars grow from the bottom of the graph (see y-up note above)
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for i in 0..GRAPH_BAR_COUNT {
+                        graph
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(GRAPH_BAR_WIDTH), Val::Px(0.0)),
+                                    ..default()
+                                },
+                                color: GRAPH_OK_CLR.into(),
+                                ..default()
+                            })
+                            .insert(FrameTimeBar(i));
+                    }
+                });
+        });
+}
+
+
+fn record_frame_time(time: Res<Time>, mut state: ResMut<PerfOverlayState>) {
+    if state.frame_times.len() == GRAPH_BAR_COUNT {
+        state.frame_times.pop_front();
+    }
+    state.frame_times.push_back(time.delta_seconds() * 1000.0);
+}
+
+
+fn update_perf_overlay(
+    diagnostics: Res<Diagnostics>,
+    state: Res<PerfOverlayState>,
+    mesh_assets: Res<Assets<Mesh>>,
+    material_counts: Res<ShaderMaterialCounts>,
+    windows: Res<Windows>,
+    webcfg: Res<WebExtrasCfg>,
+    rng: Res<SketchRng>,
+    clock: Res<SketchClock>,
+    mut text_q: Query<&mut Text, With<PerfOverlayText>>,
+    mut bar_q: Query<(&FrameTimeBar, &mut Style, &mut UiColor)>,
+) {
+    if !state.visible {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.average())
+        .unwrap_or_default();
+    let entities = diagnostics
+        .get(EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(|count| count.value())
+        .unwrap_or_default();
+    let (window_w, window_h) = windows
+        .get_primary()
+        .map_or((0.0, 0.0), |w| (w.width(), w.height()));
+
+    let mut lines = vec![
+        format!("fps: {:.1}", fps),
+        format!("entities: {}", entities),
+        format!("meshes: {}", mesh_assets.len()),
+    ];
+    let mut materials: Vec<_> = material_counts.0.iter().collect();
+    materials.sort();
+    for (name, count) in materials {
+        lines.push(format!("{}: {}", name, count));
+    }
+    lines.push(format!("window: {:.0}x{:.0}", window_w, window_h));
+    lines.push(format!("canvas: {:.0}x{:.0}", webcfg.width, webcfg.height));
+    lines.push(format!("seed: {}", rng.seed()));
+    lines.push(format!(
+        "clock: {:.1}s x{}{}",
+        clock.seconds_since_startup(),
+        clock.speed(),
+        if clock.is_paused() { " (paused)" } else { "" }
+    ));
+
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+
+    // Right-align the history so the newest frame is always the last bar
+    let offset = GRAPH_BAR_COUNT - state.frame_times.len();
+    for (bar, mut style, mut color) in bar_q.iter_mut() {
+        let frame_ms = if bar.0 >= offset {
+            state.frame_times[bar.0 - offset]
+        } else {
+            0.0
+        };
+        let height = (frame_ms / GRAPH_MAX_FRAME_MS).min(1.0) * GRAPH_HEIGHT;
+        style.size.height = Val::Px(height);
+        *color = if frame_ms > GRAPH_SLOW_FRAME_MS {
+            GRAPH_SLOW_CLR.into()
+        } else {
+            GRAPH_OK_CLR.into()
+        };
+    }
+}
@@ -0,0 +1,323 @@
+/*
+ * placement
+ *
+ * Where to put things on the canvas without them overlapping. `Placer` packs circles of given
+ * radii into a rectangle (by default the whole canvas, from `WebExtrasCfg`) with Poisson-disk
+ * sampling: each new circle is tried at random around one already placed, just out of its reach,
+ * so the circles spread out evenly and densely however big the canvas is. Candidates are also
+ * rejected if they leave an optional region polygon or touch an obstacle (polygons, or circles
+ * already on the canvas).
+ *
+ * All randomness comes from the sketch's seeded generator (see `crate::rng`), so a seed recreates
+ * the same placement.
+ *
+ * ```ignore
+ * let positions = Placer::from_webcfg(&webcfg).spacing(10.0).place(&mut *rng, &[40.0; 12]);
+ * ```
+ *
+ * Instructed by:
+ * https://www.cs.ubc.ca/~rbridson/docs/bridson-siggraph07-poissondisk.pdf
+ * https://www.jasondavies.com/poisson-disc/
+ */
+use crate::geometry::{contains, outline_distance, Bounds};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_web_extras::prelude::*;
+use rand::Rng;
+use std::f32::consts::TAU;
+
+
+/// Candidates tried around a placed circle before giving up on room next to it
+const PLACEMENT_ATTEMPTS: usize = 30;
+/// Candidates tried anywhere in the bounds for the first circle
+const FIRST_PLACEMENT_ATTEMPTS: usize = 1000;
+
+
+/// Packs circles into a rectangle, see the module docs
+#[derive(Clone, Debug)]
+pub struct Placer {
+    bounds: Bounds,
+    spacing: f32,
+    region: Option<Vec<Vec2>>,
+    obstacles: Vec<Vec<Vec2>>,
+    taken: Vec<(Vec2, f32)>,
+}
+
+
+impl Placer {
+    /// Circles inside `bounds`
+    pub fn new(bounds: Bounds) -> Self {
+        Self {
+            bounds: bounds,
+            spacing: 0.0,
+            region: None,
+            obstacles: Vec::new(),
+            taken: Vec::new(),
+        }
+    }
+
+    /// Circles inside the canvas
+    pub fn from_webcfg(webcfg: &WebExtrasCfg) -> Self {
+        return Self::new(Bounds {
+            min: Vec2::new(-webcfg.max_x, -webcfg.max_y),
+            max: Vec2::new(webcfg.max_x, webcfg.max_y),
+        });
+    }
+
+    /// Gap left between circles (and between circles and obstacles)
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing.max(0.0);
+        self
+    }
+
+    /// Keep the circles inside the polygon too
+    pub fn within(mut self, region: Vec<Vec2>) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// Keep the circles off the polygon
+    pub fn avoid(mut self, obstacle: Vec<Vec2>) -> Self {
+        self.obstacles.push(obstacle);
+        self
+    }
+
+    /// Keep the circles off circles already on the canvas
+    pub fn avoid_circles(mut self, circles: impl IntoIterator<Item = (Vec2, f32)>) -> Self {
+        self.taken.extend(circles);
+        self
+    }
+
+    /// Centers for circles of the radii, in order, for as many of them as fit. Put the largest
+    /// radii first: placement stops at the first circle that finds no room.
+    pub fn place<R: Rng + ?Sized>(&self, rng: &mut R, radii: &[f32]) -> Vec<Vec2> {
+        let largest = radii
+            .iter()
+            .chain(self.taken.iter().map(|(_, radius)| radius))
+            .fold(0.0f32, |largest, radius| largest.max(*radius));
+        let mut grid = Grid::new(largest * 2.0 + self.spacing);
+        for (center, radius) in self.taken.iter() {
+            grid.insert(*center, *radius);
+        }
+        // Placed circles that may still have room around them
+        let mut active: Vec<(Vec2, f32)> = Vec::new();
+        let mut positions = Vec::with_capacity(radii.len());
+
+        for radius in radii.iter().map(|radius| radius.max(0.0)) {
+            let mut placed = None;
+            if active.is_empty() {
+                placed = (0..FIRST_PLACEMENT_ATTEMPTS)
+                    .map(|_| self.random_center(rng, radius))
+                    .find(|center| self.fits(&grid, *center, radius));
+            }
+            while placed.is_none() && !active.is_empty() {
+                let index = rng.gen_range(0..active.len());
+                let (around, around_radius) = active[index];
+                // From touching (with the spacing) to twice as far
+                let reach = around_radius + radius + self.spacing;
+                placed = (0..PLACEMENT_ATTEMPTS)
+                    .map(|_| {
+                        let angle = rng.gen_range(0.0..TAU);
+                        // Uniform over the ring's area
+                        let distance = rng.gen_range(1.0..4.0f32).sqrt() * reach.max(f32::EPSILON);
+                        around + Vec2::new(angle.cos(), angle.sin()) * distance
+                    })
+                    .find(|center| self.fits(&grid, *center, radius));
+                if placed.is_none() {
+                    active.swap_remove(index);
+                }
+            }
+            match placed {
+                Some(center) => {
+                    grid.insert(center, radius);
+                    active.push((center, radius));
+                    positions.push(center);
+                }
+                None => break,
+            }
+        }
+        return positions;
+    }
+
+    /// Centers for as many circles of `radius` as fit
+    pub fn fill<R: Rng + ?Sized>(&self, rng: &mut R, radius: f32) -> Vec<Vec2> {
+        let size = self.bounds.size().max(Vec2::ZERO);
+        let footprint = radius.max(f32::EPSILON).powi(2) * std::f32::consts::PI;
+        // No more circles than would cover the bounds
+        let most = ((size.x * size.y) / footprint).ceil() as usize + 1;
+        return self.place(rng, &vec![radius; most]);
+    }
+
+    fn random_center<R: Rng + ?Sized>(&self, rng: &mut R, radius: f32) -> Vec2 {
+        let (min, max) = (self.bounds.min + radius, self.bounds.max - radius);
+        let pick = |rng: &mut R, low: f32, high: f32| {
+            if low < high {
+                rng.gen_range(low..high)
+            } else {
+                (low + high) / 2.0
+            }
+        };
+        return Vec2::new(pick(rng, min.x, max.x), pick(rng, min.y, max.y));
+    }
+
+    fn fits(&self, grid: &Grid, center: Vec2, radius: f32) -> bool {
+        let inside = Bounds {
+            min: self.bounds.min + radius,
+            max: self.bounds.max - radius,
+        };
+        if !inside.contains(center) {
+            return false;
+        }
+        if let Some(region) = &self.region {
+            if !contains(region, center) || outline_distance(region, center) < radius {
+                return false;
+            }
+        }
+        for obstacle in self.obstacles.iter() {
+            let clearance = radius + self.spacing;
+            if contains(obstacle, center) || outline_distance(obstacle, center) < clearance {
+                return false;
+            }
+        }
+        return !grid.overlaps(center, radius, self.spacing);
+    }
+}
+
+
+/// Placed circles bucketed by cells at least as wide as the reach between two circles, so only
+/// the neighbouring cells need checking
+struct Grid {
+    cell: f32,
+    circles: HashMap<(i32, i32), Vec<(Vec2, f32)>>,
+}
+
+
+impl Grid {
+    fn new(cell: f32) -> Self {
+        Self {
+            cell: cell.max(f32::EPSILON),
+            circles: HashMap::default(),
+        }
+    }
+
+    fn key(&self, point: Vec2) -> (i32, i32) {
+        let cell = (point / self.cell).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    fn insert(&mut self, center: Vec2, radius: f32) {
+        let key = self.key(center);
+        self.circles.entry(key).or_default().push((center, radius));
+    }
+
+    fn overlaps(&self, center: Vec2, radius: f32, spacing: f32) -> bool {
+        let (x, y) = self.key(center);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let circles = match self.circles.get(&(x + dx, y + dy)) {
+                    Some(circles) => circles,
+                    None => continue,
+                };
+                for (other, other_radius) in circles {
+                    if center.distance(*other) < radius + other_radius + spacing {
+                        return true;
+                    }
+                }
+            }
+        }
+        return false;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SketchRng;
+
+    fn assert_apart(positions: &[Vec2], radii: &[f32], spacing: f32) {
+        for i in 0..positions.len() {
+            for j in (i + 1)..positions.len() {
+                let distance = positions[i].distance(positions[j]);
+                assert!(distance >= radii[i] + radii[j] + spacing - 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn fills_any_canvas_densely() {
+        let mut rng = SketchRng::new(5);
+        for (max_x, max_y) in [(640.0, 360.0), (180.0, 400.0), (2000.0, 50.0)] {
+            let webcfg = WebExtrasCfg {
+                max_x: max_x,
+                max_y: max_y,
+                ..Default::default()
+            };
+            let positions = Placer::from_webcfg(&webcfg).fill(&mut rng, 20.0);
+            assert_apart(&positions, &vec![20.0; positions.len()], 0.0);
+            for position in &positions {
+                assert!(position.x.abs() <= max_x - 20.0 && position.y.abs() <= max_y - 20.0);
+            }
+            // Poisson-disk sampling covers a good part of the canvas
+            let covered = positions.len() as f32 * std::f32::consts::PI * 400.0;
+            let canvas = (max_x - 20.0) * (max_y - 20.0) * 4.0;
+            assert!(covered / canvas > 0.3, "{} circles", positions.len());
+        }
+    }
+
+    #[test]
+    fn places_mixed_radii_around_obstacles() {
+        let radii = [60.0, 40.0, 40.0, 20.0, 20.0, 20.0, 10.0, 10.0];
+        let obstacle = vec![
+            Vec2::new(-50.0, -50.0),
+            Vec2::new(50.0, -50.0),
+            Vec2::new(50.0, 50.0),
+            Vec2::new(-50.0, 50.0),
+        ];
+        let placer = Placer::new(Bounds {
+            min: Vec2::new(-400.0, -300.0),
+            max: Vec2::new(400.0, 300.0),
+        })
+        .spacing(5.0)
+        .avoid(obstacle.clone())
+        .avoid_circles([(Vec2::new(200.0, 0.0), 50.0)]);
+        let positions = placer.place(&mut SketchRng::new(9), &radii);
+        assert_eq!(positions.len(), radii.len());
+        assert_apart(&positions, &radii, 5.0);
+        for (position, radius) in positions.iter().zip(radii) {
+            assert!(!contains(&obstacle, *position));
+            assert!(outline_distance(&obstacle, *position) >= radius + 5.0);
+            assert!(position.distance(Vec2::new(200.0, 0.0)) >= radius + 55.0);
+        }
+
+        // The same seed places the same way
+        assert_eq!(placer.place(&mut SketchRng::new(9), &radii), positions);
+    }
+
+    #[test]
+    fn stays_within_a_region() {
+        let triangle = vec![
+            Vec2::new(-300.0, -200.0),
+            Vec2::new(300.0, -200.0),
+            Vec2::new(0.0, 250.0),
+        ];
+        let placer = Placer::new(Bounds {
+            min: Vec2::splat(-400.0),
+            max: Vec2::splat(400.0),
+        })
+        .within(triangle.clone());
+        let positions = placer.fill(&mut SketchRng::new(1), 15.0);
+        assert!(positions.len() > 20);
+        for position in &positions {
+            assert!(contains(&triangle, *position));
+            assert!(outline_distance(&triangle, *position) >= 15.0);
+        }
+
+        // Nothing fits in a canvas smaller than the circle
+        let tiny = Placer::new(Bounds {
+            min: Vec2::ZERO,
+            max: Vec2::splat(10.0),
+        });
+        assert!(tiny.place(&mut SketchRng::new(1), &[20.0]).is_empty());
+    }
+}
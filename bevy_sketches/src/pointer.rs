@@ -0,0 +1,120 @@
+/*
+ * pointer
+ *
+ * The mouse cursor or the first touch as a `Pointer` resource, in world space: a sketch's
+ * systems read it to react to the pointer, and the shader materials get it in the common uniform
+ * (see `crate::shader_materials::core`), normalized to each `DisplayQuad`.
+ *
+ * World space assumes the sketches' 2d camera: at the origin and not zoomed, so a world unit is
+ * a pixel of the canvas and (0, 0) is its center.
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/input/mouse_input.rs
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/input/touch_input.rs
+ * https://bevy-cheatbook.github.io/cookbook/cursor2world.html
+ */
+use bevy::prelude::*;
+
+
+pub struct PointerPlugin;
+
+
+impl Plugin for PointerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pointer>()
+            .add_system_to_stage(CoreStage::PreUpdate, track_pointer);
+    }
+}
+
+
+/// Where the pointer is, in world space
+#[derive(Default, Clone, Debug)]
+pub struct Pointer {
+    /// Last known position, kept when the cursor leaves the window or the touch ends
+    pub position: Vec2,
+    /// Whether the cursor is in the window, or a finger is on the screen
+    pub active: bool,
+    /// The left mouse button is held, or a finger is on the screen
+    pub pressed: bool,
+    pub just_pressed: bool,
+    /// Position of the last click or tap, if there was one
+    pub last_click: Option<Vec2>,
+}
+
+
+/// World position of a window position (origin at the bottom left, y up)
+pub fn window_to_world(position: Vec2, window_size: Vec2) -> Vec2 {
+    position - window_size / 2.0
+}
+
+
+/// A world position relative to a quad of `transform` (with the unit quad mesh), from (0, 0) at
+/// its bottom left to (1, 1) at its top right. Outside of the quad, values go beyond that range.
+pub fn quad_uv(world: Vec2, transform: &Transform) -> Vec2 {
+    let center = transform.translation.truncate();
+    let size = transform.scale.truncate();
+    return (world - center) / size + Vec2::splat(0.5);
+}
+
+
+fn track_pointer(
+    windows: Res<Windows>,
+    buttons: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut pointer: ResMut<Pointer>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    pointer.just_pressed = false;
+    if let Some(touch) = touches.iter().next() {
+        let mut position = touch.position();
+        // Unlike the cursor, browser touches have their origin at the top left (bevy_winit only
+        // flips them on android and ios)
+        if cfg!(target_arch = "wasm32") {
+            position.y = window_size.y - position.y;
+        }
+        pointer.position = window_to_world(position, window_size);
+        pointer.active = true;
+        pointer.pressed = true;
+        pointer.just_pressed = touches.just_pressed(touch.id());
+    } else if let Some(position) = window.cursor_position() {
+        pointer.position = window_to_world(position, window_size);
+        pointer.active = true;
+        pointer.pressed = buttons.pressed(MouseButton::Left);
+        pointer.just_pressed = buttons.just_pressed(MouseButton::Left);
+    } else {
+        pointer.active = false;
+        pointer.pressed = false;
+    }
+    if pointer.just_pressed {
+        pointer.last_click = Some(pointer.position);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_positions_to_quads() {
+        let world = window_to_world(Vec2::new(600.0, 100.0), Vec2::new(800.0, 600.0));
+        assert_eq!(world, Vec2::new(200.0, -200.0));
+
+        // A quad covering the right half of the canvas
+        let quad = Transform {
+            translation: Vec3::new(200.0, 0.0, 0.0),
+            scale: Vec3::new(400.0, 600.0, 1.0),
+            ..default()
+        };
+        assert!(quad_uv(world, &quad).abs_diff_eq(Vec2::new(0.5, 1.0 / 6.0), 1e-6));
+        assert_eq!(
+            quad_uv(Vec2::new(-200.0, 300.0), &quad),
+            Vec2::new(-0.5, 1.0)
+        );
+    }
+}
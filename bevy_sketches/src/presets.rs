@@ -0,0 +1,429 @@
+/*
+ * presets
+ *
+ * Named sets of sketch parameters (see `crate::params`), kept as RON files in
+ * `assets/presets/<sketch>/<name>.preset.ron`. A preset only needs the fields that differ from
+ * the defaults, e.g. `(max_radius_looser: false)`.
+ *
+ * - The preset to start with is chosen with the `preset` runtime option (`?preset=tight` on the
+ *   web, `--preset tight` natively, see `crate::runtime_opts`), or by the sketch with
+ *   `SketchBuilder::preset`. Otherwise the sketch starts with the defaults.
+ * - `PRESET_NEXT_KEY` switches to the next preset, with the defaults (`DEFAULT_PRESET`) first.
+ * - `PRESET_SAVE_KEY` saves the current values to the file of the current preset (or
+ *   `custom.preset.ron` for the defaults), keeping the `//` comment at its top. Native only.
+ *
+ * The browser can't list a directory, so the build script (`build.rs`) lists the preset files into
+ * `BUNDLED_PRESETS` and a sketch has the presets found there. A new file is picked up on the next
+ * build.
+ *
+ * Instructed by:
+ * https://github.com/ron-rs/ron#example
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/asset/custom_asset.rs
+ */
+use crate::runtime_opts::{get_opt, PRESET_OPT};
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadState, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+
+pub const PRESET_NEXT_KEY: KeyCode = KeyCode::F4;
+pub const PRESET_SAVE_KEY: KeyCode = KeyCode::F5;
+/// Name of the built-in preset with the params' `Default` values
+pub const DEFAULT_PRESET: &str = "default";
+const PRESETS_DIR: &str = "presets";
+const PRESET_EXTENSION: &str = "preset.ron";
+/// File name used when saving while on the defaults
+#[cfg(not(target_arch = "wasm32"))]
+const CUSTOM_PRESET: &str = "custom";
+
+
+/// A preset file found by the build script
+#[derive(Debug)]
+pub struct BundledPreset {
+    /// Sketch directory under `assets/presets/`, e.g. `shifty`
+    pub dir: &'static str,
+    /// File name without the extension, e.g. `star`
+    pub name: &'static str,
+    /// Contents of the file at build time
+    pub source: &'static str,
+}
+
+
+/// All the preset files in `assets/presets/`, sorted by directory and name
+pub static BUNDLED_PRESETS: &[BundledPreset] =
+    include!(concat!(env!("OUT_DIR"), "/bundled_presets.rs"));
+
+
+/// Preset files of one sketch directory, by name
+pub fn bundled(dir: &str) -> impl Iterator<Item = &'static BundledPreset> + '_ {
+    return BUNDLED_PRESETS
+        .iter()
+        .filter(move |preset| preset.dir == dir);
+}
+
+
+/// Add presets for the parameter resource `T` (which must already be added with
+/// `SketchBuilder::params`), read from `assets/presets/<dir>/`
+pub struct PresetsPlugin<T> {
+    dir: &'static str,
+    marker: PhantomData<T>,
+}
+
+
+impl<T> PresetsPlugin<T> {
+    pub fn new(dir: &'static str) -> Self {
+        Self {
+            dir: dir,
+            marker: PhantomData,
+        }
+    }
+}
+
+
+impl<T: Serialize + DeserializeOwned + Default + Send + Sync + 'static> Plugin
+    for PresetsPlugin<T>
+{
+    fn build(&self, app: &mut App) {
+        // Loader and asset are shared by the presets of all parameter resources
+        if app.world.get_resource::<Assets<PresetSource>>().is_none() {
+            app.add_asset::<PresetSource>()
+                .init_asset_loader::<PresetSourceLoader>();
+        }
+
+        let mut names = vec![DEFAULT_PRESET];
+        names.extend(
+            bundled(self.dir)
+                .map(|preset| preset.name)
+                .filter(|name| *name != DEFAULT_PRESET),
+        );
+        app.insert_resource(Presets::<T> {
+            dir: self.dir,
+            names: names,
+            handles: Vec::new(),
+            current: 0,
+            pending: false,
+            initial: None,
+            marker: PhantomData,
+        })
+        .add_startup_system(load_presets::<T>)
+        .add_system(switch_preset::<T>)
+        .add_system(apply_preset::<T>.after(switch_preset::<T>));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_system(save_preset::<T>);
+    }
+}
+
+
+/// Presets of the parameter resource `T`
+pub struct Presets<T> {
+    dir: &'static str,
+    /// Starting with `DEFAULT_PRESET`
+    names: Vec<&'static str>,
+    /// Handles for all names but `DEFAULT_PRESET`
+    handles: Vec<Handle<PresetSource>>,
+    current: usize,
+    /// The current preset still needs to be applied (e.g. it is still loading)
+    pending: bool,
+    /// The preset to start with, unless the `preset` runtime option says otherwise
+    initial: Option<&'static str>,
+    marker: PhantomData<T>,
+}
+
+
+impl<T> Presets<T> {
+    pub fn current_name(&self) -> &'static str {
+        self.names[self.current]
+    }
+
+    /// Switch to a preset by name. Returns false if there is no such preset.
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.names.iter().position(|n| *n == name) {
+            Some(index) => {
+                self.current = index;
+                self.pending = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Start with this preset, unless the `preset` runtime option says otherwise
+    pub fn set_initial(&mut self, name: &'static str) {
+        self.initial = Some(name);
+    }
+
+    fn asset_path(&self, name: &str) -> String {
+        format!("{}/{}/{}.{}", PRESETS_DIR, self.dir, name, PRESET_EXTENSION)
+    }
+
+    fn current_handle(&self) -> Option<&Handle<PresetSource>> {
+        // `DEFAULT_PRESET` at index 0 has no file
+        self.current.checked_sub(1).map(|i| &self.handles[i])
+    }
+}
+
+
+/// A preset file as text, parsed into the parameter type once applied
+#[derive(TypeUuid)]
+#[uuid = "9c3f2f8e-2d5b-4a57-8e41-6f0c7a1d5b23"]
+pub struct PresetSource(pub String);
+
+
+#[derive(Default)]
+pub struct PresetSourceLoader;
+
+
+impl AssetLoader for PresetSourceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let source = String::from_utf8(bytes.to_vec())?;
+            load_context.set_default_asset(LoadedAsset::new(PresetSource(source)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[PRESET_EXTENSION]
+    }
+}
+
+
+/// Parse a preset. Fields missing from the preset keep their default values.
+pub fn parse_preset<T: DeserializeOwned>(source: &str) -> Result<T, ron::Error> {
+    ron::de::from_str(source)
+}
+
+
+pub fn serialize_preset<T: Serialize>(params: &T) -> Result<String, ron::Error> {
+    ron::ser::to_string_pretty(params, ron::ser::PrettyConfig::default())
+}
+
+
+/// The `//` comment lines at the top of a preset, each with its line break
+pub fn preset_header(source: &str) -> String {
+    return source
+        .lines()
+        .take_while(|line| line.trim_start().starts_with("//"))
+        .map(|line| format!("{}\n", line))
+        .collect();
+}
+
+
+fn load_presets<T: Send + Sync + 'static>(
+    asset_server: Res<AssetServer>,
+    mut presets: ResMut<Presets<T>>,
+) {
+    let handles: Vec<Handle<PresetSource>> = presets.names[1..]
+        .iter()
+        .map(|name| asset_server.load(&presets.asset_path(name)))
+        .collect();
+    presets.handles = handles;
+
+    let name = get_opt(PRESET_OPT).or_else(|| presets.initial.map(String::from));
+    if let Some(name) = name {
+        if !presets.select(&name) {
+            warn!(
+                "SKETCH: no preset '{}' (presets: {})",
+                name,
+                presets.names.join(", ")
+            );
+        }
+    }
+}
+
+
+fn switch_preset<T: Send + Sync + 'static>(
+    keys: Res<Input<KeyCode>>,
+    mut presets: ResMut<Presets<T>>,
+) {
+    if keys.just_pressed(PRESET_NEXT_KEY) {
+        presets.current = (presets.current + 1) % presets.names.len();
+        presets.pending = true;
+    }
+}
+
+
+/// Apply the selected preset once loaded, and again whenever its file changes (with asset hot
+/// reloading)
+fn apply_preset<T: DeserializeOwned + Default + Send + Sync + 'static>(
+    asset_server: Res<AssetServer>,
+    sources: Res<Assets<PresetSource>>,
+    mut events: EventReader<AssetEvent<PresetSource>>,
+    mut presets: ResMut<Presets<T>>,
+    mut params: ResMut<T>,
+) {
+    let current = presets.current_handle();
+    let modified = events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => Some(handle) == current,
+        _ => false,
+    });
+    if !presets.pending && !modified {
+        return;
+    }
+
+    let name = presets.current_name();
+    let handle = match presets.current_handle() {
+        Some(handle) => handle,
+        None => {
+            *params = T::default();
+            presets.pending = false;
+            info!("SKETCH: preset {}", name);
+            return;
+        }
+    };
+    let source = match sources.get(handle) {
+        Some(source) => source,
+        None => {
+            if asset_server.get_load_state(handle) == LoadState::Failed {
+                warn!("SKETCH: failed to load preset '{}'", name);
+                presets.pending = false;
+            }
+            // Otherwise still loading, try again next frame
+            return;
+        }
+    };
+
+    match parse_preset::<T>(&source.0) {
+        Ok(preset) => {
+            *params = preset;
+            info!("SKETCH: preset {}", name);
+        }
+        Err(err) => warn!("SKETCH: invalid preset '{}': {}", name, err),
+    }
+    presets.pending = false;
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_preset<T: Serialize + Send + Sync + 'static>(
+    keys: Res<Input<KeyCode>>,
+    settings: Option<Res<bevy::asset::AssetServerSettings>>,
+    presets: Res<Presets<T>>,
+    params: Res<T>,
+) {
+    if !keys.just_pressed(PRESET_SAVE_KEY) {
+        return;
+    }
+
+    let name = match presets.current_name() {
+        DEFAULT_PRESET => CUSTOM_PRESET,
+        name => name,
+    };
+    let asset_folder = settings.map_or_else(|| String::from("assets"), |s| s.asset_folder.clone());
+    let path = bevy::asset::FileAssetIo::get_root_path()
+        .join(asset_folder)
+        .join(presets.asset_path(name));
+
+    let header = std::fs::read_to_string(&path)
+        .map(|source| preset_header(&source))
+        .unwrap_or_default();
+    let result = serialize_preset(&*params)
+        .map_err(|err| err.to_string())
+        .map(|ron| header + &ron)
+        .and_then(|ron| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            std::fs::write(&path, ron).map_err(|err| err.to_string())
+        });
+    match result {
+        Ok(()) => info!("SKETCH: saved preset to {}", path.display()),
+        Err(err) => error!(
+            "SKETCH: failed to save preset to {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(default)]
+    struct TestParams {
+        size: f32,
+        enabled: bool,
+        color: Color,
+    }
+
+    impl Default for TestParams {
+        fn default() -> Self {
+            Self {
+                size: 10.0,
+                enabled: true,
+                color: Color::rgb(0.1, 0.2, 0.3),
+            }
+        }
+    }
+
+    #[test]
+    fn missing_fields_keep_defaults() {
+        let params: TestParams = parse_preset("(enabled: false)").unwrap();
+        assert_eq!(
+            params,
+            TestParams {
+                enabled: false,
+                ..default()
+            }
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let params = TestParams {
+            size: 2.5,
+            enabled: false,
+            color: Color::rgba(1.0, 0.5, 0.0, 0.5),
+        };
+        let ron = serialize_preset(&params).unwrap();
+        assert_eq!(parse_preset::<TestParams>(&ron).unwrap(), params);
+    }
+
+    #[test]
+    fn bundled_presets_parse() {
+        use crate::{
+            cellular::quadratic::CellParams, path_changer::ChangerParams,
+            shifty_circle::ShiftyParams,
+        };
+
+        assert!(bundled("cellular").any(|preset| preset.name == "tight"));
+        for preset in BUNDLED_PRESETS {
+            let parsed = match preset.dir {
+                "cellular" => parse_preset::<CellParams>(preset.source).map(drop),
+                "shifty" => parse_preset::<ShiftyParams>(preset.source).map(drop),
+                "path_changer" => parse_preset::<ChangerParams>(preset.source).map(drop),
+                dir => panic!("no params for presets/{}", dir),
+            };
+            assert!(
+                parsed.is_ok(),
+                "{}/{}: {:?}",
+                preset.dir,
+                preset.name,
+                parsed
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_header() {
+        let source = "// Stars\n// and more\n(\n    shape: Circle, // not this\n)\n";
+        assert_eq!(preset_header(source), "// Stars\n// and more\n");
+        assert_eq!(preset_header("(enabled: false)"), "");
+    }
+
+    #[test]
+    fn reports_invalid_presets() {
+        assert!(parse_preset::<TestParams>("(size: \"big\")").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+/*
+ * A seeded random number generator shared by all sketch generators, so a result (a nice skyline,
+ * or a bug report) can be recreated exactly by running with the same seed.
+ *
+ * The seed is random by default, and can be set with the `seed` runtime option (e.g. `?seed=42`
+ * on the web or `--seed 42` natively, see `crate::runtime_opts`). The active seed is logged at
+ * startup and shown in the performance overlay.
+ *
+ * Systems that draw random values take their own `stream` of the generator, so the order Bevy
+ * happens to run them in (which isn't fixed between systems without explicit ordering) doesn't
+ * change what each of them gets.
+ */
+use crate::runtime_opts::{get_opt, SEED_OPT};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::{Error, Rng, RngCore, SeedableRng};
+// Using a ChaCha generator explicitly (rather than `StdRng`) because its output is guaranteed to
+// stay the same across rand versions and platforms.
+use rand_chacha::ChaCha8Rng;
+
+
+/// Use it like any `Rng`, e.g. `rng.gen_range(0.0..1.0)`
+pub struct SketchRng {
+    seed: u64,
+    rng: ChaCha8Rng,
+    streams: HashMap<&'static str, SketchRng>,
+}
+
+
+impl SketchRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed: seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            streams: HashMap::default(),
+        }
+    }
+
+    /// Seed from the `seed` runtime option, or a random seed if it isn't set
+    pub fn from_runtime_opts() -> Self {
+        let seed = match get_opt(SEED_OPT).map(|seed| seed.parse::<u64>()) {
+            Some(Ok(seed)) => seed,
+            Some(Err(_)) => {
+                warn!("SKETCH: invalid seed option, using a random seed instead");
+                rand::random()
+            }
+            None => rand::random(),
+        };
+        Self::new(seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// `gen_range(start..end)` that gives `start` for an empty range instead of panicking, for
+    /// ranges taken from params that can be edited into any order at runtime
+    pub fn range(&mut self, start: f32, end: f32) -> f32 {
+        if start >= end {
+            return start;
+        }
+        self.gen_range(start..end)
+    }
+
+    /// A generator of its own for one consumer (usually a system), e.g. `rng.stream("skyline")`,
+    /// from the same seed but independent of what the others draw. Every consumer of the
+    /// `SketchRng` resource should draw from a stream, as systems run in no fixed order.
+    pub fn stream(&mut self, name: &'static str) -> &mut SketchRng {
+        let seed = self.seed;
+        return self.streams.entry(name).or_insert_with(|| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream_id(name));
+            Self {
+                seed: seed,
+                rng: rng,
+                streams: HashMap::default(),
+            }
+        });
+    }
+
+    /// Start over from the given seed, including the streams
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+}
+
+
+impl Default for SketchRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+
+/// FNV-1a hash of the stream name, stable across platforms and Rust versions unlike `Hash`
+fn stream_id(name: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in name.bytes() {
+        hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+
+impl RngCore for SketchRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = SketchRng::new(42);
+        let mut b = SketchRng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.gen_range(0.0..1.0), b.gen_range(0.0..1.0));
+        }
+    }
+
+    #[test]
+    fn range_survives_empty_ranges() {
+        let mut rng = SketchRng::new(1);
+        assert_eq!(rng.range(5.0, 5.0), 5.0);
+        assert_eq!(rng.range(5.0, 1.0), 5.0);
+        let value = rng.range(1.0, 5.0);
+        assert!((1.0..5.0).contains(&value));
+    }
+
+    #[test]
+    fn reseed_starts_over() {
+        let mut rng = SketchRng::new(7);
+        let first: u64 = rng.gen();
+        rng.gen::<u64>();
+        rng.reseed(7);
+        assert_eq!(rng.gen::<u64>(), first);
+        assert_eq!(rng.seed(), 7);
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut a = SketchRng::new(42);
+        let mut b = SketchRng::new(42);
+        // Drawing from one stream (or the main generator) doesn't shift another one
+        a.stream("skyline").gen::<u64>();
+        b.gen::<u64>();
+        assert_eq!(
+            a.stream("circles").gen::<u64>(),
+            b.stream("circles").gen::<u64>()
+        );
+        assert_ne!(
+            a.stream("circles").gen::<u64>(),
+            a.stream("skyline").gen::<u64>()
+        );
+        assert_eq!(b.stream("circles").seed(), 42);
+    }
+}
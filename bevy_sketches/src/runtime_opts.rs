@@ -0,0 +1,126 @@
+/*
+ * Options that can be chosen at runtime, so a single release build can be debugged in the
+ * field without rebuilding the wasm.
+ *
+ * On the web, options come from the page's URL query, e.g. `shiftyc.html?log=debug&stats=1`.
+ * Options the query doesn't set fall back to the page's own defaults, in the same format, from
+ * `<meta name="sketch-opts" content="preset=star">` (which is how the `build-sketches` tool gives
+ * each shifty variation a page).
+ *
+ * Natively, options come from command line args (`--log=debug`, `--log debug` or just `--stats`
+ * for a flag) or from `SKETCH_` prefixed environment variables (`SKETCH_LOG=debug`). Args take
+ * precedence over environment variables.
+ */
+
+
+/// Log level, e.g. `error`, `warn`, `info`, `debug`, `trace`
+pub const LOG_OPT: &str = "log";
+/// Log filter in `EnvFilter` format, e.g. `wgpu=error,bevy_render=error`
+pub const LOG_FILTER_OPT: &str = "logfilter";
+/// Enable frame stats diagnostics
+pub const STATS_OPT: &str = "stats";
+/// Show the performance overlay from the start
+pub const OVERLAY_OPT: &str = "overlay";
+/// Seed for `crate::rng::SketchRng`
+pub const SEED_OPT: &str = "seed";
+/// Parameter preset to start with (see `crate::presets`)
+pub const PRESET_OPT: &str = "preset";
+/// First sketch shown by the gallery (see `crate::gallery`)
+pub const SKETCH_OPT: &str = "sketch";
+/// Audio file in `assets/` for audio reactive sketches (see `crate::audio_analysis`)
+pub const AUDIO_OPT: &str = "audio";
+/// Element holding the page's default options, in its `content` attribute
+#[cfg(target_arch = "wasm32")]
+const PAGE_OPTS_SELECTOR: &str = "meta[name=sketch-opts]";
+
+
+#[cfg(target_arch = "wasm32")]
+pub fn get_opt(name: &str) -> Option<String> {
+    let window = web_sys::window()?;
+    let search = window.location().search().ok()?;
+    query_opt(&search, name).or_else(|| {
+        let page_opts = window
+            .document()?
+            .query_selector(PAGE_OPTS_SELECTOR)
+            .ok()??
+            .get_attribute("content")?;
+        query_opt(&page_opts, name)
+    })
+}
+
+
+/// Option `name` in a query string like `?log=debug&stats` (the `?` is optional)
+#[cfg(target_arch = "wasm32")]
+fn query_opt(query: &str, name: &str) -> Option<String> {
+    web_sys::UrlSearchParams::new_with_str(query).ok()?.get(name)
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_opt(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    find_arg(&args, name).or_else(|| std::env::var(format!("SKETCH_{}", name.to_uppercase())).ok())
+}
+
+
+/// Get an option as a boolean. A flag given without a value (`--stats` or `?stats`) is `true`.
+pub fn get_flag(name: &str) -> Option<bool> {
+    get_opt(name).and_then(|value| parse_flag(&value))
+}
+
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "" | "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+
+/// Find `--name=value`, `--name value` or a bare `--name` (which gives an empty value) in args
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn find_arg(args: &[String], name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let prefix = format!("{}=", flag);
+
+    for (i, arg) in args.iter().enumerate() {
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+        if *arg == flag {
+            return match args.get(i + 1) {
+                Some(next) if !next.starts_with("--") => Some(next.clone()),
+                _ => Some(String::new()),
+            };
+        }
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_args_in_all_forms() {
+        let args = args(&["--log=debug", "--seed", "42", "--stats", "--other"]);
+        assert_eq!(find_arg(&args, "log"), Some(String::from("debug")));
+        assert_eq!(find_arg(&args, "seed"), Some(String::from("42")));
+        assert_eq!(find_arg(&args, "stats"), Some(String::new()));
+        assert_eq!(find_arg(&args, "missing"), None);
+    }
+
+    #[test]
+    fn parses_flags() {
+        assert_eq!(parse_flag(""), Some(true));
+        assert_eq!(parse_flag("1"), Some(true));
+        assert_eq!(parse_flag("Off"), Some(false));
+        assert_eq!(parse_flag("maybe"), None);
+    }
+}
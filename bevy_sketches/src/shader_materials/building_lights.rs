@@ -1,10 +1,15 @@
+use super::core::{
+    color_to_shader_vec4, BaseShaderTrait, CommonUniformData, NoiseUniformData, PaletteUniformData,
+};
 /// Demonstrates providing additional uniform data from material to shader. See also the
 /// accompanying shader at `MATERIAL_PATH` below.
 ///
 /// Steps to creating a new material
 /// 1. First, follow all the steps listed in `super::eg_material.rs`
 /// 2..?
-use super::core::{color_to_shader_vec3, BaseShaderTrait, CommonUniformData};
+use crate::noise::NoiseField;
+use crate::palette::Palette;
+use crate::pointer::Pointer;
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     prelude::*,
@@ -26,14 +31,26 @@ use bevy::{
 // const MATERIAL_PATH: &str = "poc_shaders/time_colors.wgsl";
 const MATERIAL_PATH: &str = "shiftyc/building_lights.wgsl";
 const DEFAULT_BGCOLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+/// Window colors (linear, as the shader gets them): the background for lights that are off, then
+/// the accent, fill and stroke from the brightest light to the dimmest
+pub const DEFAULT_LIGHTS: Palette = Palette {
+    background: Color::rgba_linear(0.005, 0.006, 0.0, 1.0),
+    fill: Color::rgba_linear(0.08, 0.1, 0.05, 1.0),
+    stroke: Color::rgba_linear(0.04, 0.06, 0.02, 1.0),
+    accent: Color::rgba_linear(0.9, 1.0, 0.6, 1.0),
+};
 
 
 #[derive(Clone, AsStd140)]
 pub struct BuildingLightsUniform {
     pub common: CommonUniformData,
-    pub background_color: Vec3,
-    pub alpha: f32,
+    /// Linear, with the alpha of the whole quad (see `color_to_shader_vec4`)
+    pub background_color: Vec4,
     pub rand_modifier: f32,
+    pub lights: PaletteUniformData,
+    /// More windows are lit where the field is busy (see `busyness`). The default, an empty field,
+    /// is evenly busy at 0.5.
+    pub noise: NoiseUniformData,
 }
 
 
@@ -41,14 +58,24 @@ impl Default for BuildingLightsUniform {
     fn default() -> Self {
         Self {
             common: CommonUniformData::default(),
-            background_color: color_to_shader_vec3(DEFAULT_BGCOLOR),
-            alpha: 1.0,
+            background_color: color_to_shader_vec4(DEFAULT_BGCOLOR),
             rand_modifier: 1.0,
+            lights: PaletteUniformData::from(&DEFAULT_LIGHTS),
+            noise: NoiseUniformData::default(),
         }
     }
 }
 
 
+/// How busy the city is at a point in world space, from 0.0 to 1.0, the same as `busyness` in the
+/// shader gives for the `noise` of the uniform. The shader samples it at the middle of the quad's
+/// bottom edge, so sketches can shape each building to match its lights (e.g. taller ones where
+/// more windows are lit).
+pub fn busyness(field: &NoiseField, point: Vec2) -> f32 {
+    return (field.sample(point) * 0.5 + 0.5).clamp(0.0, 1.0);
+}
+
+
 #[derive(TypeUuid, Clone)]
 #[uuid = "f305c425-4f41-40cf-b7d3-b6e4a1ed6f04"]
 pub struct BuildingLights {
@@ -71,6 +98,10 @@ impl BaseShaderTrait for BuildingLights {
     fn set_resolution(&mut self, resolution: Vec2) {
         self.uniform.common.resolution = resolution;
     }
+
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.uniform.common.set_pointer(pointer, transform);
+    }
 }
 
 
@@ -19,19 +19,34 @@
  */
 
 
+use crate::audio_analysis::AudioLevels;
+use crate::noise::NoiseField;
+use crate::palette::{Palette, PALETTE_SIZE};
+use crate::pointer::{quad_uv, Pointer};
+use crate::sketch_clock::SketchClock;
 use bevy::{
     prelude::*,
     render::{render_resource::std140::AsStd140, RenderApp, RenderStage},
     sprite::{Material2d, Material2dPlugin},
+    utils::HashMap,
 };
+use std::any::type_name;
 use std::marker::PhantomData;
 
 
+/// Mirrored by `assets/shader_common/common_uniform.wgsl`, keep the field order in sync
 #[derive(Clone, AsStd140)]
 pub struct CommonUniformData {
-    // @TODO Add resolution, mouse, etc.
     pub time: f32,
     pub resolution: Vec2,
+    /// Pointer position on the quad, from (0, 0) at its bottom left to (1, 1) at its top right
+    pub pointer: Vec2,
+    /// Pointer position in world space (see `crate::pointer`)
+    pub pointer_world: Vec2,
+    /// Position of the last click or tap on the quad, like `pointer`
+    pub last_click: Vec2,
+    /// 1.0 while the pointer is pressed, 0.0 otherwise
+    pub pointer_pressed: f32,
 }
 
 impl Default for CommonUniformData {
@@ -39,6 +54,78 @@ impl Default for CommonUniformData {
         Self {
             time: 0.0,
             resolution: Vec2::ONE,
+            // Off the quad until there is a pointer
+            pointer: Vec2::splat(-1.0),
+            pointer_world: Vec2::ZERO,
+            last_click: Vec2::splat(-1.0),
+            pointer_pressed: 0.0,
+        }
+    }
+}
+
+impl CommonUniformData {
+    /// Set the pointer fields for a quad with `transform`
+    pub fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.pointer = quad_uv(pointer.position, transform);
+        self.pointer_world = pointer.position;
+        if let Some(click) = pointer.last_click {
+            self.last_click = quad_uv(click, transform);
+        }
+        self.pointer_pressed = if pointer.pressed { 1.0 } else { 0.0 };
+    }
+}
+
+
+/// Music levels for audio reactive materials (see `crate::audio_analysis`), mirrored by
+/// `assets/shader_common/audio_uniform.wgsl`. Materials add it next to their
+/// `CommonUniformData` and implement `BaseShaderTrait::set_audio`.
+#[derive(Clone, Default, AsStd140)]
+pub struct AudioUniformData {
+    /// Bass, low mids, high mids and treble, 0.0 to 1.0
+    pub bands: Vec4,
+    /// 1.0 on a beat, fading to 0.0
+    pub beat: f32,
+}
+
+impl AudioUniformData {
+    pub fn set(&mut self, levels: &AudioLevels) {
+        self.bands = Vec4::from(levels.bands);
+        self.beat = levels.beat_pulse();
+    }
+}
+
+
+/// A `NoiseField` for materials (see `crate::noise`), mirrored by `NoiseField` in
+/// `assets/shader_common/noise.wgsl`. Sample it there with `noise_field`.
+#[derive(Clone, Default, AsStd140)]
+pub struct NoiseUniformData {
+    pub seed: u32,
+    pub frequency: f32,
+    pub octaves: u32,
+}
+
+impl From<&NoiseField> for NoiseUniformData {
+    fn from(field: &NoiseField) -> Self {
+        Self {
+            seed: field.seed,
+            frequency: field.frequency,
+            octaves: field.octaves,
+        }
+    }
+}
+
+
+/// A `Palette` for materials (see `crate::palette`), mirrored by `PaletteUniformData` in
+/// `assets/shader_common/palette.wgsl`: linear colors, in role order
+#[derive(Clone, AsStd140)]
+pub struct PaletteUniformData {
+    pub colors: [Vec4; PALETTE_SIZE],
+}
+
+impl From<&Palette> for PaletteUniformData {
+    fn from(palette: &Palette) -> Self {
+        Self {
+            colors: palette.colors().map(color_to_shader_vec4),
         }
     }
 }
@@ -48,6 +135,12 @@ pub trait BaseShaderTrait: Material2d {
     fn set_time(&mut self, time: f32);
 
     fn set_resolution(&mut self, resolution: Vec2);
+
+    /// See `CommonUniformData::set_pointer`
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform);
+
+    /// Only needed by materials with `AudioUniformData`
+    fn set_audio(&mut self, _levels: &AudioLevels) {}
 }
 
 
@@ -80,6 +173,8 @@ impl<M: BaseShaderTrait> Default for ShaderMaterialPlugin<M> {
 impl<T: BaseShaderTrait> Plugin for ShaderMaterialPlugin<T> {
     fn build(&self, app: &mut App) {
         app.add_plugin(Material2dPlugin::<T>::default())
+            .init_resource::<ShaderMaterialCounts>()
+            .add_system(count_shader_materials::<T>)
             .sub_app_mut(RenderApp)
             // .add_system_to_stage(RenderStage::Extract, update_time::<T>)
             .add_system_to_stage(RenderStage::Extract, update_common_uniform_data::<T>);
@@ -99,9 +194,28 @@ impl<T: BaseShaderTrait> Plugin for ShaderMaterialPlugin<T> {
 pub struct DisplayQuad;
 
 
-/// Update uniform data (time & resolution) in material for sending to shader
+/// Number of assets of each shader material type (keyed by type name), e.g. for spotting leaks
+#[derive(Default)]
+pub struct ShaderMaterialCounts(pub HashMap<&'static str, usize>);
+
+
+pub fn count_shader_materials<T: BaseShaderTrait>(
+    mat_resources: Res<Assets<T>>,
+    mut counts: ResMut<ShaderMaterialCounts>,
+) {
+    // Strip the module path, e.g. "bevy_sketches::shader_materials::scaling_net::ScalingNet"
+    let name = type_name::<T>().rsplit("::").next().unwrap_or_default();
+    counts.0.insert(name, mat_resources.len());
+}
+
+
+/// Update uniform data (time, resolution, pointer & audio) in material for sending to shader.
+///
+/// Time is sketch time, so shaders freeze while the sketch is paused.
 pub fn update_common_uniform_data<T: BaseShaderTrait>(
-    time: Res<Time>,
+    clock: Res<SketchClock>,
+    pointer: Option<Res<Pointer>>,
+    audio: Option<Res<AudioLevels>>,
     mut mat_resources: ResMut<Assets<T>>,
     // Figured out by looking at the declaration of MaterialMesh2dBundle that you can
     // query for the handle of the material. With the handleId from this, we can filter
@@ -109,24 +223,91 @@ pub fn update_common_uniform_data<T: BaseShaderTrait>(
     quad_query: Query<(&Transform, &Handle<T>), With<DisplayQuad>>,
 ) {
     for (asset_handle, mymaterial) in mat_resources.iter_mut() {
-        mymaterial.set_time(time.seconds_since_startup() as f32);
+        mymaterial.set_time(clock.seconds_since_startup() as f32);
+        if let Some(levels) = &audio {
+            mymaterial.set_audio(levels);
+        }
 
         for (transform, handle) in quad_query.iter() {
             if handle.id == asset_handle {
                 mymaterial.set_resolution(Vec2::new(transform.scale.x, transform.scale.y));
+                if let Some(pointer) = &pointer {
+                    mymaterial.set_pointer(pointer, transform);
+                }
             }
         }
     }
 }
 
 
-/// Colors from Bevy need to be adjusted before sending to shader as there seems to be
-/// some kind of mismatch.
-///
-/// Current implementation is to multiply color values by 0.1, but another seemingly
-/// less accurate option is to use the Color.as_linear_rgba_f32() method.
-///
-/// See https://github.com/bevyengine/bevy/discussions/2783
+// Colors in uniforms are linear RGB with straight (not premultiplied) alpha, as documented in
+// `assets/shader_common/common_uniform.wgsl`. `Color::rgb` and CSS colors are sRGB, which is
+// "gamma encoded": shaders work in linear RGB and the render target encodes their output back to
+// sRGB, so a color passed through these helpers renders exactly as specified (and matches the
+// same color on the HTML page).
+
+
+/// Linear RGB of the color, for a `vec3<f32>` uniform
 pub fn color_to_shader_vec3(color: Color) -> Vec3 {
-    Vec3::new(color.r(), color.g(), color.b()) * 0.1
+    color_to_shader_vec4(color).truncate()
+}
+
+
+/// Linear RGB and alpha of the color, for a `vec4<f32>` uniform
+pub fn color_to_shader_vec4(color: Color) -> Vec4 {
+    Vec4::from(color.as_linear_rgba_f32())
+}
+
+
+/// The color of a linear RGB `vec3<f32>`, opaque
+pub fn shader_vec3_to_color(value: Vec3) -> Color {
+    shader_vec4_to_color(value.extend(1.0))
+}
+
+
+/// The color of a linear RGB and alpha `vec4<f32>`
+pub fn shader_vec4_to_color(value: Vec4) -> Color {
+    Color::rgba_linear(value.x, value.y, value.z, value.w).as_rgba()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec4, b: Vec4) {
+        assert!(a.abs_diff_eq(b, 1e-5), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn converts_srgb_colors_to_linear() {
+        // Reference values from the sRGB transfer function (IEC 61966-2-1)
+        let cases = [
+            (
+                Color::rgba(0.0, 0.5, 1.0, 0.25),
+                Vec4::new(0.0, 0.214041, 1.0, 0.25),
+            ),
+            (
+                Color::rgb(0.04045, 0.735357, 0.2),
+                Vec4::new(0.003131, 0.5, 0.033105, 1.0),
+            ),
+            (
+                Color::rgb_u8(128, 64, 255),
+                Vec4::new(0.215861, 0.051269, 1.0, 1.0),
+            ),
+            (
+                Color::hsl(0.0, 1.0, 0.25),
+                Vec4::new(0.214041, 0.0, 0.0, 1.0),
+            ),
+        ];
+        for (color, linear) in cases {
+            assert_close(color_to_shader_vec4(color), linear);
+            assert_close(color_to_shader_vec3(color).extend(linear.w), linear);
+            // And back
+            let back = shader_vec4_to_color(linear).as_rgba_f32();
+            assert_close(Vec4::from(back), Vec4::from(color.as_rgba_f32()));
+        }
+        let white = shader_vec3_to_color(Vec3::ONE).as_rgba_f32();
+        assert_close(Vec4::from(white), Vec4::ONE);
+    }
 }
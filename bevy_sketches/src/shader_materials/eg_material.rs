@@ -1,3 +1,4 @@
+use super::core::{BaseShaderMaterial, BaseShaderTrait, CommonUniformData};
 /// Steps to creating a new material
 /// 1. Copy the code below to a new source file
 /// 2. Globally replace "ExampleMaterial" with the name of the new struct
@@ -5,7 +6,7 @@
 /// 4. Change the MATERIAL_PATH constant below to the shader you want.
 /// 5. That should be it in most cases.
 /// 6. If you need to send additional uniform data, take a look at the `eg_mo_data_material` module.
-use super::core::{BaseShaderMaterial, BaseShaderTrait, CommonUniformData};
+use crate::pointer::Pointer;
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     prelude::*,
@@ -46,6 +47,10 @@ impl BaseShaderTrait for ExampleMaterial {
     fn set_resolution(&mut self, resolution: Vec2) {
         self.0.uniform.resolution = resolution;
     }
+
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.0.uniform.set_pointer(pointer, transform);
+    }
 }
 
 
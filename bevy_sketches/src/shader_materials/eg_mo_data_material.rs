@@ -1,3 +1,4 @@
+use super::core::{BaseShaderTrait, CommonUniformData};
 /// Demonstrates providing additional uniform data from material to shader. See also the
 /// accompanying shader at `MATERIAL_PATH` below.
 ///
@@ -9,7 +10,7 @@
 /// 5. Update SomeCustomUniformData to contain the data you want. Update other structs,
 ///    impls, etc. also to reflect these changes.
 /// 6. Make sure you also update the shader to resemble the new custom uniform data.
-use super::core::{BaseShaderTrait, CommonUniformData};
+use crate::pointer::Pointer;
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     prelude::*,
@@ -74,6 +75,10 @@ impl BaseShaderTrait for AdditionalDataMaterial {
     fn set_resolution(&mut self, resolution: Vec2) {
         self.uniform.common.resolution = resolution;
     }
+
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.uniform.common.set_pointer(pointer, transform);
+    }
 }
 
 
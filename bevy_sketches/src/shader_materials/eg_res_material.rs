@@ -1,6 +1,7 @@
+use super::core::{BaseShaderMaterial, BaseShaderTrait, CommonUniformData};
 /// Material boilterplate for shader that demonstrates using resolution data. See shader
 /// located at MATERIAL_PATH below for more details.
-use super::core::{BaseShaderMaterial, BaseShaderTrait, CommonUniformData};
+use crate::pointer::Pointer;
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     prelude::*,
@@ -41,6 +42,10 @@ impl BaseShaderTrait for ResExampleMaterial {
     fn set_resolution(&mut self, resolution: Vec2) {
         self.0.uniform.resolution = resolution;
     }
+
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.0.uniform.set_pointer(pointer, transform);
+    }
 }
 
 
@@ -1,4 +1,6 @@
-use super::core::{BaseShaderMaterial, BaseShaderTrait, CommonUniformData};
+use super::core::{AudioUniformData, BaseShaderTrait, CommonUniformData};
+use crate::audio_analysis::AudioLevels;
+use crate::pointer::Pointer;
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     prelude::*,
@@ -19,25 +21,57 @@ use bevy::{
 const MATERIAL_PATH: &str = "poc_shaders/erratic_wave.wgsl";
 
 
+#[derive(Clone, AsStd140)]
+pub struct ErraticWaveUniform {
+    pub common: CommonUniformData,
+    pub audio: AudioUniformData,
+    /// How much the bass grows the wave, 0.0 for not at all
+    pub bass_swell: f32,
+}
+
+
+impl Default for ErraticWaveUniform {
+    fn default() -> Self {
+        Self {
+            common: CommonUniformData::default(),
+            audio: AudioUniformData::default(),
+            bass_swell: 2.0,
+        }
+    }
+}
+
+
 #[derive(TypeUuid, Clone)]
 #[uuid = "ba185d29-c899-4acb-b140-94c787c779a0"]
-pub struct ErraticWaveMaterial(BaseShaderMaterial);
+pub struct ErraticWaveMaterial {
+    pub uniform: ErraticWaveUniform,
+}
 
 
 impl Default for ErraticWaveMaterial {
     fn default() -> Self {
-        Self(BaseShaderMaterial::default())
+        Self {
+            uniform: ErraticWaveUniform::default(),
+        }
     }
 }
 
 
 impl BaseShaderTrait for ErraticWaveMaterial {
     fn set_time(&mut self, time: f32) {
-        self.0.uniform.time = time;
+        self.uniform.common.time = time;
     }
 
     fn set_resolution(&mut self, resolution: Vec2) {
-        self.0.uniform.resolution = resolution;
+        self.uniform.common.resolution = resolution;
+    }
+
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.uniform.common.set_pointer(pointer, transform);
+    }
+
+    fn set_audio(&mut self, levels: &AudioLevels) {
+        self.uniform.audio.set(levels);
     }
 }
 
@@ -64,7 +98,7 @@ impl Material2d for ErraticWaveMaterial {
                     ty: BufferBindingType::Uniform,
                     has_dynamic_offset: false,
                     min_binding_size: BufferSize::new(
-                        CommonUniformData::std140_size_static() as u64
+                        ErraticWaveUniform::std140_size_static() as u64
                     ),
                 },
                 count: None,
@@ -106,7 +140,7 @@ impl RenderAsset for ErraticWaveMaterial {
     ) -> Result<GPUErraticWaveMaterial, PrepareAssetError<ErraticWaveMaterial>> {
         let uniform_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
             label: None,
-            contents: extracted_asset.0.uniform.as_std140().as_bytes(),
+            contents: extracted_asset.uniform.as_std140().as_bytes(),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
@@ -0,0 +1,216 @@
+/*
+ * layout
+ *
+ * Responsive layout for `DisplayQuad` sketches. A sketch spawns its quads once, each with a
+ * `QuadLayout` in normalized canvas coordinates, and `fit_display_quads` sets their transforms
+ * whenever the canvas size changes (a browser resize on the web, a window resize natively).
+ * Since the common uniform's resolution is read from the transform scale, the shaders follow.
+ *
+ * The quads share one mesh (`DisplayQuadMesh`), and a resize only moves and scales the existing
+ * entities, so no meshes or materials pile up in `Assets`. The 2d camera scales with the window
+ * on its own and is spawned only once.
+ *
+ * ```ignore
+ * fn setup(mut commands: Commands, quad: Res<DisplayQuadMesh>, mut materials: ResMut<Assets<M>>) {
+ *     commands.spawn_bundle(DisplayQuadBundle::new(&quad, materials.add(M::default()), QuadLayout::FULL));
+ * }
+ * ```
+ */
+use crate::shader_materials::core::DisplayQuad;
+use bevy::{
+    prelude::*,
+    sprite::{Material2d, MaterialMesh2dBundle},
+};
+use bevy_web_extras::prelude::*;
+
+
+/// Where a quad goes on the canvas, in fractions of the canvas size
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct QuadLayout {
+    /// Center of the quad, from (0, 0) at the bottom left to (1, 1) at the top right
+    pub center: Vec2,
+    /// Size of the quad, (1, 1) covering the whole canvas
+    pub size: Vec2,
+}
+
+
+impl QuadLayout {
+    pub const FULL: Self = Self {
+        center: bevy::math::const_vec2!([0.5, 0.5]),
+        size: Vec2::ONE,
+    };
+
+    pub fn new(center: Vec2, size: Vec2) -> Self {
+        Self {
+            center: center,
+            size: size,
+        }
+    }
+
+    /// Vertically centered quads side by side from the left edge, with `padding` before each
+    pub fn row(padding: f32, sizes: &[Vec2]) -> Vec<Self> {
+        let mut left = 0.0;
+        return sizes
+            .iter()
+            .map(|size| {
+                left += padding;
+                let layout = Self::new(Vec2::new(left + size.x / 2.0, 0.5), *size);
+                left += size.x;
+                layout
+            })
+            .collect();
+    }
+
+    /// The quad's transform on a canvas of `width` x `height`, centered on the origin
+    pub fn transform(&self, width: f32, height: f32, z: f32) -> Transform {
+        let canvas = Vec2::new(width, height);
+        let size = self.size * canvas;
+        let center = (self.center - Vec2::splat(0.5)) * canvas;
+        return Transform {
+            translation: center.extend(z),
+            scale: size.extend(1.0),
+            ..default()
+        };
+    }
+}
+
+
+/// The unit quad mesh shared by all display quads
+pub struct DisplayQuadMesh(pub Handle<Mesh>);
+
+
+impl FromWorld for DisplayQuadMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        Self(meshes.add(Mesh::from(shape::Quad::default())))
+    }
+}
+
+
+#[derive(Bundle)]
+pub struct DisplayQuadBundle<T: Material2d> {
+    #[bundle]
+    pub mesh: MaterialMesh2dBundle<T>,
+    pub layout: QuadLayout,
+    pub display_quad: DisplayQuad,
+}
+
+
+impl<T: Material2d> DisplayQuadBundle<T> {
+    pub fn new(quad: &DisplayQuadMesh, material: Handle<T>, layout: QuadLayout) -> Self {
+        Self {
+            mesh: MaterialMesh2dBundle {
+                mesh: quad.0.clone().into(),
+                material: material,
+                ..default()
+            },
+            layout: layout,
+            display_quad: DisplayQuad,
+        }
+    }
+}
+
+
+/// Shared mesh and resize handling for display quads. Added with `SketchBuilder::display_quads`.
+pub struct QuadLayoutPlugin;
+
+
+impl Plugin for QuadLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DisplayQuadMesh>()
+            .add_system(fit_display_quads);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_system_to_stage(CoreStage::PreUpdate, track_window_size);
+    }
+}
+
+
+pub fn spawn_display_camera(mut commands: Commands) {
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+}
+
+
+/// Keep the canvas size in `WebExtrasCfg` in step with the window, as `bevy_web_extras` does on
+/// `BrowserResized`
+#[cfg(not(target_arch = "wasm32"))]
+fn track_window_size(
+    mut resized_events: EventReader<bevy::window::WindowResized>,
+    windows: Res<Windows>,
+    mut webcfg: ResMut<WebExtrasCfg>,
+) {
+    let primary = match windows.get_primary() {
+        Some(window) => window.id(),
+        None => return,
+    };
+    if let Some(resized) = resized_events.iter().rev().find(|e| e.id == primary) {
+        webcfg.width = resized.width;
+        webcfg.height = resized.height;
+        webcfg.max_x = resized.width / 2.0;
+        webcfg.max_y = resized.height / 2.0;
+    }
+}
+
+
+/// Set the transforms of new quads, and of all of them when the canvas size changes
+pub fn fit_display_quads(
+    webcfg: Res<WebExtrasCfg>,
+    mut quad_q: Query<(&QuadLayout, &mut Transform, ChangeTrackers<QuadLayout>)>,
+) {
+    for (layout, mut transform, layout_tracker) in quad_q.iter_mut() {
+        if webcfg.is_changed() || layout_tracker.is_changed() {
+            *transform = layout.transform(webcfg.width, webcfg.height, transform.translation.z);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::AssetPlugin;
+
+    #[test]
+    fn row_packs_from_the_left() {
+        let row = QuadLayout::row(0.1, &[Vec2::new(0.2, 1.0), Vec2::new(0.4, 0.5)]);
+        assert!(row[0].center.abs_diff_eq(Vec2::new(0.2, 0.5), 1e-6));
+        assert!(row[1].center.abs_diff_eq(Vec2::new(0.6, 0.5), 1e-6));
+        assert_eq!(row[1].size, Vec2::new(0.4, 0.5));
+    }
+
+    #[test]
+    fn resizing_reuses_quads() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .insert_resource(WebExtrasCfg {
+                width: 800.0,
+                height: 600.0,
+                ..default()
+            })
+            .init_resource::<DisplayQuadMesh>()
+            .add_system(fit_display_quads);
+        let quad = app
+            .world
+            .spawn()
+            .insert(QuadLayout::new(Vec2::new(0.25, 0.5), Vec2::new(0.5, 1.0)))
+            .insert(Transform::from_xyz(0.0, 0.0, 2.0))
+            .id();
+        app.update();
+        let transform = *app.world.get::<Transform>(quad).unwrap();
+        assert_eq!(transform.translation, Vec3::new(-200.0, 0.0, 2.0));
+        assert_eq!(transform.scale, Vec3::new(400.0, 600.0, 1.0));
+
+        {
+            let mut webcfg = app.world.resource_mut::<WebExtrasCfg>();
+            webcfg.width = 400.0;
+            webcfg.height = 300.0;
+        }
+        app.update();
+        let transform = *app.world.get::<Transform>(quad).unwrap();
+        assert_eq!(transform.translation, Vec3::new(-100.0, 0.0, 2.0));
+        assert_eq!(transform.scale, Vec3::new(200.0, 300.0, 1.0));
+        assert_eq!(app.world.resource::<Assets<Mesh>>().len(), 1);
+    }
+}
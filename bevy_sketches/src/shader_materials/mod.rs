@@ -1,4 +1,5 @@
 pub mod core;
+pub mod layout;
 
 pub mod building_lights;
 pub mod eg_material;
@@ -1,5 +1,6 @@
-/// Steps to creating a new material
 use super::core::{BaseShaderMaterial, BaseShaderTrait, CommonUniformData};
+/// Steps to creating a new material
+use crate::pointer::Pointer;
 use bevy::{
     ecs::system::{lifetimeless::SRes, SystemParamItem},
     prelude::*,
@@ -40,6 +41,10 @@ impl BaseShaderTrait for ScalingNet {
     fn set_resolution(&mut self, resolution: Vec2) {
         self.0.uniform.resolution = resolution;
     }
+
+    fn set_pointer(&mut self, pointer: &Pointer, transform: &Transform) {
+        self.0.uniform.set_pointer(pointer, transform);
+    }
 }
 
 
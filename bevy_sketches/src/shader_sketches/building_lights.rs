@@ -0,0 +1,84 @@
+use crate::base::SketchBuilder;
+use crate::palette::with_alpha;
+use crate::shader_materials::{
+    building_lights::{BuildingLights, BuildingLightsUniform},
+    core::color_to_shader_vec4,
+    layout::{DisplayQuadBundle, DisplayQuadMesh, QuadLayout},
+};
+use crate::sketch::Sketch;
+use bevy::prelude::*;
+
+
+const SURFACE_WIDTH: f32 = 0.25;
+const SURFACE_HEIGHT: f32 = 1.0;
+
+
+pub struct BuildingLightsSketch;
+
+
+impl Sketch for BuildingLightsSketch {
+    fn name(&self) -> &'static str {
+        "building_lights_shader"
+    }
+
+    fn title(&self) -> &'static str {
+        "building lights shader"
+    }
+
+    fn description(&self) -> &'static str {
+        "The building lights shader of the shifty skylines, in three panels"
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["shader"]
+    }
+
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        sketch
+            .clear_color(Color::WHITE)
+            .material::<BuildingLights>()
+            .display_quads(poc_setup)
+    }
+}
+
+
+fn poc_setup(
+    mut commands: Commands,
+    quad: Res<DisplayQuadMesh>,
+    mut material_assets: ResMut<Assets<BuildingLights>>,
+) {
+    let layouts = QuadLayout::row(
+        0.0,
+        &[
+            Vec2::new(SURFACE_WIDTH, SURFACE_HEIGHT),
+            Vec2::new(SURFACE_WIDTH, SURFACE_HEIGHT),
+            Vec2::new(SURFACE_WIDTH * 2.0, SURFACE_HEIGHT),
+        ],
+    );
+    let materials = [
+        BuildingLights::default(),
+        BuildingLights {
+            uniform: BuildingLightsUniform {
+                background_color: color_to_shader_vec4(with_alpha(Color::MIDNIGHT_BLUE, 0.95)),
+                rand_modifier: 100.0,
+                ..default()
+            },
+            ..default()
+        },
+        BuildingLights {
+            uniform: BuildingLightsUniform {
+                background_color: color_to_shader_vec4(Color::MIDNIGHT_BLUE),
+                rand_modifier: 500.0,
+                ..default()
+            },
+            ..default()
+        },
+    ];
+    for (layout, material) in layouts.into_iter().zip(materials) {
+        commands.spawn_bundle(DisplayQuadBundle::new(
+            &quad,
+            material_assets.add(material),
+            layout,
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::base::SketchBuilder;
+use crate::shader_materials::{
+    erratic_wave::ErraticWaveMaterial,
+    layout::{DisplayQuadBundle, DisplayQuadMesh, QuadLayout},
+};
+use crate::sketch::Sketch;
+use bevy::prelude::*;
+
+
+pub struct ErraticWaveSketch;
+
+
+impl Sketch for ErraticWaveSketch {
+    fn name(&self) -> &'static str {
+        "erratic_wave_shader"
+    }
+
+    fn title(&self) -> &'static str {
+        "erratic wave shader"
+    }
+
+    fn description(&self) -> &'static str {
+        "The erratic wave shader, full screen"
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["shader"]
+    }
+
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        sketch
+            .clear_color(Color::BLACK)
+            .material::<ErraticWaveMaterial>()
+            .audio_reactive()
+            .display_quads(poc_setup)
+    }
+}
+
+
+fn poc_setup(
+    mut commands: Commands,
+    quad: Res<DisplayQuadMesh>,
+    mut material_assets: ResMut<Assets<ErraticWaveMaterial>>,
+) {
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        material_assets.add(ErraticWaveMaterial::default()),
+        QuadLayout::FULL,
+    ));
+}
@@ -0,0 +1,102 @@
+use crate::base::SketchBuilder;
+use crate::shader_materials::{
+    eg_material::ExampleMaterial,
+    eg_mo_data_material::AdditionalDataMaterial,
+    eg_res_material::ResExampleMaterial,
+    layout::{DisplayQuadBundle, DisplayQuadMesh, QuadLayout},
+};
+use crate::sketch::Sketch;
+use bevy::prelude::*;
+
+
+pub const HEIGHT: f32 = 900.0;
+pub const RESOLUTION: f32 = 16.0 / 9.0;
+// const SURFACE_PADDING: f32 = 0.009;
+// const SURFACE_WIDTH: f32 = 0.18;
+const SURFACE_PADDING: f32 = 0.001;
+const SURFACE_WIDTH: f32 = 0.1895;
+const SURFACE_HEIGHT: f32 = 0.995;
+const SURFACE2_WIDTH: f32 = SURFACE_WIDTH;
+// const SURFACE2_HEIGHT: f32 = SURFACE_HEIGHT * 1.5;
+const SURFACE2_HEIGHT: f32 = SURFACE_HEIGHT;
+const SURFACE3_WIDTH: f32 = SURFACE_WIDTH * 1.25;
+const SURFACE3_HEIGHT: f32 = SURFACE_HEIGHT * 0.9;
+const SURFACE4_WIDTH: f32 = SURFACE_WIDTH;
+const SURFACE4_HEIGHT: f32 = SURFACE_HEIGHT;
+
+
+pub struct MaterialPocSketch;
+
+
+impl Sketch for MaterialPocSketch {
+    fn name(&self) -> &'static str {
+        "shader_material_poc"
+    }
+
+    fn title(&self) -> &'static str {
+        "reusable shader material proof of concept"
+    }
+
+    fn description(&self) -> &'static str {
+        "Proof of concept of reusable shader materials, side by side"
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["shader", "poc"]
+    }
+
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        sketch
+            .clear_color(Color::rgb(0.3, 0.41, 0.48))
+            .material::<ExampleMaterial>()
+            .material::<ResExampleMaterial>()
+            .material::<AdditionalDataMaterial>()
+            .display_quads(poc_setup)
+    }
+}
+
+
+fn poc_setup(
+    mut commands: Commands,
+    quad: Res<DisplayQuadMesh>,
+    mut eg_material_assets: ResMut<Assets<ExampleMaterial>>,
+    mut res_eg_material_assets: ResMut<Assets<ResExampleMaterial>>,
+    mut eg_mo_data_material_assets: ResMut<Assets<AdditionalDataMaterial>>,
+) {
+    let layouts = QuadLayout::row(
+        SURFACE_PADDING,
+        &[
+            Vec2::new(SURFACE_WIDTH, SURFACE_HEIGHT),
+            Vec2::new(SURFACE2_WIDTH, SURFACE2_HEIGHT),
+            Vec2::new(SURFACE3_WIDTH, SURFACE3_HEIGHT),
+            Vec2::new(SURFACE4_WIDTH, SURFACE4_HEIGHT),
+            Vec2::new(SURFACE4_WIDTH, SURFACE4_HEIGHT),
+        ],
+    );
+
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        eg_material_assets.add(ExampleMaterial::default()),
+        layouts[0],
+    ));
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        res_eg_material_assets.add(ResExampleMaterial::default()),
+        layouts[1],
+    ));
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        res_eg_material_assets.add(ResExampleMaterial::default()),
+        layouts[2],
+    ));
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        eg_mo_data_material_assets.add(AdditionalDataMaterial::default()),
+        layouts[3],
+    ));
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        eg_mo_data_material_assets.add(AdditionalDataMaterial::with_rects(3)),
+        layouts[4],
+    ));
+}
@@ -0,0 +1,6 @@
+// DisplayQuad sketches of the shader materials in `crate::shader_materials`
+
+pub mod building_lights;
+pub mod erratic_wave;
+pub mod material_poc;
+pub mod scaling_net;
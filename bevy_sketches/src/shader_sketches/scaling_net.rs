@@ -0,0 +1,49 @@
+use crate::base::SketchBuilder;
+use crate::shader_materials::{
+    layout::{DisplayQuadBundle, DisplayQuadMesh, QuadLayout},
+    scaling_net::ScalingNet,
+};
+use crate::sketch::Sketch;
+use bevy::prelude::*;
+
+
+pub struct ScalingNetSketch;
+
+
+impl Sketch for ScalingNetSketch {
+    fn name(&self) -> &'static str {
+        "scaling_net_shader"
+    }
+
+    fn title(&self) -> &'static str {
+        "scaling net shader"
+    }
+
+    fn description(&self) -> &'static str {
+        "The scaling net shader, full screen"
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["shader"]
+    }
+
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        sketch
+            .clear_color(Color::SEA_GREEN)
+            .material::<ScalingNet>()
+            .display_quads(poc_setup)
+    }
+}
+
+
+fn poc_setup(
+    mut commands: Commands,
+    quad: Res<DisplayQuadMesh>,
+    mut scaling_net_assets: ResMut<Assets<ScalingNet>>,
+) {
+    commands.spawn_bundle(DisplayQuadBundle::new(
+        &quad,
+        scaling_net_assets.add(ScalingNet::default()),
+        QuadLayout::FULL,
+    ));
+}
@@ -1,157 +1,591 @@
+/*
+ * shapegen
+ *
+ * Random shapes for the lyon sketches.
+ *
+ * Random polygons are star-shaped: the vertices go around the center at increasing angles, one
+ * in each of `segments` equal sectors (jittered inside it), each at a random distance from the
+ * center. From 4 segments up no gap between two angles reaches half a turn and no vertex is at
+ * the center, so the polygon is star-shaped around the center and its edges can't cross.
+ * Triangles can have a wider gap, which leaves the center outside, but the edges of a triangle
+ * never cross. Scaling to the canvas (`max_x`, `max_y`) is linear, which keeps the polygon
+ * simple, and everything stays in `f32` so large canvases and sub-pixel sizes work too.
+ *
+ * Other kinds of shapes implement `ShapeGenerator`: convex hulls of random points, stars,
+ * noise-perturbed circles, smoothed blobs and superellipses. Sketches pick one with a `ShapeKind`
+ * in their params, so presets can switch between them.
+ *
+ * `is_simple` checks a polygon for crossing or overlapping edges.
+ *
+ * Instructed by:
+ * https://en.wikibooks.org/wiki/Algorithm_Implementation/Geometry/Convex_hull/Monotone_chain
+ * https://en.wikipedia.org/wiki/Centripetal_Catmull%E2%80%93Rom_spline
+ * https://en.wikipedia.org/wiki/Superellipse
+ */
+use bevy::math::DVec2;
 use bevy::prelude::*;
 use bevy_prototype_lyon::prelude::*;
-use rand::prelude::thread_rng;
-use rand::Rng;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
 
 
-/// Function to safeguard against invalid arguments when generating random range
-fn gen_random_safely(start: f32, end: f32) -> f32 {
-    let mut rng = thread_rng();
+/// How far (as a fraction of its sector) a vertex's angle can move from its sector's middle.
+/// Below 0.5, so the angles keep increasing, with gaps of 0.2 to 1.8 sectors between them. That
+/// stays under half a turn from 4 segments up (at most 1.8 × 90° = 162°). A triangle's gap can
+/// reach 1.8 × 120° = 216°, but three vertices make a simple polygon whatever their angles.
+const ANGLE_JITTER: f32 = 0.4;
+/// Closest a vertex gets to the center, as a fraction of the distance to the edge of the canvas
+const MIN_RADIUS_RATIO: f32 = 0.2;
+/// Vertices of the smooth shapes (noisy circles, superellipses), and of the blobs per control point
+const SMOOTH_SEGMENTS: usize = 64;
+const BLOB_SAMPLES: usize = 8;
 
-    // if start >= end {
-    //     debug!("!!! s {}, e {}", start, end);
-    //     return end;
-    // }
-    // return rng.gen_range(start..end);
 
-    // Returning as i16 might somewhat improve results (not as many small floating point
-    // variations that translate to screen coord variations). But dunno if it does much good.
-    let start = start as i16;
-    let end = end as i16;
+/// Vertices of a random simple polygon of 3 to `max_segments` (at least 3) vertices, centered on
+/// the origin and inside `-max_x..=max_x`, `-max_y..=max_y`, counterclockwise
+pub fn random_polygon<R: Rng + ?Sized>(
+    rng: &mut R,
+    max_x: f32,
+    max_y: f32,
+    max_segments: u8,
+) -> Vec<Vec2> {
+    let num_segments = rng.gen_range(3..=max_segments.max(3));
+    let sector = TAU / num_segments as f32;
+    // Starting at a random angle, so the first sector isn't always on the right
+    let start = rng.gen_range(0.0..TAU);
+    return (0..num_segments)
+        .map(|i| {
+            let jitter = rng.gen_range(-ANGLE_JITTER..=ANGLE_JITTER);
+            let angle = start + (i as f32 + 0.5 + jitter) * sector;
+            let radius = rng.gen_range(MIN_RADIUS_RATIO..=1.0);
+            Vec2::new(angle.cos() * max_x, angle.sin() * max_y) * radius
+        })
+        .collect();
+}
+
 
-    if start >= end {
-        debug!("!!! s {}, e {}", start, end);
-        // return end;
-        return end as f32;
+/// A closed path through the points
+pub fn polygon_builder(points: &[Vec2]) -> PathBuilder {
+    let mut path_builder = PathBuilder::new();
+    if let Some((first, rest)) = points.split_first() {
+        path_builder.move_to(*first);
+        for point in rest {
+            path_builder.line_to(*point);
+        }
+        path_builder.close();
     }
-    return rng.gen_range(start..end) as f32;
+    return path_builder;
 }
 
 
-/// @TODO Good enough for now, but can be improved.
-///
-/// Currently the randomization reaches the end of range in a quadrant too quickly,
-/// so even if you increase number of segments, this function reaches the end of the range sooner.
+/// A random polygon (see `random_polygon`) as a path.
 ///
-/// So it doesn't work well for a large number of segments.
-///
-/// One idea for improvement is to break down each quadrant to sub-ranges for gen_range().
-pub fn random_polygon_builder(max_x: f32, max_y: f32, max_segments: u8) -> PathBuilder {
-    let mut rng = thread_rng();
+/// Some kind of weirdness with the Path location prevents returning the built shape, so this
+/// returns the builder itself.
+pub fn random_polygon_builder<R: Rng + ?Sized>(
+    rng: &mut R,
+    max_x: f32,
+    max_y: f32,
+    max_segments: u8,
+) -> PathBuilder {
+    let points = random_polygon(rng, max_x, max_y, max_segments);
+    debug!("--{} segments shape---", points.len());
+    return polygon_builder(&points);
+}
+
+
+/// Makes random closed shapes, centered on the origin and inside `-max_x..=max_x`,
+/// `-max_y..=max_y`
+pub trait ShapeGenerator {
+    /// Vertices of a new shape, counterclockwise
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2>;
+
+    /// A new shape as a path, see `random_polygon_builder` on returning the builder
+    fn builder(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> PathBuilder {
+        return polygon_builder(&self.points(rng, max_x, max_y));
+    }
+}
+
+
+/// The kinds of shapes, for sketch params
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect_value]
+pub enum ShapeKind {
+    Polygon,
+    ConvexHull,
+    Star,
+    NoisyCircle,
+    Blob,
+    Superellipse,
+}
+
+
+impl Default for ShapeKind {
+    fn default() -> Self {
+        Self::Polygon
+    }
+}
+
+
+impl ShapeKind {
+    /// The generator of this kind with its default look, with `max_segments` (at least 3) as the
+    /// most vertices, points or control points, where the kind has a count to pick
+    pub fn generator(self, max_segments: u8) -> Box<dyn ShapeGenerator> {
+        let max_segments = max_segments.max(3);
+        match self {
+            Self::Polygon => Box::new(RandomPolygon { max_segments }),
+            Self::ConvexHull => Box::new(ConvexHull {
+                max_points: max_segments,
+            }),
+            Self::Star => Box::new(Star {
+                max_points: max_segments,
+                ..default()
+            }),
+            Self::NoisyCircle => Box::new(NoisyCircle::default()),
+            Self::Blob => Box::new(Blob {
+                max_control_points: max_segments,
+                ..default()
+            }),
+            Self::Superellipse => Box::new(Superellipse::default()),
+        }
+    }
+}
+
+
+/// See `random_polygon`
+#[derive(Clone, Debug)]
+pub struct RandomPolygon {
+    pub max_segments: u8,
+}
+
+
+impl ShapeGenerator for RandomPolygon {
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2> {
+        return random_polygon(rng, max_x, max_y, self.max_segments);
+    }
+}
+
+
+/// Convex hull of 3 to `max_points` random points of the ellipse filling the bounds
+#[derive(Clone, Debug)]
+pub struct ConvexHull {
+    pub max_points: u8,
+}
+
+
+impl ShapeGenerator for ConvexHull {
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2> {
+        let count = rng.gen_range(3..=self.max_points.max(3));
+        let points: Vec<Vec2> = (0..count)
+            .map(|_| {
+                let angle = rng.gen_range(0.0..TAU);
+                // Uniform over the disc, rather than bunched up in the middle
+                let radius = rng.gen_range(MIN_RADIUS_RATIO..=1.0f32).sqrt();
+                Vec2::new(angle.cos() * max_x, angle.sin() * max_y) * radius
+            })
+            .collect();
+        return convex_hull(&points);
+    }
+}
+
+
+/// Star with 3 to `max_points` tips on the ellipse filling the bounds, and the inner vertices at
+/// `inner_ratio` of the way there (picked at random from the range)
+#[derive(Clone, Debug)]
+pub struct Star {
+    pub max_points: u8,
+    pub inner_ratio: (f32, f32),
+}
+
+
+impl Default for Star {
+    fn default() -> Self {
+        Self {
+            max_points: 8,
+            inner_ratio: (0.35, 0.6),
+        }
+    }
+}
+
+
+impl ShapeGenerator for Star {
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2> {
+        let tips = rng.gen_range(3..=self.max_points.max(3));
+        let (low, high) = self.inner_ratio;
+        let inner = if low < high {
+            rng.gen_range(low..high)
+        } else {
+            low
+        };
+        let inner = inner.clamp(MIN_RADIUS_RATIO, 1.0);
+        let start = rng.gen_range(0.0..TAU);
+        let step = TAU / (tips as f32 * 2.0);
+        return (0..tips * 2)
+            .map(|i| {
+                let angle = start + i as f32 * step;
+                let radius = if i % 2 == 0 { 1.0 } else { inner };
+                Vec2::new(angle.cos() * max_x, angle.sin() * max_y) * radius
+            })
+            .collect();
+    }
+}
+
+
+/// Circle (an ellipse filling the bounds) with its radius shrunk by up to `noise` by a sum of
+/// random waves, from 2 to `max_waves` bumps around
+#[derive(Clone, Debug)]
+pub struct NoisyCircle {
+    /// From 0.0 (a plain circle) to 1.0
+    pub noise: f32,
+    pub max_waves: u8,
+}
+
+
+impl Default for NoisyCircle {
+    fn default() -> Self {
+        Self {
+            noise: 0.3,
+            max_waves: 6,
+        }
+    }
+}
 
-    let num_segments = rng.gen_range(3..=max_segments);
-    let mut path_builder = PathBuilder::new();
 
-    let mut last_x: f32 = 0.0;
-    let mut last_y: f32 = 0.0;
-    let mut current_quad = 1;
-
-    // @HINT
-    // Using an underscore to discard the iterator value since it's not being used
-    for _i in 1..=num_segments {
-        let segment_place: f32 = _i as f32 / num_segments as f32;
-
-        if segment_place <= 0.25 {
-            if last_x == 0.0 {
-                debug!("entered quad 1");
-                last_x = gen_random_safely(-max_x, 0.0);
-                last_y = gen_random_safely(0.0, max_y);
-                path_builder.move_to(Vec2::new(last_x, last_y));
-            } else {
-                last_x = gen_random_safely(last_x, 0.0);
-                // For quads 1 & 3 using `last_y` seems less necessary in terms of
-                // edges crossing each other. Crossing does occur, but much less
-                // frequently. However, using it does eliminate all crossing, seemingly
-                // at the cost of more "conservative shapes" (but I have not verified it).
-                last_y = gen_random_safely(last_y, max_y);
-                // last_y = rng.gen_range(0.0..winsetup.max_y);
-                path_builder.line_to(Vec2::new(last_x, last_y));
+impl ShapeGenerator for NoisyCircle {
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2> {
+        // Whole numbers of waves around, so the noise lines up where the circle closes
+        let waves: Vec<(f32, f32, f32)> = (2..=self.max_waves.max(2))
+            .map(|bumps| {
+                let amplitude = rng.gen_range(0.0..=1.0) / bumps as f32;
+                (bumps as f32, amplitude, rng.gen_range(0.0..TAU))
+            })
+            .collect();
+        let total: f32 = waves.iter().map(|(_, amplitude, _)| amplitude).sum();
+        let noise = self.noise.clamp(0.0, 1.0 - MIN_RADIUS_RATIO);
+        return (0..SMOOTH_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / SMOOTH_SEGMENTS as f32 * TAU;
+                let wave: f32 = waves
+                    .iter()
+                    .map(|(bumps, amplitude, phase)| amplitude * (bumps * angle + phase).sin())
+                    .sum();
+                // From -1.0 to 1.0
+                let wave = if total > 0.0 { wave / total } else { 0.0 };
+                let radius = 1.0 - noise * (1.0 - wave) / 2.0;
+                Vec2::new(angle.cos() * max_x, angle.sin() * max_y) * radius
+            })
+            .collect();
+    }
+}
+
+
+/// Smooth closed curve through 4 to `max_control_points` random control points (a centripetal
+/// Catmull-Rom spline), scaled down to the bounds when the curve bulges out of them
+#[derive(Clone, Debug)]
+pub struct Blob {
+    pub max_control_points: u8,
+    /// Closest a control point gets to the center, as a fraction of the distance to the bounds
+    pub min_radius: f32,
+}
+
+
+impl Default for Blob {
+    fn default() -> Self {
+        Self {
+            max_control_points: 8,
+            min_radius: 0.5,
+        }
+    }
+}
+
+
+impl ShapeGenerator for Blob {
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2> {
+        let count = rng.gen_range(4..=self.max_control_points.max(4));
+        let sector = TAU / count as f32;
+        let start = rng.gen_range(0.0..TAU);
+        let min_radius = self.min_radius.clamp(MIN_RADIUS_RATIO, 1.0);
+        let controls: Vec<Vec2> = (0..count)
+            .map(|i| {
+                let angle = start + (i as f32 + rng.gen_range(-0.25..=0.25)) * sector;
+                Vec2::new(angle.cos(), angle.sin()) * rng.gen_range(min_radius..=1.0)
+            })
+            .collect();
+        let curve = catmull_rom(&controls, BLOB_SAMPLES);
+        let reach = curve
+            .iter()
+            .fold(1.0f32, |reach, point| reach.max(point.abs().max_element()));
+        return curve
+            .iter()
+            .map(|point| *point / reach * Vec2::new(max_x, max_y))
+            .collect();
+    }
+}
+
+
+/// Superellipse `|x / max_x|^n + |y / max_y|^n = 1` with the exponent `n` picked at random from
+/// the range: below 1.0 it pinches into a star, 2.0 is an ellipse, higher values get boxier
+#[derive(Clone, Debug)]
+pub struct Superellipse {
+    pub exponent: (f32, f32),
+}
+
+
+impl Default for Superellipse {
+    fn default() -> Self {
+        Self {
+            exponent: (0.6, 5.0),
+        }
+    }
+}
+
+
+impl ShapeGenerator for Superellipse {
+    fn points(&self, rng: &mut dyn RngCore, max_x: f32, max_y: f32) -> Vec<Vec2> {
+        let (low, high) = self.exponent;
+        let exponent = if low < high {
+            rng.gen_range(low..high)
+        } else {
+            low
+        };
+        let power = 2.0 / exponent.max(0.1);
+        let curve = |t: f32| t.signum() * t.abs().powf(power);
+        return (0..SMOOTH_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / SMOOTH_SEGMENTS as f32 * TAU;
+                Vec2::new(curve(angle.cos()) * max_x, curve(angle.sin()) * max_y)
+            })
+            .collect();
+    }
+}
+
+
+/// Vertices of the convex hull of the points, counterclockwise starting from the leftmost one
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    // Monotone chain: the lower hull left to right, then the upper hull back
+    let mut hull: Vec<Vec2> = Vec::with_capacity(sorted.len() * 2);
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if orientation(a.as_dvec2(), b.as_dvec2(), point.as_dvec2()) > 0.0 {
+                    break;
+                }
+                hull.pop();
             }
-            debug!(
-                "---i: {}, segment_place: {}, x: {}, y: {}",
-                _i, segment_place, last_x, last_y
-            );
-            continue;
+            hull.push(point);
+        }
+        // The last point of each chain starts the other one
+        hull.pop();
+    }
+    return hull;
+}
+
+
+/// Closed centripetal Catmull-Rom spline through the points, `samples` vertices per span
+fn catmull_rom(controls: &[Vec2], samples: usize) -> Vec<Vec2> {
+    let count = controls.len();
+    let mut curve = Vec::with_capacity(count * samples);
+    for i in 0..count {
+        let p0 = controls[(i + count - 1) % count];
+        let p1 = controls[i];
+        let p2 = controls[(i + 1) % count];
+        let p3 = controls[(i + 2) % count];
+        // Knots spaced by the square root of the distances
+        let knot = |a: Vec2, b: Vec2| a.distance(b).sqrt().max(f32::EPSILON);
+        let t1 = knot(p0, p1);
+        let t2 = t1 + knot(p1, p2);
+        let t3 = t2 + knot(p2, p3);
+        let lerp = |a: Vec2, b: Vec2, ta: f32, tb: f32, t: f32| {
+            a * ((tb - t) / (tb - ta)) + b * ((t - ta) / (tb - ta))
+        };
+        for sample in 0..samples {
+            let t = t1 + (t2 - t1) * sample as f32 / samples as f32;
+            let a1 = lerp(p0, p1, 0.0, t1, t);
+            let a2 = lerp(p1, p2, t1, t2, t);
+            let a3 = lerp(p2, p3, t2, t3, t);
+            let b1 = lerp(a1, a2, 0.0, t2, t);
+            let b2 = lerp(a2, a3, t1, t3, t);
+            curve.push(lerp(b1, b2, t1, t2, t));
         }
+    }
+    return curve;
+}
 
-        if segment_place > 0.25 && segment_place <= 0.5 {
-            if current_quad < 2 {
-                debug!("entered quad 2");
-                last_x = gen_random_safely(0.0, max_x);
-                last_y = gen_random_safely(0.0, max_y);
-                path_builder.line_to(Vec2::new(last_x, last_y));
-            } else {
-                last_x = gen_random_safely(last_x, max_x);
-                // In quads 2 & 4, where the horizontal direction of the shape generation
-                // changes on the next quadrant, it seems that using `last_y` in the range
-                // makes better shapes (edges don't cross)
-                //
-                // last_y = rng.gen_range(0.0..winsetup.max_y);
-                last_y = gen_random_safely(0.0, last_y);
-                path_builder.line_to(Vec2::new(last_x, last_y));
+
+/// Whether the closed polygon through the points is simple: at least 3 vertices, and no two
+/// edges cross or touch except where neighbours share a vertex
+pub fn is_simple(points: &[Vec2]) -> bool {
+    let count = points.len();
+    if count < 3 {
+        return false;
+    }
+    let edge = |i: usize| (points[i].as_dvec2(), points[(i + 1) % count].as_dvec2());
+    for i in 0..count {
+        let (a, b) = edge(i);
+        if a == b {
+            return false;
+        }
+        for j in (i + 1)..count {
+            let (c, d) = edge(j);
+            let neighbours = j == i + 1 || (i == 0 && j == count - 1);
+            if neighbours {
+                // Sharing a vertex is fine, folding back over each other isn't
+                let (shared, other_a, other_c) = if j == i + 1 { (b, a, d) } else { (a, b, c) };
+                let overlap = orientation(other_a, shared, other_c) == 0.0
+                    && (other_a - shared).dot(other_c - shared) > 0.0;
+                if overlap {
+                    return false;
+                }
+            } else if segments_touch(a, b, c, d) {
+                return false;
             }
-            debug!(
-                "---i: {}, segment_place: {}, x: {}, y: {}",
-                _i, segment_place, last_x, last_y
-            );
-            current_quad = 2;
-            continue;
         }
+    }
+    return true;
+}
+
+
+/// Twice the signed area of the triangle, positive if counterclockwise
+fn orientation(a: DVec2, b: DVec2, c: DVec2) -> f64 {
+    (b - a).perp_dot(c - a)
+}
+
 
-        if segment_place > 0.5 && segment_place <= 0.75 {
-            if current_quad < 3 {
-                debug!("entered quad 3");
-                last_x = gen_random_safely(0.0, max_x);
-                last_y = gen_random_safely(-max_y, 0.0);
-                path_builder.line_to(Vec2::new(last_x, last_y));
-            } else {
-                last_x = gen_random_safely(0.0, last_x);
-                // For quads 1 & 3 using `last_y` seems less necessary in terms of
-                // edges crossing each other. Crossing does occur, but much less
-                // frequently. However, using it does eliminate all crossing, seemingly
-                // at the cost of more "conservative shapes" (but I have not verified it).
-                last_y = gen_random_safely(-max_y, last_y);
-                // last_y = rng.gen_range(-winsetup.max_y..0.0);
-                path_builder.line_to(Vec2::new(last_x, last_y));
+fn segments_touch(a: DVec2, b: DVec2, c: DVec2, d: DVec2) -> bool {
+    let on_segment =
+        |p: DVec2, q: DVec2, r: DVec2| r.cmpge(p.min(q)).all() && r.cmple(p.max(q)).all();
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    if o1 * o2 < 0.0 && o3 * o4 < 0.0 {
+        return true;
+    }
+    return (o1 == 0.0 && on_segment(a, b, c))
+        || (o2 == 0.0 && on_segment(a, b, d))
+        || (o3 == 0.0 && on_segment(c, d, a))
+        || (o4 == 0.0 && on_segment(c, d, b));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SketchRng;
+
+    #[test]
+    fn detects_crossing_edges() {
+        let square = [
+            Vec2::new(-1.0, -1.0),
+            Vec2::new(1.0, -1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(-1.0, 1.0),
+        ];
+        assert!(is_simple(&square));
+
+        let bow_tie = [square[0], square[2], square[1], square[3]];
+        assert!(!is_simple(&bow_tie));
+
+        // A vertex touching another edge
+        let touching = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 2.0),
+        ];
+        assert!(!is_simple(&touching));
+
+        // Folding back along the previous edge
+        let spike = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 2.0),
+        ];
+        assert!(!is_simple(&spike));
+        assert!(!is_simple(&square[..2]));
+    }
+
+    #[test]
+    fn random_polygons_are_simple() {
+        let mut rng = SketchRng::new(42);
+        let canvases = [(640.0, 360.0), (0.5, 0.25), (20_000_000.0, 50_000.0)];
+        for max_segments in [3, 4, 5, 8, 16, 50, 100, 200, 255] {
+            for (max_x, max_y) in canvases {
+                for _ in 0..20 {
+                    let points = random_polygon(&mut rng, max_x, max_y, max_segments);
+                    assert!((3..=max_segments as usize).contains(&points.len()));
+                    assert!(is_simple(&points), "crossing edges: {:?}", points);
+                    for point in &points {
+                        assert!(point.x.abs() <= max_x * 1.0001);
+                        assert!(point.y.abs() <= max_y * 1.0001);
+                    }
+                }
             }
-            debug!(
-                "---i: {}, segment_place: {}, x: {}, y: {}",
-                _i, segment_place, last_x, last_y
-            );
-            current_quad = 3;
-            continue;
         }
+    }
 
-        if segment_place > 0.75 && segment_place <= 1.0 {
-            // Check if it's the first time in this quadrant
-            if current_quad < 4 {
-                debug!("entered quad 4");
-                last_x = gen_random_safely(-max_x, 0.0);
-                last_y = gen_random_safely(-max_y, 0.0);
-                path_builder.line_to(Vec2::new(last_x, last_y));
-            } else {
-                last_x = gen_random_safely(-max_x, last_x);
-                // In quads 2 & 4, where the horizontal direction of the shape generation
-                // changes on the next quadrant, it seems that using `last_y` in the range
-                // makes better shapes (edges don't cross)
-                //
-                // last_y = rng.gen_range(-winsetup.max_y..0.0);
-                last_y = gen_random_safely(last_y, 0.0);
-                path_builder.line_to(Vec2::new(last_x, last_y));
+    #[test]
+    fn same_seed_same_polygon() {
+        let polygon = |seed| random_polygon(&mut SketchRng::new(seed), 640.0, 360.0, 30);
+        assert_eq!(polygon(7), polygon(7));
+        assert_ne!(polygon(7), polygon(8));
+    }
+
+    #[test]
+    fn generated_shapes_are_simple() {
+        let kinds = [
+            ShapeKind::Polygon,
+            ShapeKind::ConvexHull,
+            ShapeKind::Star,
+            ShapeKind::NoisyCircle,
+            ShapeKind::Blob,
+            ShapeKind::Superellipse,
+        ];
+        let mut rng = SketchRng::new(42);
+        for kind in kinds {
+            for max_segments in [0, 3, 8, 32] {
+                let generator = kind.generator(max_segments);
+                for _ in 0..20 {
+                    let points = generator.points(&mut rng, 640.0, 360.0);
+                    assert!(is_simple(&points), "{:?}: {:?}", kind, points);
+                    for point in &points {
+                        assert!(point.x.abs() <= 640.0 * 1.0001, "{:?}: {}", kind, point);
+                        assert!(point.y.abs() <= 360.0 * 1.0001, "{:?}: {}", kind, point);
+                    }
+                }
             }
-            debug!(
-                "---i: {}, segment_place: {}, x: {}, y: {}",
-                _i, segment_place, last_x, last_y
-            );
-            current_quad = 4;
-            continue;
         }
     }
-    debug!("--end-{} segments shape---\n", num_segments);
-    path_builder.close();
 
-    // Some kind of weirdness with the Path location is preventing me from returning the
-    // built shape, so I am returning the builder itself
-    // return path_builder.build().0;
-    return path_builder;
+    #[test]
+    fn hull_drops_inner_points() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(0.5, 1.5),
+        ];
+        let hull = convex_hull(&points);
+        assert_eq!(
+            hull,
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(0.0, 2.0),
+            ]
+        );
+    }
 }
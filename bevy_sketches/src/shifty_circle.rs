@@ -1,14 +1,25 @@
-use crate::base::sketch;
+use crate::audio_analysis::AudioLevels;
+use crate::base::SketchBuilder;
+use crate::noise::NoiseField;
+use crate::palette::{mix_oklab, with_alpha, Palette, PaletteKind};
+use crate::placement::Placer;
+use crate::pointer::Pointer;
+use crate::presets::DEFAULT_PRESET;
+use crate::rng::SketchRng;
 use crate::shader_materials::{
-    building_lights::{BuildingLights, BuildingLightsUniform},
-    core::{color_to_shader_vec3, DisplayQuad, ShaderMaterialPlugin},
+    building_lights::{busyness, BuildingLights, BuildingLightsUniform, DEFAULT_LIGHTS},
+    core::{color_to_shader_vec4, DisplayQuad, NoiseUniformData, PaletteUniformData},
 };
-use bevy::core::FixedTimestep;
+use crate::shapegen::{polygon_builder, ShapeKind};
+use crate::sketch::{Sketch, SketchParams};
+use crate::sketch_clock::SketchClock;
+use crate::svg_import::SvgPath;
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use bevy_prototype_lyon::prelude::*;
 use bevy_web_extras::prelude::*;
-use rand::prelude::thread_rng;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, TAU};
 
 // Place window on top right corner
 const SHIFTY_CIRCLE_STEP: f64 = 0.01;
@@ -17,30 +28,128 @@ const SHIFTY_CHANGE_STEP: f64 = 1.5;
 // This reddish color is kind of nice too
 const CLEAR_COLOR: Color = Color::rgb(0.14, 0.13, 0.03);
 // const CLEAR_COLOR: Color = Color::rgb(0.13, 0.13, 0.03);
-const SHIFTY_CIRCLE_COUNT: u8 = 3;
-const SHIFTY_CIRCLE_RADIUS: f32 = 40.0;
-const SHIFTY_CIRCLE_STROKE: f32 = 1.0;
-const SHIFTY_CIRCLE_MIN_SPEED: f32 = 0.01;
-const SHIFTY_CIRCLE_MAX_SPEED: f32 = 25.0;
-// const SHIFTY_CIRCLE_FILL_COLOR: Color = Color::rgba(0.784, 0.713, 0.345, 0.0);
-// const SHIFTY_CIRCLE_STROKE_COLOR: Color = Color::rgba(0.784, 0.713, 0.345, 0.0);
-const SHIFTY_CIRCLE_FILL_COLOR: Color = Color::rgba(0.04, 0.06, 0.06, 1.0);
-// const SHIFTY_CIRCLE_STROKE_COLOR: Color = SHIFTY_CIRCLE_FILL_COLOR;
-const SHIFTY_CIRCLE_STROKE_COLOR: Color = Color::rgba(0.27, 0.09, 0.02, 0.0);
-const BUILDING_MIN_WIDTH: f32 = 10.0;
-const BUILDING_MAX_WIDTH: f32 = 200.0;
-// const BUILDING_COLOR: Color = Color::GREEN;
-const BUILDING_COLOR: Color = Color::rgb(0.12, 0.12, 0.02);
-const BUILDING_FORE_COLOR: Color = Color::rgb(0.1, 0.09, 0.0);
-// Ratio of x below means the tallest building is 1/x screen height
-const BUILDING_MAX_HEIGHT_RATIO: f32 = 2.0;
-const BUILDING_MIN_HEIGHT_RATIO: f32 = 16.0;
 const PULSATING_STEP: f64 = 0.1;
-const PULSE_MAX_ALPHA: f32 = 1.0;
-// const PULSE_SCALE: f64 = 0.1;
-const PULSE_SCALE: f64 = 0.6;
-const PULSE_AMPLITUDE: f64 = 1.0;
-const PULSE_FREQ: f64 = 2.0;
+/// Most vertices (or tips, or control points) of generated circle shapes
+const SHIFTY_SHAPE_SEGMENTS: u8 = 8;
+/// Busy districts of the skyline (see `busyness`) are about this many pixels apart
+const SKYLINE_DISTRICT_WIDTH: f32 = 400.0;
+
+
+/// Look of the sketch, editable at runtime (see `crate::params`) and kept as presets in
+/// `assets/presets/shifty/` (see `crate::presets`). Each of these presets is also a variation of
+/// the sketch (see `SHIFTY_VARIATIONS`).
+#[derive(Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShiftyParams {
+    /// See `ShiftyShape`. Only set by presets: the editor shows it but can't change it, as it is
+    /// reflected as an opaque value (bevy can't reflect the fields of an enum's variants)
+    pub shape: ShiftyShape,
+    pub circle_count: u8,
+    pub circle_radius: f32,
+    pub circle_stroke: f32,
+    pub circle_min_speed: f32,
+    pub circle_max_speed: f32,
+    /// `Custom` for the colors below, or a palette generated from `palette_seed` (see
+    /// `crate::palette`), which keeps only the alphas of the circle colors
+    pub palette: PaletteKind,
+    pub palette_seed: u32,
+    pub circle_fill_color: Color,
+    pub circle_stroke_color: Color,
+    pub building_min_width: f32,
+    pub building_max_width: f32,
+    pub building_color: Color,
+    pub building_fore_color: Color,
+    /// Ratio of x below means the tallest building is 1/x screen height
+    pub building_max_height_ratio: f32,
+    pub building_min_height_ratio: f32,
+    pub pulse_max_alpha: f32,
+    pub pulse_scale: f64,
+    pub pulse_amplitude: f64,
+    pub pulse_freq: f64,
+    /// How much the bass of the music adds to the pulse (see `crate::audio_analysis`)
+    pub pulse_bass: f64,
+    /// How far new destinations move toward the pointer while it is pressed, from 0.0 (not at
+    /// all) to 1.0 (onto the pointer). Negative values push them away.
+    pub pointer_pull: f32,
+}
+
+
+impl Default for ShiftyParams {
+    fn default() -> Self {
+        Self {
+            shape: ShiftyShape::Circle,
+            circle_count: 3,
+            circle_radius: 40.0,
+            circle_stroke: 1.0,
+            circle_min_speed: 0.01,
+            circle_max_speed: 25.0,
+            palette: PaletteKind::Custom,
+            palette_seed: 0,
+            // circle_fill_color: Color::rgba(0.784, 0.713, 0.345, 0.0),
+            // circle_stroke_color: Color::rgba(0.784, 0.713, 0.345, 0.0),
+            circle_fill_color: Color::rgba(0.04, 0.06, 0.06, 1.0),
+            circle_stroke_color: Color::rgba(0.27, 0.09, 0.02, 0.0),
+            building_min_width: 10.0,
+            building_max_width: 200.0,
+            // building_color: Color::GREEN,
+            building_color: Color::rgb(0.12, 0.12, 0.02),
+            building_fore_color: Color::rgb(0.1, 0.09, 0.0),
+            building_max_height_ratio: 2.0,
+            building_min_height_ratio: 16.0,
+            pulse_max_alpha: 1.0,
+            // pulse_scale: 0.1,
+            pulse_scale: 0.6,
+            pulse_amplitude: 1.0,
+            pulse_freq: 2.0,
+            pulse_bass: 0.8,
+            pointer_pull: 0.6,
+        }
+    }
+}
+
+
+/// Colors of the sketch, see `ShiftyParams::palette`
+struct ShiftyColors {
+    clear: Color,
+    circle_fill: Color,
+    circle_stroke: Color,
+    building: Color,
+    building_fore: Color,
+    /// Window lights, see `DEFAULT_LIGHTS`
+    lights: Palette,
+}
+
+
+impl ShiftyParams {
+    fn colors(&self) -> ShiftyColors {
+        let palette = match self.palette.generate(self.palette_seed) {
+            Some(palette) => palette,
+            None => {
+                return ShiftyColors {
+                    clear: CLEAR_COLOR,
+                    circle_fill: self.circle_fill_color,
+                    circle_stroke: self.circle_stroke_color,
+                    building: self.building_color,
+                    building_fore: self.building_fore_color,
+                    lights: DEFAULT_LIGHTS,
+                }
+            }
+        };
+        // Buildings in the background's shadow, lit by the other roles
+        let shadow = mix_oklab(palette.background, Color::BLACK, 0.5);
+        ShiftyColors {
+            clear: palette.background,
+            circle_fill: with_alpha(palette.fill, self.circle_fill_color.a()),
+            circle_stroke: with_alpha(palette.stroke, self.circle_stroke_color.a()),
+            building: mix_oklab(palette.background, shadow, 0.5),
+            building_fore: shadow,
+            lights: Palette {
+                background: mix_oklab(shadow, Color::BLACK, 0.5),
+                ..palette
+            },
+        }
+    }
+}
 
 
 // Resource for app globals.
@@ -71,124 +180,186 @@ pub struct Destination {
 }
 
 
-// Helpful on how to return multiple types:
-// https://www.reddit.com/r/rust/comments/dme4og/can_we_return_multiple_type_data_from_the_function/
-// https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=57223180ab43fff42e057d367468ac22
-enum OneOf<A, B, C> {
-    First(A),
-    Second(B),
-    Third(C),
+/// Shape of the circles, picked by name in the presets, e.g. `shape: Polygon(6)` or
+/// `shape: Star(points: 5, inner_ratio: 0.45)`. Every shape is scaled to `circle_radius`.
+#[derive(Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect_value]
+pub enum ShiftyShape {
+    Circle,
+    /// Flattened to `ratio` of its width
+    Ellipse(f32),
+    /// Regular polygon with that many sides (at least 3)
+    Polygon(usize),
+    /// Regular star with that many points (at least 2), the inner corners at `inner_ratio` of the
+    /// radius
+    Star {
+        points: usize,
+        inner_ratio: f32,
+    },
+    /// SVG path data, e.g. `M 0 0 L 10 0 L 5 8 Z` (see `crate::svg_import`). Falls back to a
+    /// circle when it doesn't parse.
+    Svg(String),
+    /// A different random shape per circle (see `crate::shapegen`)
+    Generated(ShapeKind),
 }
 
 
-enum ShiftyShapes {
-    RECT,
-    CIRCLE,
-    ELLIPSE,
+impl Default for ShiftyShape {
+    fn default() -> Self {
+        ShiftyShape::Circle
+    }
 }
 
 
-fn get_shape(
-    shape: ShiftyShapes,
-) -> OneOf<shapes::Circle, shapes::Ellipse, shapes::RegularPolygon> {
-    match shape {
-        ShiftyShapes::CIRCLE => {
-            return OneOf::First(shapes::Circle {
-                radius: SHIFTY_CIRCLE_RADIUS,
-                ..Default::default()
-            })
-        }
-        ShiftyShapes::ELLIPSE => {
-            return OneOf::Second(shapes::Ellipse {
-                radii: Vec2::new(SHIFTY_CIRCLE_RADIUS, SHIFTY_CIRCLE_RADIUS / 2.0),
-                ..Default::default()
-            })
-        }
-        ShiftyShapes::RECT => {
-            return OneOf::Third(shapes::RegularPolygon {
-                sides: 4,
-                feature: shapes::RegularPolygonFeature::Radius(SHIFTY_CIRCLE_RADIUS),
-                ..shapes::RegularPolygon::default()
-            });
+impl ShiftyShape {
+    /// The shape as a `Path` of radius `radius`, with `rng` for generated shapes
+    fn path(&self, radius: f32, rng: &mut SketchRng) -> Path {
+        match self {
+            ShiftyShape::Circle => {
+                return ShapePath::build_as(&shapes::Circle {
+                    radius: radius,
+                    ..Default::default()
+                });
+            }
+            ShiftyShape::Ellipse(ratio) => {
+                return ShapePath::build_as(&shapes::Ellipse {
+                    radii: Vec2::new(radius, radius * ratio),
+                    ..Default::default()
+                });
+            }
+            ShiftyShape::Polygon(sides) => {
+                return ShapePath::build_as(&shapes::RegularPolygon {
+                    sides: (*sides).max(3),
+                    feature: shapes::RegularPolygonFeature::Radius(radius),
+                    ..shapes::RegularPolygon::default()
+                });
+            }
+            ShiftyShape::Star {
+                points,
+                inner_ratio,
+            } => {
+                return polygon_builder(&star_points(*points, radius, radius * inner_ratio))
+                    .build();
+            }
+            ShiftyShape::Svg(data) => match SvgPath::parse(data) {
+                Ok(svg_path) => return svg_path.builder(Vec2::splat(radius)).build(),
+                Err(err) => {
+                    warn!("Invalid shifty shape {:?}: {}", data, err);
+                    return ShiftyShape::Circle.path(radius, rng);
+                }
+            },
+            ShiftyShape::Generated(kind) => {
+                let generator = kind.generator(SHIFTY_SHAPE_SEGMENTS);
+                return generator.builder(rng, radius, radius).build();
+            }
         }
     }
 }
 
 
-fn setup_shifty_circle(commands: Commands) {
-    let some_shape = get_shape(ShiftyShapes::CIRCLE);
-    /*
-     * This way of destructuring took some time to figure out and is still is a little hard
-     * to understand as I'm new to Rust.
-     *
-     * What it means is: "If let destructures `some_shape` into Either::Left(myshape), where
-     * `myshape` would be the Circle shape we want, then run the `if` block. Need to do this
-     * because I'm using the "Either" pattern for returning multiple types described here:
-     *
-     * https://play.rust-lang.org/?version=stable&mode=debug&edition=2018&gist=57223180ab43fff42e057d367468ac22
-     * (Update: Changed "Either" to "OneOf")
-     *
-     * By using `if let` I can avoid the verbose and redundant match statement from the
-     * previous commit of this function.
-     *
-     * Based on:
-     * https://www.reddit.com/r/rust/comments/dme4og/can_we_return_multiple_type_data_from_the_function/
-     */
-    if let OneOf::First(myshape) = some_shape {
-        setup_generic(commands, myshape);
-    } else {
-        panic!("Got the wrong shape!");
-    }
+/// Corners of a regular star pointing up, alternating between `outer` and `inner` radii
+fn star_points(points: usize, outer: f32, inner: f32) -> Vec<Vec2> {
+    let corners = points.max(2) * 2;
+    return (0..corners)
+        .map(|i| {
+            let angle = FRAC_PI_2 + TAU * i as f32 / corners as f32;
+            let radius = if i % 2 == 0 { outer } else { inner };
+            Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect();
 }
 
 
-fn setup_shifty_ufo(commands: Commands) {
-    let some_shape = get_shape(ShiftyShapes::ELLIPSE);
-    if let OneOf::Second(myshape) = some_shape {
-        setup_generic(commands, myshape);
-    } else {
-        panic!("Got the wrong shape!");
+fn setup_shifty_circles(
+    mut commands: Commands,
+    webcfg: Res<WebExtrasCfg>,
+    params: Res<ShiftyParams>,
+    mut rng: ResMut<SketchRng>,
+) {
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+
+    let rng = rng.stream("shifty circles");
+    let count = params.circle_count as usize;
+    for position in place_shifty_circles(&webcfg, &params, rng, count, Vec::new()) {
+        let shape_path = params.shape.path(params.circle_radius, rng);
+        spawn_shifty_circle(&mut commands, &shape_path, &params, position);
     }
 }
 
-fn setup_shifty_rect(commands: Commands) {
-    // For an example of triggering the panic below, you can ask for an ELLIPSE from `get_shape`
-    // instead of the expected RECT
-    // let some_shape = get_shape(ShiftyShapes::ELLIPSE);
-    let some_shape = get_shape(ShiftyShapes::RECT);
-    if let OneOf::Third(myshape) = some_shape {
-        setup_generic(commands, myshape);
-    } else {
-        panic!("Got the wrong shape!");
+
+/// Starting positions for `count` new circles, spread over the canvas and apart from the `taken`
+/// circles (see `crate::placement`). Circles that don't fit start in the middle.
+fn place_shifty_circles(
+    webcfg: &WebExtrasCfg,
+    params: &ShiftyParams,
+    rng: &mut SketchRng,
+    count: usize,
+    taken: Vec<(Vec2, f32)>,
+) -> Vec<Vec2> {
+    let mut positions = Placer::from_webcfg(webcfg)
+        .avoid_circles(taken)
+        .place(rng, &vec![params.circle_radius; count]);
+    positions.resize(count, Vec2::ZERO);
+    return positions;
+}
+
+
+fn shifty_circle_draw_mode(params: &ShiftyParams) -> DrawMode {
+    let colors = params.colors();
+    DrawMode::Outlined {
+        fill_mode: FillMode::color(colors.circle_fill),
+        outline_mode: StrokeMode::new(colors.circle_stroke, params.circle_stroke),
     }
 }
 
 
-fn setup_generic(mut commands: Commands, myshape: impl Geometry) {
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+fn spawn_shifty_circle(
+    commands: &mut Commands,
+    myshape: &impl Geometry,
+    params: &ShiftyParams,
+    position: Vec2,
+) {
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            myshape,
+            shifty_circle_draw_mode(params),
+            // Transform::default(),
+            Transform::from_translation(position.extend(1.0)),
+        ))
+        .insert(ShiftyCircle)
+        .insert(Destination {
+            x: position.x,
+            y: position.y,
+            speed: params.circle_min_speed,
+        });
+}
 
-    // `_` means to discard the iterator element, since it's not being used:
-    // https://stackoverflow.com/questions/29932503/what-is-the-idiomatic-way-to-write-a-for-loop-without-using-the-iterator-value
-    //
-    // `..=` is "inclusive ranges" notation:
-    // https://blog.rust-lang.org/2018/05/10/Rust-1.26.html#inclusive-ranges-with-
-    for _ in 1..=SHIFTY_CIRCLE_COUNT {
-        commands
-            .spawn_bundle(GeometryBuilder::build_as(
-                &myshape,
-                DrawMode::Outlined {
-                    fill_mode: FillMode::color(SHIFTY_CIRCLE_FILL_COLOR),
-                    outline_mode: StrokeMode::new(SHIFTY_CIRCLE_STROKE_COLOR, SHIFTY_CIRCLE_STROKE),
-                },
-                // Transform::default(),
-                Transform::from_translation(Vec3::new(0.0, 0.0, 1.0)),
-            ))
-            .insert(ShiftyCircle)
-            .insert(Destination {
-                x: 0.0,
-                y: 0.0,
-                speed: SHIFTY_CIRCLE_MIN_SPEED,
-            });
+
+/// Apply edited params to the circles: shape, colors and count
+fn restyle_shifty_circles(
+    mut commands: Commands,
+    webcfg: Res<WebExtrasCfg>,
+    params: Res<ShiftyParams>,
+    mut rng: ResMut<SketchRng>,
+    mut q: Query<(Entity, &mut Path, &mut DrawMode, &Transform), With<ShiftyCircle>>,
+) {
+    let rng = rng.stream("shifty circles");
+    let mut count = 0;
+    let mut taken = Vec::new();
+    for (entity, mut path, mut draw_mode, transform) in q.iter_mut() {
+        count += 1;
+        if count > params.circle_count {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        *path = params.shape.path(params.circle_radius, rng);
+        *draw_mode = shifty_circle_draw_mode(&params);
+        taken.push((transform.translation.truncate(), params.circle_radius));
+    }
+    let missing = params.circle_count.saturating_sub(count) as usize;
+    for position in place_shifty_circles(&webcfg, &params, rng, missing, taken) {
+        let shape_path = params.shape.path(params.circle_radius, rng);
+        spawn_shifty_circle(&mut commands, &shape_path, &params, position);
     }
 }
 
@@ -197,6 +368,9 @@ fn draw_skyline_layer(
     commands: &mut Commands,
     mesh_assets: &mut ResMut<Assets<Mesh>>,
     material_assets: &mut ResMut<Assets<BuildingLights>>,
+    rng: &mut SketchRng,
+    params: &ShiftyParams,
+    district: &NoiseField,
     available_space: f32,
     buildings_start_x: f32,
     buildings_start_y: f32,
@@ -206,25 +380,34 @@ fn draw_skyline_layer(
     building_alpha: f32,
     z_index: f32,
 ) {
+    let lights = PaletteUniformData::from(&params.colors().lights);
+    let noise = NoiseUniformData::from(district);
     let mut remaining_space = available_space;
     let mut building_pos_x = buildings_start_x;
     let mut building_pos_y;
-    let mut rng = thread_rng();
 
     while remaining_space > 0.0 {
         // debug!("{:?}, {:?}", available_space, remaining_space);
 
-        let building_width = if remaining_space > BUILDING_MAX_WIDTH {
-            rng.gen_range(BUILDING_MIN_WIDTH..BUILDING_MAX_WIDTH)
+        let building_width = if remaining_space > params.building_max_width {
+            // At least a pixel wide, so edited params can't stall the loop
+            rng.range(params.building_min_width, params.building_max_width)
+                .max(1.0)
         } else {
             // It should correctly be `remaining_space`, but iirc there used to be
-            // some kind of error before. Re-instate BUILDING_MAX_WIDTH if error recurrs.
-            // BUILDING_MAX_WIDTH
+            // some kind of error before. Re-instate building_max_width if error recurrs.
+            // params.building_max_width
             remaining_space
         };
-        let building_height = rng.gen_range(building_min_height..building_max_height);
-
         building_pos_x = building_pos_x + building_width / 2.0;
+        // Taller where the shader lights more windows, sampled where it samples it: up to half
+        // the height range in quiet districts, all of it in the busiest
+        let base = Vec2::new(building_pos_x, buildings_start_y);
+        let building_height = rng.range(
+            building_min_height,
+            skyline_tallest(district, base, building_min_height, building_max_height),
+        );
+
         building_pos_y = buildings_start_y + building_height / 2.0;
 
         commands
@@ -237,10 +420,14 @@ fn draw_skyline_layer(
                 },
                 material: material_assets.add(BuildingLights {
                     uniform: BuildingLightsUniform {
-                        background_color: color_to_shader_vec3(building_color),
-                        alpha: building_alpha,
+                        background_color: color_to_shader_vec4(with_alpha(
+                            building_color,
+                            building_alpha,
+                        )),
                         // rand_modifier: building_width,
                         rand_modifier: rng.gen_range(1.0..100000.0),
+                        lights: lights.clone(),
+                        noise: noise.clone(),
                         ..default()
                     },
                 }),
@@ -255,32 +442,53 @@ fn draw_skyline_layer(
     }
 }
 
+
+/// Tallest a building standing on `base` can be, see `busyness`
+fn skyline_tallest(district: &NoiseField, base: Vec2, min_height: f32, max_height: f32) -> f32 {
+    let busy = busyness(district, base);
+    return min_height + (max_height - min_height).max(0.0) * (0.5 + 0.5 * busy);
+}
+
+
 fn draw_skyline(
     mut commands: Commands,
     mut mesh_assets: ResMut<Assets<Mesh>>,
     mut material_assets: ResMut<Assets<BuildingLights>>,
+    mut rng: ResMut<SketchRng>,
     webcfg: ResMut<WebExtrasCfg>,
+    params: Res<ShiftyParams>,
     mut q: Query<Entity, With<Building>>,
 ) {
     for entity in q.iter_mut() {
         commands.entity(entity).despawn();
     }
+    let rng = rng.stream("skyline");
+    // Shared by both layers, so busy districts are busy in front and behind
+    let district = NoiseField {
+        frequency: 1.0 / SKYLINE_DISTRICT_WIDTH,
+        octaves: 2,
+        ..NoiseField::from_rng(rng)
+    };
 
     let buildings_start_x = -webcfg.max_x;
     let buildings_start_y = -webcfg.max_y;
-    let building_max_height = webcfg.height / BUILDING_MAX_HEIGHT_RATIO;
-    let building_min_height = webcfg.height / BUILDING_MIN_HEIGHT_RATIO;
+    let building_max_height = webcfg.height / params.building_max_height_ratio;
+    let building_min_height = webcfg.height / params.building_min_height_ratio;
+    let colors = params.colors();
 
     draw_skyline_layer(
         &mut commands,
         &mut mesh_assets,
         &mut material_assets,
+        rng,
+        &params,
+        &district,
         webcfg.width,
         buildings_start_x,
         buildings_start_y,
         building_min_height,
         building_max_height,
-        BUILDING_COLOR,
+        colors.building,
         0.2,
         0.0,
     );
@@ -290,12 +498,15 @@ fn draw_skyline(
         &mut commands,
         &mut mesh_assets,
         &mut material_assets,
+        rng,
+        &params,
+        &district,
         webcfg.width,
         buildings_start_x,
         buildings_start_y,
         building_min_height,
         building_max_height - building_max_height / 4.0,
-        BUILDING_FORE_COLOR,
+        colors.building_fore,
         1.0,
         2.0,
     );
@@ -323,24 +534,51 @@ fn translate_circle(mut q: Query<(&mut Transform, &Destination)>) {
 
 fn change_circle_destination(
     app_globals: Res<AppGlobals>,
+    params: Res<ShiftyParams>,
+    pointer: Option<Res<Pointer>>,
+    mut rng: ResMut<SketchRng>,
     mut q: Query<&mut Destination, With<ShiftyCircle>>,
 ) {
-    let mut rng = thread_rng();
+    let rng = rng.stream("shifty destinations");
     for mut dest in q.iter_mut() {
-        dest.x = rng.gen_range(app_globals.dest_low_x..app_globals.dest_high_x);
-        dest.y = rng.gen_range(app_globals.dest_low_y..app_globals.dest_high_y);
-        dest.speed = rng.gen_range(SHIFTY_CIRCLE_MIN_SPEED..SHIFTY_CIRCLE_MAX_SPEED);
+        dest.x = rng.range(app_globals.dest_low_x, app_globals.dest_high_x);
+        dest.y = rng.range(app_globals.dest_low_y, app_globals.dest_high_y);
+        dest.speed = rng.range(params.circle_min_speed, params.circle_max_speed);
+        if let Some(pointer) = pointer.as_ref().filter(|pointer| pointer.pressed) {
+            pull_destination(
+                &mut dest,
+                pointer.position,
+                params.pointer_pull,
+                &app_globals,
+            );
+        }
     }
 }
 
 
+/// Move `dest` by `pull` of the way to `target`, staying inside the destination bounds
+fn pull_destination(dest: &mut Destination, target: Vec2, pull: f32, bounds: &AppGlobals) {
+    dest.x = (dest.x + (target.x - dest.x) * pull).clamp(bounds.dest_low_x, bounds.dest_high_x);
+    dest.y = (dest.y + (target.y - dest.y) * pull).clamp(bounds.dest_low_y, bounds.dest_high_y);
+}
+
+
 // Based on https://github.com/Nilirad/bevy_prototype_lyon/blob/master/examples/dynamic_shape.rs
-fn do_pulsating_effect(time: Res<Time>, mut query: Query<&mut DrawMode, With<ShiftyCircle>>) {
+fn do_pulsating_effect(
+    clock: Res<SketchClock>,
+    params: Res<ShiftyParams>,
+    audio: Option<Res<AudioLevels>>,
+    mut query: Query<&mut DrawMode, With<ShiftyCircle>>,
+) {
     // let hue = (time.seconds_since_startup() * 50.0) % 360.0;
     // let outline_width = 2.0 + time.seconds_since_startup().sin().abs() * 10.0;
-    let secs_since = time.seconds_since_startup();
+    let secs_since = clock.seconds_since_startup();
     let secs_theta = secs_since % 360.0;
-    let pulse_wave = PULSE_AMPLITUDE * (secs_theta * PULSE_FREQ).sin().abs() * PULSE_SCALE;
+    let mut pulse_wave =
+        params.pulse_amplitude * (secs_theta * params.pulse_freq).sin().abs() * params.pulse_scale;
+    if let Some(audio) = audio {
+        pulse_wave += audio.bass() as f64 * params.pulse_bass;
+    }
     // debug!(
     //     "since: {:?}, theta: {:?}, pulse: {}",
     //     secs_since, secs_theta, pulse_wave
@@ -353,9 +591,9 @@ fn do_pulsating_effect(time: Res<Time>, mut query: Query<&mut DrawMode, With<Shi
             ref mut outline_mode,
         } = *draw_mode
         {
-            if pulse_wave > PULSE_MAX_ALPHA as f64 {
-                fill_mode.color.set_a(PULSE_MAX_ALPHA);
-                outline_mode.color.set_a(1.0 - PULSE_MAX_ALPHA * 3.5);
+            if pulse_wave > params.pulse_max_alpha as f64 {
+                fill_mode.color.set_a(params.pulse_max_alpha);
+                outline_mode.color.set_a(1.0 - params.pulse_max_alpha * 3.5);
             } else {
                 fill_mode.color.set_a(pulse_wave as f32);
                 outline_mode.color.set_a(1.0 - pulse_wave as f32 * 3.5);
@@ -365,75 +603,285 @@ fn do_pulsating_effect(time: Res<Time>, mut query: Query<&mut DrawMode, With<Shi
 }
 
 
-#[cfg(target_arch = "wasm32")]
-fn handle_post_browser_resize(
-    commands: Commands,
-    webcfg: ResMut<WebExtrasCfg>,
-    mesh_assets: ResMut<Assets<Mesh>>,
-    material_assets: ResMut<Assets<BuildingLights>>,
-    mut resize_event_reader: EventReader<BrowserResized>,
+/// Keep destinations inside the canvas
+fn update_destination_bounds(
+    webcfg: Res<WebExtrasCfg>,
+    params: Res<ShiftyParams>,
     mut app_globals: ResMut<AppGlobals>,
-    buildings_query: Query<Entity, With<Building>>,
 ) {
-    if resize_event_reader.iter().next().is_some() {
-        app_globals.dest_low_x = -webcfg.max_x + SHIFTY_CIRCLE_RADIUS;
-        app_globals.dest_high_x = webcfg.max_x - SHIFTY_CIRCLE_RADIUS;
-        app_globals.dest_low_y = -webcfg.max_y + SHIFTY_CIRCLE_RADIUS;
-        app_globals.dest_high_y = webcfg.max_y - SHIFTY_CIRCLE_RADIUS;
-        draw_skyline(
-            commands,
-            mesh_assets,
-            material_assets,
-            webcfg,
-            buildings_query,
-        );
-    }
+    app_globals.dest_low_x = -webcfg.max_x + params.circle_radius;
+    app_globals.dest_high_x = webcfg.max_x - params.circle_radius;
+    app_globals.dest_low_y = -webcfg.max_y + params.circle_radius;
+    app_globals.dest_high_y = webcfg.max_y - params.circle_radius;
 }
 
 
-pub fn app(variation: &str) {
-    let webcfg = WebExtrasCfg {
-        title: format!("shifty{}", String::from(variation)),
-        match_element: Some(String::from("content")),
-        match_clear_color: true,
-        ..Default::default()
-    };
-    // Need to copy a couple of values here b/c webcfg will be lost to `web_app`
-    let webcfg_max_x = webcfg.max_x;
-    let webcfg_max_y = webcfg.max_y;
-    let mut app = sketch(webcfg);
-    app.insert_resource(ClearColor(CLEAR_COLOR))
-        .insert_resource(AppGlobals {
-            dest_low_x: -webcfg_max_x,
-            dest_high_x: webcfg_max_x,
-            dest_low_y: -webcfg_max_y,
-            dest_high_y: webcfg_max_y,
-        })
-        .add_plugin(ShapePlugin)
-        .add_plugin(ShaderMaterialPlugin::<BuildingLights>::default());
+/// The shifty sketch, starting with the preset `preset` (see `ShiftyParams`)
+pub struct ShiftySketch {
+    name: &'static str,
+    title: &'static str,
+    description: &'static str,
+    preset: &'static str,
+}
 
 
-    match variation {
-        "ufo" => app.add_startup_system(setup_shifty_ufo),
-        "rect" => app.add_startup_system(setup_shifty_rect),
-        _ => app.add_startup_system(setup_shifty_circle),
-    };
+/// The shifty sketch on the default params, with circles
+pub const SHIFTY: ShiftySketch = ShiftySketch {
+    name: "shiftyc",
+    title: "shiftycircle",
+    description: "Circles drifting over a skyline of lit buildings",
+    preset: DEFAULT_PRESET,
+};
+
+
+/// A variation of the shifty sketch per file in `assets/presets/shifty/`, named `shifty<preset>`
+/// and described by the `//` comment at the top of the file (listed by `build.rs`)
+pub static SHIFTY_VARIATIONS: &[ShiftySketch] =
+    include!(concat!(env!("OUT_DIR"), "/shifty_variations.rs"));
+
+
+impl Sketch for ShiftySketch {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn title(&self) -> &'static str {
+        self.title
+    }
 
-    // If wasm32, the skyline will be drawn in handle_post_browser_resize
-    #[cfg(not(target_arch = "wasm32"))]
-    app.add_startup_system(draw_skyline);
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn tags(&self) -> &'static [&'static str] {
+        &["shifty", "lyon", "shader"]
+    }
 
-    #[cfg(target_arch = "wasm32")]
-    app.add_system(handle_post_browser_resize);
+    fn params(&self) -> Option<SketchParams> {
+        Some(SketchParams::of::<ShiftyParams>("ShiftyParams"))
+    }
 
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder {
+        let webcfg = sketch.webcfg();
+        let app_globals = AppGlobals {
+            dest_low_x: -webcfg.max_x,
+            dest_high_x: webcfg.max_x,
+            dest_low_y: -webcfg.max_y,
+            dest_high_y: webcfg.max_y,
+        };
+        sketch
+            .clear_color_from::<ShiftyParams>(|params| params.colors().clear)
+            .resource(app_globals)
+            .lyon()
+            .material::<BuildingLights>()
+            .audio_reactive()
+            .params::<ShiftyParams>()
+            .presets::<ShiftyParams>("shifty")
+            .preset::<ShiftyParams>(self.preset)
+            .startup_system(setup_shifty_circles)
+            .resize_system(update_destination_bounds)
+            .resize_system(draw_skyline)
+            .on_params_change::<ShiftyParams, _>(update_destination_bounds)
+            .on_params_change::<ShiftyParams, _>(draw_skyline)
+            .on_params_change::<ShiftyParams, _>(restyle_shifty_circles)
+            .fixed_system(translate_circle, SHIFTY_CIRCLE_STEP)
+            .fixed_system(change_circle_destination, SHIFTY_CHANGE_STEP)
+            .fixed_system(do_pulsating_effect, PULSATING_STEP)
+    }
+}
 
-    // Note setting with_run_criteria on a single system
-    // (Found it here: https://bevy-cheatbook.github.io/programming/run-criteria.html#run-criteria-labels)
-    app.add_system(translate_circle.with_run_criteria(FixedTimestep::step(SHIFTY_CIRCLE_STEP)))
-        .add_system(
-            change_circle_destination.with_run_criteria(FixedTimestep::step(SHIFTY_CHANGE_STEP)),
-        )
-        .add_system(do_pulsating_effect.with_run_criteria(FixedTimestep::step(PULSATING_STEP)));
 
-    app.run();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessSketch;
+
+    const SEED: u64 = 3;
+
+    #[test]
+    fn destinations_stay_inside_bounds() {
+        let mut sketch = HeadlessSketch::new(SEED)
+            .canvas(800.0, 600.0)
+            .params::<ShiftyParams>()
+            .resource(AppGlobals::default())
+            .startup_system(setup_shifty_circles)
+            .startup_system(update_destination_bounds)
+            .fixed_system(change_circle_destination, SHIFTY_CHANGE_STEP);
+        for _ in 0..50 {
+            sketch.run_steps(SHIFTY_CHANGE_STEP, 1);
+            let bounds = sketch.read_resource::<AppGlobals>();
+            let (low_x, high_x) = (bounds.dest_low_x, bounds.dest_high_x);
+            let (low_y, high_y) = (bounds.dest_low_y, bounds.dest_high_y);
+            // Bounds keep a whole circle inside the canvas
+            assert_eq!((low_x, high_x), (-360.0, 360.0));
+            assert_eq!((low_y, high_y), (-260.0, 260.0));
+
+            let destinations = sketch.components::<Destination>();
+            assert_eq!(
+                destinations.len(),
+                ShiftyParams::default().circle_count as usize
+            );
+            for dest in destinations {
+                assert!((low_x..=high_x).contains(&dest.x), "x {}", dest.x);
+                assert!((low_y..=high_y).contains(&dest.y), "y {}", dest.y);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_sketch() {
+        // Sorted, as entities from different systems may be spawned in any order
+        let run = |seed| {
+            let mut sketch = HeadlessSketch::new(seed)
+                .canvas(800.0, 600.0)
+                .asset::<Mesh>()
+                .asset::<BuildingLights>()
+                .params::<ShiftyParams>()
+                .resource(AppGlobals::default())
+                .startup_system(setup_shifty_circles)
+                .startup_system(draw_skyline)
+                .startup_system(update_destination_bounds)
+                .fixed_system(translate_circle, SHIFTY_CIRCLE_STEP)
+                .fixed_system(change_circle_destination, SHIFTY_CHANGE_STEP);
+            sketch.run_steps(SHIFTY_CHANGE_STEP, 5);
+            let mut transforms: Vec<[u32; 6]> = sketch
+                .components::<Transform>()
+                .into_iter()
+                .map(|t| {
+                    let (translation, scale) = (t.translation, t.scale);
+                    [
+                        translation.x,
+                        translation.y,
+                        translation.z,
+                        scale.x,
+                        scale.y,
+                        scale.z,
+                    ]
+                    .map(f32::to_bits)
+                })
+                .collect();
+            transforms.sort_unstable();
+            let mut destinations: Vec<[u32; 3]> = sketch
+                .components::<Destination>()
+                .into_iter()
+                .map(|dest| [dest.x, dest.y, dest.speed].map(f32::to_bits))
+                .collect();
+            destinations.sort_unstable();
+            (transforms, destinations)
+        };
+        let (transforms, destinations) = run(SEED);
+        assert!(transforms.len() > ShiftyParams::default().circle_count as usize + 1);
+        assert_eq!(run(SEED), (transforms.clone(), destinations));
+        assert_ne!(run(SEED + 1).0, transforms);
+    }
+
+    #[test]
+    fn skyline_follows_the_lights_noise() {
+        let mut sketch = HeadlessSketch::new(SEED)
+            .canvas(800.0, 600.0)
+            .asset::<Mesh>()
+            .asset::<BuildingLights>()
+            .params::<ShiftyParams>()
+            .startup_system(draw_skyline);
+        sketch.update(0.0);
+        let params = ShiftyParams::default();
+        let min_height = 600.0 / params.building_min_height_ratio;
+        let max_height = 600.0 / params.building_max_height_ratio;
+
+        let world = sketch.world();
+        let mut query =
+            world.query_filtered::<(&Transform, &Handle<BuildingLights>), With<Building>>();
+        let materials = world.resource::<Assets<BuildingLights>>();
+        let mut fields = Vec::new();
+        for (transform, handle) in query.iter(world) {
+            // The field the shader gets
+            let noise = &materials.get(handle).unwrap().uniform.noise;
+            let district = NoiseField {
+                seed: noise.seed,
+                frequency: noise.frequency,
+                octaves: noise.octaves,
+            };
+            let base = Vec2::new(transform.translation.x, -300.0);
+            let height = transform.scale.y;
+            let tallest = skyline_tallest(&district, base, min_height, max_height);
+            assert!(height <= tallest + 1e-3, "{} > {}", height, tallest);
+            // Both layers stand on the ground
+            assert!((transform.translation.y - height / 2.0 - base.y).abs() < 1e-3);
+            fields.push(district);
+        }
+        assert!(fields.len() > 2);
+        assert!(fields[0].frequency > 0.0);
+        assert!(fields.iter().all(|field| *field == fields[0]));
+    }
+
+    #[test]
+    fn pressed_pointer_pulls_destinations() {
+        let pointer = Pointer {
+            position: Vec2::new(400.0, 300.0),
+            active: true,
+            pressed: true,
+            ..Default::default()
+        };
+        let mut sketch = HeadlessSketch::new(SEED)
+            .canvas(800.0, 600.0)
+            .params::<ShiftyParams>()
+            .resource(AppGlobals::default())
+            .resource(pointer)
+            .startup_system(setup_shifty_circles)
+            .startup_system(update_destination_bounds)
+            .fixed_system(change_circle_destination, SHIFTY_CHANGE_STEP);
+        let pull = ShiftyParams::default().pointer_pull;
+        for _ in 0..20 {
+            sketch.run_steps(SHIFTY_CHANGE_STEP, 1);
+            // At least `pull` of the way from the far edge to the top right corner (clamped)
+            for dest in sketch.components::<Destination>() {
+                assert!(dest.x >= -360.0 + 720.0 * pull - 1e-3, "x {}", dest.x);
+                assert!(dest.y >= -260.0 + 520.0 * pull - 1e-3, "y {}", dest.y);
+                assert!(dest.x <= 360.0 && dest.y <= 260.0);
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_fit_the_radius() {
+        use crate::geometry::{bounds, flatten};
+        use crate::presets::parse_preset;
+
+        let radius = 40.0;
+        let mut rng = SketchRng::new(SEED);
+        let hex =
+            parse_preset::<ShiftyParams>(include_str!("../assets/presets/shifty/hex.preset.ron"))
+                .unwrap();
+        assert_eq!(hex.shape, ShiftyShape::Polygon(6));
+        let shapes = [
+            ShiftyShape::Circle,
+            ShiftyShape::Ellipse(0.5),
+            ShiftyShape::Polygon(6),
+            ShiftyShape::Star {
+                points: 5,
+                inner_ratio: 0.45,
+            },
+            ShiftyShape::Svg("M 0 0 L 10 0 L 5 8 Z".to_string()),
+            // Falls back to a circle
+            ShiftyShape::Svg("M 0 0 X".to_string()),
+            ShiftyShape::Generated(ShapeKind::Blob),
+        ];
+        for shape in shapes {
+            let polygons = flatten(&shape.path(radius, &mut rng), 0.1);
+            assert_eq!(polygons.len(), 1, "{:?}", shape);
+            let size = bounds(&polygons[0]).unwrap().size();
+            assert!(size.max_element() > radius, "{:?}: {:?}", shape, size);
+            assert!(
+                size.max_element() <= radius * 2.0 + 1e-3,
+                "{:?}: {:?}",
+                shape,
+                size
+            );
+        }
+
+        let star = star_points(5, radius, radius * 0.45);
+        assert_eq!(star.len(), 10);
+        assert!((star[0] - Vec2::new(0.0, radius)).length() < 1e-3);
+        assert!((star[1].length() - radius * 0.45).abs() < 1e-3);
+    }
 }
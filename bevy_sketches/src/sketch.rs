@@ -0,0 +1,221 @@
+/*
+ * sketch
+ *
+ * Every sketch implements `Sketch` (what it is called, what it is about, how it is built) and is
+ * listed by `sketches()`, so tooling and gallery code can enumerate the sketches from Rust. An
+ * example is then just `run(&SomeSketch)`.
+ *
+ * To add a sketch: implement `Sketch` next to its systems, add it to `SKETCHES`, add the
+ * one-line example named after `Sketch::name` and its tags to the
+ * `[package.metadata.sketches.<name>]` table in Cargo.toml (read by the `build-sketches` tool).
+ * The tests below check that these agree.
+ *
+ * The shifty variations are data only: each file in `assets/presets/shifty/` is one (see
+ * `SHIFTY_VARIATIONS`), found in the gallery and by `find`, with no example of its own. Run one
+ * with the `shiftyc` example and its preset, e.g. `shiftyc.html?preset=star`; build-sketches
+ * gives each its own page too (`shiftystar.html`), from the `variations` key in Cargo.toml.
+ */
+use crate::base::SketchBuilder;
+use crate::cellular::quadratic::CellularSketch;
+use crate::path_changer::PathChangerSketch;
+use crate::shader_sketches::{
+    building_lights::BuildingLightsSketch, erratic_wave::ErraticWaveSketch,
+    material_poc::MaterialPocSketch, scaling_net::ScalingNetSketch,
+};
+use crate::shifty_circle::{SHIFTY, SHIFTY_VARIATIONS};
+use std::any::TypeId;
+
+
+/// The sketches with an example each, in gallery order
+pub static SKETCHES: &[&dyn Sketch] = &[
+    &SHIFTY,
+    &CellularSketch,
+    &PathChangerSketch,
+    &BuildingLightsSketch,
+    &ErraticWaveSketch,
+    &ScalingNetSketch,
+    &MaterialPocSketch,
+];
+
+
+/// All the sketches in gallery order: `SKETCHES`, with the shifty variations after `shiftyc`
+pub fn sketches() -> Vec<&'static dyn Sketch> {
+    let variations = SHIFTY_VARIATIONS.iter().map(|sketch| sketch as &dyn Sketch);
+    return SKETCHES[..1]
+        .iter()
+        .copied()
+        .chain(variations)
+        .chain(SKETCHES[1..].iter().copied())
+        .collect();
+}
+
+
+pub trait Sketch: Sync {
+    /// Name of the example and of the built `.wasm` / `.html` files, e.g. `shiftyc`, or for a
+    /// shifty variation the name in the gallery
+    fn name(&self) -> &'static str;
+
+    /// Window (or page) title
+    fn title(&self) -> &'static str;
+
+    /// A sentence about the sketch, for listings
+    fn description(&self) -> &'static str;
+
+    /// Tags to filter sketches by, e.g. `lyon` or `shader`
+    fn tags(&self) -> &'static [&'static str];
+
+    /// The sketch's parameter resource (see `crate::params`), if it has one
+    fn params(&self) -> Option<SketchParams> {
+        None
+    }
+
+    /// Add the sketch's resources, materials and systems
+    fn build(&self, sketch: SketchBuilder) -> SketchBuilder;
+}
+
+
+/// Identifies a sketch's parameter resource for tooling: look the type up in the app's
+/// `TypeRegistry` (where `SketchBuilder::params` registers it) with `type_id`, or refer to it by
+/// `name`, which is written out rather than taken from `std::any::type_name` so that it stays the
+/// same across compilers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SketchParams {
+    pub name: &'static str,
+    pub type_id: TypeId,
+}
+
+
+impl SketchParams {
+    pub fn of<T: 'static>(name: &'static str) -> Self {
+        Self {
+            name: name,
+            type_id: TypeId::of::<T>(),
+        }
+    }
+}
+
+
+pub fn find(name: &str) -> Option<&'static dyn Sketch> {
+    sketches().into_iter().find(|sketch| sketch.name() == name)
+}
+
+
+pub fn builder(sketch: &dyn Sketch) -> SketchBuilder {
+    sketch.build(SketchBuilder::new(sketch.title()))
+}
+
+
+pub fn run(sketch: &dyn Sketch) {
+    builder(sketch).run();
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    /// Tags from a `[package.metadata.sketches.<name>]` table, assuming the single-line
+    /// `tags = ["a", "b"]` format used in Cargo.toml
+    fn cargo_tags(manifest: &str, name: &str) -> Option<Vec<String>> {
+        let table = format!("[package.metadata.sketches.{}]", name);
+        let mut lines = manifest
+            .lines()
+            .skip_while(|line| line.trim() != table)
+            .skip(1);
+        let tags = lines
+            .next()?
+            .trim()
+            .strip_prefix("tags = [")?
+            .strip_suffix(']')?;
+        return Some(
+            tags.split(',')
+                .map(|tag| tag.trim().trim_matches('"').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let names: HashSet<&str> = sketches().iter().map(|sketch| sketch.name()).collect();
+        assert_eq!(names.len(), sketches().len());
+        assert_eq!(
+            find("cellular").map(|sketch| sketch.title()),
+            Some("cellular")
+        );
+        assert_eq!(
+            find("shiftystar").map(|sketch| sketch.description()),
+            Some("Stars drifting over a skyline of lit buildings")
+        );
+        assert!(find("nope").is_none());
+    }
+
+    #[test]
+    fn params_names_match_types() {
+        let params: Vec<SketchParams> = sketches().iter().filter_map(|s| s.params()).collect();
+        assert!(!params.is_empty());
+        // Sketches sharing a parameter type agree on its name, other types have other names
+        for a in params.iter() {
+            for b in params.iter() {
+                assert_eq!(
+                    a.type_id == b.type_id,
+                    a.name == b.name,
+                    "{} {}",
+                    a.name,
+                    b.name
+                );
+            }
+        }
+        assert_eq!(
+            find("cellular").and_then(|sketch| sketch.params()),
+            Some(SketchParams::of::<crate::cellular::quadratic::CellParams>(
+                "CellParams"
+            ))
+        );
+    }
+
+    #[test]
+    fn variations_follow_the_presets() {
+        let presets: Vec<&str> = crate::presets::bundled("shifty")
+            .map(|preset| preset.name)
+            .collect();
+        assert!(presets.contains(&"star") && presets.contains(&"hex"));
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        // build-sketches makes the pages of the variations from the same directory
+        let manifest = include_str!("../Cargo.toml");
+        let shifty_table: Vec<&str> = manifest
+            .lines()
+            .skip_while(|line| line.trim() != "[package.metadata.sketches.shiftyc]")
+            .take_while(|line| !line.trim().is_empty())
+            .collect();
+        assert!(shifty_table.contains(&"variations = \"shifty\""));
+        assert_eq!(SHIFTY_VARIATIONS.len(), presets.len());
+        for (variation, preset) in SHIFTY_VARIATIONS.iter().zip(presets) {
+            assert_eq!(variation.name(), format!("shifty{}", preset));
+            assert!(!examples.join(format!("{}.rs", variation.name())).exists());
+            assert_eq!(variation.tags(), SHIFTY.tags());
+        }
+    }
+
+    #[test]
+    fn matches_examples_and_cargo_metadata() {
+        let manifest = include_str!("../Cargo.toml");
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+        for sketch in SKETCHES {
+            let name = sketch.name();
+            assert!(
+                examples.join(format!("{}.rs", name)).exists(),
+                "no example for {}",
+                name
+            );
+            assert_eq!(
+                cargo_tags(manifest, name),
+                Some(sketch.tags().iter().map(|tag| tag.to_string()).collect()),
+                "tags of {} differ from Cargo.toml",
+                name
+            );
+        }
+    }
+}
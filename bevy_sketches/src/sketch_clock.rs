@@ -0,0 +1,236 @@
+/*
+ * sketch_clock
+ *
+ * A sketch-wide clock that can be paused, stepped one frame at a time and sped up or slowed down.
+ * Animation should follow it instead of Bevy's wall-clock `Time`: fixed-step systems added with
+ * `SketchBuilder::fixed_system` and the shader `time` uniform already do, so a paused sketch
+ * freezes both the lyon shapes and the shaders.
+ *
+ * Keys:
+ * - `PAUSE_KEY` pauses and resumes
+ * - `STEP_KEY` pauses and advances a single frame
+ * - `SLOWER_KEY` / `FASTER_KEY` halve / double the speed
+ * - `RESET_SPEED_KEY` goes back to normal speed
+ *
+ * Instructed by:
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/crates/bevy_core/src/time/fixed_timestep.rs
+ */
+use bevy::core::CoreSystem;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+
+
+pub const PAUSE_KEY: KeyCode = KeyCode::Space;
+pub const STEP_KEY: KeyCode = KeyCode::Period;
+pub const SLOWER_KEY: KeyCode = KeyCode::LBracket;
+pub const FASTER_KEY: KeyCode = KeyCode::RBracket;
+pub const RESET_SPEED_KEY: KeyCode = KeyCode::Key0;
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 16.0;
+
+
+pub struct SketchClockPlugin;
+
+
+impl Plugin for SketchClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SketchClock>()
+            .add_system_to_stage(CoreStage::First, tick_sketch_clock.after(CoreSystem::Time))
+            .add_system(control_sketch_clock);
+    }
+}
+
+
+/// Use it like `Time`, e.g. `clock.seconds_since_startup()`
+pub struct SketchClock {
+    paused: bool,
+    speed: f64,
+    step_requested: bool,
+    delta: f64,
+    elapsed: f64,
+}
+
+
+impl Default for SketchClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: 1.0,
+            step_requested: false,
+            delta: 0.0,
+            elapsed: 0.0,
+        }
+    }
+}
+
+
+impl SketchClock {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Pause (if not paused already) and advance a single frame on the next tick
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Speed multiplier, clamped to `MIN_SPEED..=MAX_SPEED`
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Sketch time passed in the last frame (0.0 while paused)
+    pub fn delta_seconds(&self) -> f64 {
+        self.delta
+    }
+
+    /// Sketch time passed since startup, not counting pauses
+    pub fn seconds_since_startup(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Advance by a frame that took `real_delta` seconds of wall-clock time
+    pub fn tick(&mut self, real_delta: f64) {
+        self.delta = if !self.paused || self.step_requested {
+            real_delta * self.speed
+        } else {
+            0.0
+        };
+        self.step_requested = false;
+        self.elapsed += self.delta;
+    }
+}
+
+
+fn tick_sketch_clock(time: Res<Time>, mut clock: ResMut<SketchClock>) {
+    clock.tick(time.delta_seconds_f64());
+}
+
+
+fn control_sketch_clock(keys: Res<Input<KeyCode>>, mut clock: ResMut<SketchClock>) {
+    if keys.just_pressed(PAUSE_KEY) {
+        let paused = clock.is_paused();
+        clock.set_paused(!paused);
+    }
+    if keys.just_pressed(STEP_KEY) {
+        clock.step();
+    }
+    if keys.just_pressed(SLOWER_KEY) {
+        let speed = clock.speed();
+        clock.set_speed(speed / 2.0);
+    }
+    if keys.just_pressed(FASTER_KEY) {
+        let speed = clock.speed();
+        clock.set_speed(speed * 2.0);
+    }
+    if keys.just_pressed(RESET_SPEED_KEY) {
+        clock.set_speed(1.0);
+    }
+}
+
+
+/// Run criteria state of `clock_step`
+#[derive(Default)]
+pub struct ClockStepState {
+    accumulator: f64,
+    looping: bool,
+}
+
+
+/// Like `FixedTimestep`, but following the sketch clock: yes for every `step` seconds of sketch
+/// time (catching up with several runs in one frame if needed), no while paused.
+pub fn clock_step(step: f64, clock: &SketchClock, state: &mut ClockStepState) -> ShouldRun {
+    // Only take in the frame's time once, not again when checking after a catch-up run
+    if !state.looping {
+        state.accumulator += clock.delta_seconds();
+    }
+    if state.accumulator >= step {
+        state.accumulator -= step;
+        state.looping = true;
+        return ShouldRun::YesAndCheckAgain;
+    }
+    state.looping = false;
+    ShouldRun::No
+}
+
+
+/// Add a system that runs every `step` seconds of sketch time (see `clock_step`)
+pub fn add_fixed_system<Params: 'static>(
+    app: &mut App,
+    system: impl IntoSystem<(), (), Params> + 'static,
+    step: f64,
+) {
+    // Note setting with_run_criteria on a single system
+    // (Found it here: https://bevy-cheatbook.github.io/programming/run-criteria.html#run-criteria-labels)
+    app.add_system(system.with_run_criteria(
+        move |clock: Res<SketchClock>, mut state: Local<ClockStepState>| {
+            clock_step(step, &clock, &mut state)
+        },
+    ));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_step_and_speed() {
+        let mut clock = SketchClock::default();
+        clock.tick(0.5);
+        assert_eq!(clock.seconds_since_startup(), 0.5);
+
+        clock.set_paused(true);
+        clock.tick(0.5);
+        assert_eq!(clock.delta_seconds(), 0.0);
+
+        clock.step();
+        clock.tick(0.25);
+        assert_eq!(clock.delta_seconds(), 0.25);
+        clock.tick(0.25);
+        assert_eq!(clock.delta_seconds(), 0.0);
+        assert!(clock.is_paused());
+
+        clock.set_paused(false);
+        clock.set_speed(2.0);
+        clock.tick(0.5);
+        assert_eq!(clock.seconds_since_startup(), 1.75);
+
+        clock.set_speed(1000.0);
+        assert_eq!(clock.speed(), MAX_SPEED);
+    }
+
+    #[test]
+    fn clock_step_follows_sketch_time() {
+        let mut clock = SketchClock::default();
+        let mut state = ClockStepState::default();
+        let mut runs_per_frame = |clock: &SketchClock| {
+            let mut runs = 0;
+            while clock_step(0.5, clock, &mut state) == ShouldRun::YesAndCheckAgain {
+                runs += 1;
+            }
+            runs
+        };
+
+        clock.tick(0.3);
+        assert_eq!(runs_per_frame(&clock), 0);
+        clock.tick(0.3);
+        assert_eq!(runs_per_frame(&clock), 1);
+        // Catches up after a long frame
+        clock.tick(1.0);
+        assert_eq!(runs_per_frame(&clock), 2);
+
+        clock.set_paused(true);
+        clock.tick(5.0);
+        assert_eq!(runs_per_frame(&clock), 0);
+    }
+}
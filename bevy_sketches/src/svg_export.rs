@@ -0,0 +1,373 @@
+/*
+ * svg_export
+ *
+ * Export the lyon shapes of a sketch as a standalone SVG, e.g. for printing at any resolution.
+ * Press `SVG_EXPORT_KEY` to export: natively the file is written to `SVG_EXPORT_DIR`, on the
+ * web it is downloaded by the browser.
+ *
+ * Each entity with a lyon `Path` and `DrawMode` becomes an SVG `<path>` with its geometry, fill
+ * and stroke colors (with alpha), stroke width and transform, in z-order. Entities are exported
+ * with their `GlobalTransform`, so child shapes (like the inner cells in `cellular`) come out in
+ * the right place.
+ *
+ * Instructed by:
+ * https://developer.mozilla.org/en-US/docs/Web/SVG/Tutorial/Paths
+ */
+use crate::rng::SketchRng;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_web_extras::prelude::*;
+use std::fmt::Write;
+use tess::path::{Path as TessPath, PathEvent};
+use tess::{FillRule, LineCap, LineJoin};
+
+
+pub const SVG_EXPORT_KEY: KeyCode = KeyCode::F8;
+#[cfg(not(target_arch = "wasm32"))]
+pub const SVG_EXPORT_DIR: &str = "svg_exports";
+
+
+pub struct SvgExportPlugin;
+
+
+impl Plugin for SvgExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(export_svg_on_key);
+    }
+}
+
+
+/// Everything needed to draw one shape
+pub struct SvgShape<'a> {
+    pub path: &'a TessPath,
+    pub draw_mode: &'a DrawMode,
+    pub transform: Mat4,
+}
+
+
+/// Serialize shapes to a standalone SVG document of the given size.
+///
+/// Coordinates are Bevy world coordinates with the origin at the center of the canvas and y
+/// pointing up (as with the default 2D camera), so the document flips y to match.
+pub fn shapes_to_svg(shapes: &[SvgShape], size: Vec2, background: Option<Color>) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="{x} {y} {w} {h}">"#,
+        w = num(size.x),
+        h = num(size.y),
+        x = num(-size.x / 2.0),
+        y = num(-size.y / 2.0),
+    )
+    .unwrap();
+
+    if let Some(color) = background {
+        writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+            num(-size.x / 2.0),
+            num(-size.y / 2.0),
+            num(size.x),
+            num(size.y),
+            paint_attrs("fill", color),
+        )
+        .unwrap();
+    }
+
+    svg.push_str("  <g transform=\"scale(1 -1)\">\n");
+
+    // Bevy draws higher z on top, SVG draws later elements on top. The sort is stable, so shapes
+    // with the same z keep their order.
+    let mut ordered: Vec<&SvgShape> = shapes.iter().collect();
+    ordered.sort_by(|a, b| a.transform.w_axis.z.total_cmp(&b.transform.w_axis.z));
+
+    for shape in ordered {
+        let m = shape.transform;
+        writeln!(
+            svg,
+            r#"    <path d="{}" transform="matrix({} {} {} {} {} {})" {}/>"#,
+            path_data(shape.path),
+            num(m.x_axis.x),
+            num(m.x_axis.y),
+            num(m.y_axis.x),
+            num(m.y_axis.y),
+            num(m.w_axis.x),
+            num(m.w_axis.y),
+            draw_mode_attrs(shape.draw_mode),
+        )
+        .unwrap();
+    }
+
+    svg.push_str("  </g>\n</svg>\n");
+    return svg;
+}
+
+
+/// SVG path data (the `d` attribute) for a lyon path
+pub fn path_data(path: &TessPath) -> String {
+    let mut commands: Vec<String> = Vec::new();
+    for event in path.iter() {
+        match event {
+            PathEvent::Begin { at } => commands.push(format!("M{} {}", num(at.x), num(at.y))),
+            PathEvent::Line { to, .. } => commands.push(format!("L{} {}", num(to.x), num(to.y))),
+            PathEvent::Quadratic { ctrl, to, .. } => commands.push(format!(
+                "Q{} {} {} {}",
+                num(ctrl.x),
+                num(ctrl.y),
+                num(to.x),
+                num(to.y)
+            )),
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => commands.push(format!(
+                "C{} {} {} {} {} {}",
+                num(ctrl1.x),
+                num(ctrl1.y),
+                num(ctrl2.x),
+                num(ctrl2.y),
+                num(to.x),
+                num(to.y)
+            )),
+            PathEvent::End { close: true, .. } => commands.push(String::from("Z")),
+            PathEvent::End { close: false, .. } => {}
+        }
+    }
+    commands.join(" ")
+}
+
+
+fn draw_mode_attrs(draw_mode: &DrawMode) -> String {
+    match draw_mode {
+        DrawMode::Fill(fill_mode) => format!(r#"{} stroke="none""#, fill_attrs(fill_mode)),
+        DrawMode::Stroke(stroke_mode) => format!(r#"fill="none" {}"#, stroke_attrs(stroke_mode)),
+        DrawMode::Outlined {
+            fill_mode,
+            outline_mode,
+        } => format!("{} {}", fill_attrs(fill_mode), stroke_attrs(outline_mode)),
+    }
+}
+
+
+fn fill_attrs(fill_mode: &FillMode) -> String {
+    let fill_rule = match fill_mode.options.fill_rule {
+        FillRule::EvenOdd => "evenodd",
+        FillRule::NonZero => "nonzero",
+    };
+    format!(
+        r#"{} fill-rule="{}""#,
+        paint_attrs("fill", fill_mode.color),
+        fill_rule
+    )
+}
+
+
+fn stroke_attrs(stroke_mode: &StrokeMode) -> String {
+    let options = &stroke_mode.options;
+    let line_join = match options.line_join {
+        LineJoin::Round => "round",
+        LineJoin::Bevel => "bevel",
+        LineJoin::Miter | LineJoin::MiterClip => "miter",
+    };
+    let line_cap = match options.start_cap {
+        LineCap::Round => "round",
+        LineCap::Square => "square",
+        LineCap::Butt => "butt",
+    };
+    format!(
+        r#"{} stroke-width="{}" stroke-linejoin="{}" stroke-linecap="{}" stroke-miterlimit="{}""#,
+        paint_attrs("stroke", stroke_mode.color),
+        num(options.line_width),
+        line_join,
+        line_cap,
+        num(options.miter_limit),
+    )
+}
+
+
+/// e.g. `fill="rgb(255,128,0)" fill-opacity="0.5"`
+fn paint_attrs(attr: &str, color: Color) -> String {
+    // as_rgba_f32 gives the sRGB components, which is what SVG expects
+    let [r, g, b, a] = color.as_rgba_f32();
+    let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        r#"{attr}="rgb({},{},{})" {attr}-opacity="{}""#,
+        to_byte(r),
+        to_byte(g),
+        to_byte(b),
+        num(a.clamp(0.0, 1.0)),
+        attr = attr,
+    )
+}
+
+
+/// Format a number compactly (at most 3 decimals, no trailing zeros)
+fn num(value: f32) -> String {
+    let formatted = format!("{:.3}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        return String::from("0");
+    }
+    trimmed.to_string()
+}
+
+
+fn export_svg_on_key(
+    keys: Res<Input<KeyCode>>,
+    webcfg: Res<WebExtrasCfg>,
+    clear_color: Res<ClearColor>,
+    rng: Res<SketchRng>,
+    time: Res<Time>,
+    shapes_q: Query<(&Path, &DrawMode, &GlobalTransform, Option<&Visibility>)>,
+) {
+    if !keys.just_pressed(SVG_EXPORT_KEY) {
+        return;
+    }
+
+    let shapes: Vec<SvgShape> = shapes_q
+        .iter()
+        .filter(|(_, _, _, visibility)| visibility.map_or(true, |v| v.is_visible))
+        .map(|(path, draw_mode, transform, _)| SvgShape {
+            path: &path.0,
+            draw_mode,
+            transform: transform.compute_matrix(),
+        })
+        .collect();
+    let svg = shapes_to_svg(
+        &shapes,
+        Vec2::new(webcfg.width, webcfg.height),
+        Some(clear_color.0),
+    );
+
+    // Seed in the name so the export can be matched with a re-run of the sketch
+    let file_name = format!(
+        "{}-{}-{}.svg",
+        webcfg.title.replace(' ', "_"),
+        rng.seed(),
+        (time.seconds_since_startup() * 1000.0) as u64
+    );
+    save_svg(&svg, &file_name);
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_svg(svg: &str, file_name: &str) {
+    let dir = std::path::Path::new(SVG_EXPORT_DIR);
+    let result =
+        std::fs::create_dir_all(dir).and_then(|_| std::fs::write(dir.join(file_name), svg));
+    match result {
+        Ok(_) => info!("SKETCH: exported {}/{}", SVG_EXPORT_DIR, file_name),
+        Err(e) => error!("SKETCH: could not export svg: {}", e),
+    }
+}
+
+
+/// Download by clicking a temporary link to a blob of the svg
+///
+/// Based on https://stackoverflow.com/a/19328891
+#[cfg(target_arch = "wasm32")]
+fn save_svg(svg: &str, file_name: &str) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let download = || -> Result<(), JsValue> {
+        let parts = js_sys::Array::of1(&JsValue::from_str(svg));
+        let mut options = web_sys::BlobPropertyBag::new();
+        options.type_("image/svg+xml");
+        let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or_else(|| JsValue::from_str("no document"))?;
+        let anchor = document
+            .create_element("a")?
+            .dyn_into::<web_sys::HtmlAnchorElement>()
+            .map_err(|_| JsValue::from_str("could not create link"))?;
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+        web_sys::Url::revoke_object_url(&url)
+    };
+
+    match download() {
+        Ok(_) => info!("SKETCH: exported {}", file_name),
+        Err(e) => error!("SKETCH: could not export svg: {:?}", e),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Path {
+        let mut path_builder = PathBuilder::new();
+        path_builder.move_to(Vec2::new(0.0, 0.0));
+        path_builder.line_to(Vec2::new(10.0, 0.0));
+        path_builder.line_to(Vec2::new(0.0, 10.5));
+        path_builder.close();
+        path_builder.build()
+    }
+
+    #[test]
+    fn serializes_path_data() {
+        let mut path_builder = PathBuilder::new();
+        path_builder.move_to(Vec2::new(0.0, 0.0));
+        path_builder.quadratic_bezier_to(Vec2::new(1.0, 2.0), Vec2::new(3.0, 4.0));
+        path_builder.cubic_bezier_to(Vec2::ONE, Vec2::new(2.0, 2.0), Vec2::new(-0.25, 0.0));
+        let path = path_builder.build();
+
+        assert_eq!(path_data(&path.0), "M0 0 Q1 2 3 4 C1 1 2 2 -0.25 0");
+        assert_eq!(path_data(&triangle().0), "M0 0 L10 0 L0 10.5 Z");
+    }
+
+    #[test]
+    fn serializes_colors_stroke_and_transform() {
+        let path = triangle();
+        let draw_mode = DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::rgba(1.0, 0.0, 0.0, 0.5)),
+            outline_mode: StrokeMode::new(Color::BLACK, 5.0),
+        };
+        let shapes = [SvgShape {
+            path: &path.0,
+            draw_mode: &draw_mode,
+            transform: Transform::from_xyz(5.0, -6.0, 1.0)
+                .with_scale(Vec3::new(2.0, 2.0, 1.0))
+                .compute_matrix(),
+        }];
+        let svg = shapes_to_svg(&shapes, Vec2::new(200.0, 100.0), Some(Color::WHITE));
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="-100 -50 200 100">"#
+        ));
+        assert!(svg.contains(r#"<rect x="-100" y="-50" width="200" height="100" fill="rgb(255,255,255)" fill-opacity="1"/>"#));
+        assert!(svg.contains(r#"d="M0 0 L10 0 L0 10.5 Z" transform="matrix(2 0 0 2 5 -6)""#));
+        assert!(svg.contains(r#"fill="rgb(255,0,0)" fill-opacity="0.5""#));
+        assert!(svg.contains(r#"stroke="rgb(0,0,0)" stroke-opacity="1" stroke-width="5""#));
+    }
+
+    #[test]
+    fn orders_by_z() {
+        let path = triangle();
+        let below = DrawMode::Fill(FillMode::color(Color::RED));
+        let above = DrawMode::Stroke(StrokeMode::new(Color::BLUE, 1.0));
+        let shapes = [
+            SvgShape {
+                path: &path.0,
+                draw_mode: &above,
+                transform: Transform::from_xyz(0.0, 0.0, 2.0).compute_matrix(),
+            },
+            SvgShape {
+                path: &path.0,
+                draw_mode: &below,
+                transform: Transform::from_xyz(0.0, 0.0, 1.0).compute_matrix(),
+            },
+        ];
+        let svg = shapes_to_svg(&shapes, Vec2::new(10.0, 10.0), None);
+
+        let below_at = svg.find(r#"fill="rgb(255,0,0)""#).unwrap();
+        let above_at = svg.find(r#"stroke="rgb(0,0,255)""#).unwrap();
+        assert!(below_at < above_at);
+        assert!(svg.contains(r#"fill="none" stroke="rgb(0,0,255)""#));
+        assert!(!svg.contains("<rect"));
+    }
+}
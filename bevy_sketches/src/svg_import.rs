@@ -0,0 +1,626 @@
+/*
+ * svg_import
+ *
+ * Load SVG path data as lyon shapes, the counterpart to `crate::svg_export`. Hand-drawn shapes
+ * can be saved from any vector editor into `assets/shapes/` and loaded with the asset server
+ * (`asset_server.load::<SvgPath, _>("shapes/heart.svg")`), or path data strings can be parsed
+ * directly with `SvgPath::parse`.
+ *
+ * All `<path>` elements of a file make up a single shape, except those inside `<defs>`,
+ * `<clipPath>`, `<mask>` and other containers that aren't drawn in place. Only the `d` attribute is
+ * read, so transforms, styles and other elements (`<rect>`, `<circle>`...) are ignored: convert
+ * those to paths in the editor before saving. Arcs are converted to cubic bézier curves.
+ *
+ * Shapes are normalized: centered on the origin, y flipped to point up (like Bevy's 2D world) and
+ * scaled so the longest side of the bounding box spans -1.0 to 1.0. Scale them to the size you
+ * need with `SvgPath::builder`.
+ *
+ * A file that fails to parse is reported by the asset server with the file's path and the
+ * position of the error, and its handle never loads.
+ *
+ * Instructed by:
+ * https://developer.mozilla.org/en-US/docs/Web/SVG/Tutorial/Paths
+ * https://www.w3.org/TR/SVG11/paths.html#PathData
+ * https://github.com/bevyengine/bevy/blob/v0.7.0/examples/asset/custom_asset.rs
+ */
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy_prototype_lyon::prelude::*;
+use std::fmt;
+use svgtypes::{PathParser, PathSegment};
+use tess::geom::euclid::default::Box2D;
+use tess::geom::{ArcFlags, CubicBezierSegment, QuadraticBezierSegment, SvgArc};
+use tess::math::{point, vector, Angle, Point};
+
+
+/// Elements whose paths aren't drawn where they stand: definitions, clip and mask outlines,
+/// markers and the like
+const HIDDEN_CONTAINERS: &[&str] = &["defs", "clipPath", "mask", "marker", "pattern", "symbol"];
+/// Markup that isn't an element, from its start to its end: comments, CDATA sections, processing
+/// instructions and declarations (the doctype)
+const SKIPPED_MARKUP: &[(&str, &str)] = &[
+    ("<!--", "-->"),
+    ("<![CDATA[", "]]>"),
+    ("<?", "?>"),
+    ("<!", ">"),
+];
+
+
+pub struct SvgImportPlugin;
+
+
+impl Plugin for SvgImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SvgPath>()
+            .init_asset_loader::<SvgPathLoader>();
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    MoveTo(Vec2),
+    LineTo(Vec2),
+    QuadraticTo(Vec2, Vec2),
+    CubicTo(Vec2, Vec2, Vec2),
+    Close,
+}
+
+
+/// A shape parsed from SVG path data, normalized to fit in -1.0..=1.0
+#[derive(Debug, Clone, PartialEq, TypeUuid)]
+#[uuid = "5b0b3b47-8d1e-4c5f-9a3e-2f6c1d7e8a90"]
+pub struct SvgPath {
+    segments: Vec<Segment>,
+    size: Vec2,
+}
+
+
+#[derive(Debug)]
+pub enum SvgImportError {
+    /// Invalid path data, with the index of the `<path>` it's in
+    Parse(usize, svgtypes::Error),
+    /// The file has no `<path>` element with a `d` attribute
+    NoPathData,
+    /// The path data draws nothing (or only a point), so it can't be normalized
+    Empty,
+}
+
+
+impl fmt::Display for SvgImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SvgImportError::Parse(index, err) => write!(f, "<path> #{}: {}", index, err),
+            SvgImportError::NoPathData => write!(f, "no <path> with a `d` attribute found"),
+            SvgImportError::Empty => write!(f, "path data draws nothing"),
+        }
+    }
+}
+
+
+impl std::error::Error for SvgImportError {}
+
+
+impl SvgPath {
+    /// Parse a path data string, e.g. `M 10 10 L 90 10 L 50 80 Z`
+    pub fn parse(data: &str) -> Result<SvgPath, SvgImportError> {
+        return SvgPath::parse_all(&[data]);
+    }
+
+    /// Parse an SVG document, combining all of its `<path>` elements into one shape
+    pub fn from_svg(svg: &str) -> Result<SvgPath, SvgImportError> {
+        let data = path_data_attrs(svg);
+        if data.is_empty() {
+            return Err(SvgImportError::NoPathData);
+        }
+        return SvgPath::parse_all(&data);
+    }
+
+    fn parse_all(data: &[&str]) -> Result<SvgPath, SvgImportError> {
+        let mut segments = Vec::new();
+        for (index, d) in data.iter().enumerate() {
+            parse_path_data(d, &mut segments).map_err(|err| SvgImportError::Parse(index, err))?;
+        }
+
+        let (min, max) = bounding_box(&segments).ok_or(SvgImportError::Empty)?;
+        let extent = (max - min).max_element();
+        if extent <= f32::EPSILON {
+            return Err(SvgImportError::Empty);
+        }
+
+        let center = (min + max) / 2.0;
+        let scale = 2.0 / extent;
+        // SVG's y points down, Bevy's up
+        let normalize = |p: Vec2| Vec2::new(p.x - center.x, center.y - p.y) * scale;
+        for segment in segments.iter_mut() {
+            *segment = match *segment {
+                Segment::MoveTo(to) => Segment::MoveTo(normalize(to)),
+                Segment::LineTo(to) => Segment::LineTo(normalize(to)),
+                Segment::QuadraticTo(ctrl, to) => {
+                    Segment::QuadraticTo(normalize(ctrl), normalize(to))
+                }
+                Segment::CubicTo(ctrl1, ctrl2, to) => {
+                    Segment::CubicTo(normalize(ctrl1), normalize(ctrl2), normalize(to))
+                }
+                Segment::Close => Segment::Close,
+            };
+        }
+
+        let size = (max - min) * scale;
+        return Ok(SvgPath { segments, size });
+    }
+
+    /// Size of the normalized bounding box. The longest side is always 2.0.
+    pub fn size(&self) -> Vec2 {
+        return self.size;
+    }
+
+    /// A `PathBuilder` with the shape scaled by `scale`, e.g. `Vec2::splat(100.0)` for a shape
+    /// whose longest side is 200.0
+    pub fn builder(&self, scale: Vec2) -> PathBuilder {
+        let mut path_builder = PathBuilder::new();
+        for segment in self.segments.iter() {
+            match *segment {
+                Segment::MoveTo(to) => {
+                    path_builder.move_to(to * scale);
+                }
+                Segment::LineTo(to) => {
+                    path_builder.line_to(to * scale);
+                }
+                Segment::QuadraticTo(ctrl, to) => {
+                    path_builder.quadratic_bezier_to(ctrl * scale, to * scale);
+                }
+                Segment::CubicTo(ctrl1, ctrl2, to) => {
+                    path_builder.cubic_bezier_to(ctrl1 * scale, ctrl2 * scale, to * scale);
+                }
+                Segment::Close => path_builder.close(),
+            }
+        }
+        return path_builder;
+    }
+}
+
+
+/// Parse path data into absolute segments, resolving relative, shorthand and smooth commands
+fn parse_path_data(data: &str, segments: &mut Vec<Segment>) -> Result<(), svgtypes::Error> {
+    let mut current = Vec2::ZERO;
+    let mut subpath_start = Vec2::ZERO;
+    // Last control point of the previous segment, to be reflected by smooth curves (`S` and `T`)
+    let mut prev_cubic_ctrl: Option<Vec2> = None;
+    let mut prev_quad_ctrl: Option<Vec2> = None;
+    let mut closed = false;
+
+    for segment in PathParser::from(data) {
+        let segment = segment?;
+        let from = current;
+        let abs = |abs: bool, x: f64, y: f64| {
+            let p = Vec2::new(x as f32, y as f32);
+            if abs {
+                p
+            } else {
+                from + p
+            }
+        };
+
+        // After a close, drawing continues from the start of the closed subpath
+        if closed && !matches!(segment, PathSegment::MoveTo { .. }) {
+            segments.push(Segment::MoveTo(subpath_start));
+        }
+        closed = false;
+
+        let mut cubic_ctrl = None;
+        let mut quad_ctrl = None;
+        match segment {
+            PathSegment::MoveTo { abs: a, x, y } => {
+                current = abs(a, x, y);
+                subpath_start = current;
+                segments.push(Segment::MoveTo(current));
+            }
+            PathSegment::LineTo { abs: a, x, y } => {
+                current = abs(a, x, y);
+                segments.push(Segment::LineTo(current));
+            }
+            PathSegment::HorizontalLineTo { abs: a, x } => {
+                current.x = if a { x as f32 } else { current.x + x as f32 };
+                segments.push(Segment::LineTo(current));
+            }
+            PathSegment::VerticalLineTo { abs: a, y } => {
+                current.y = if a { y as f32 } else { current.y + y as f32 };
+                segments.push(Segment::LineTo(current));
+            }
+            PathSegment::CurveTo {
+                abs: a,
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                let ctrl1 = abs(a, x1, y1);
+                let ctrl2 = abs(a, x2, y2);
+                current = abs(a, x, y);
+                segments.push(Segment::CubicTo(ctrl1, ctrl2, current));
+                cubic_ctrl = Some(ctrl2);
+            }
+            PathSegment::SmoothCurveTo {
+                abs: a,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                let ctrl1 = prev_cubic_ctrl.map_or(current, |ctrl| current * 2.0 - ctrl);
+                let ctrl2 = abs(a, x2, y2);
+                current = abs(a, x, y);
+                segments.push(Segment::CubicTo(ctrl1, ctrl2, current));
+                cubic_ctrl = Some(ctrl2);
+            }
+            PathSegment::Quadratic {
+                abs: a,
+                x1,
+                y1,
+                x,
+                y,
+            } => {
+                let ctrl = abs(a, x1, y1);
+                current = abs(a, x, y);
+                segments.push(Segment::QuadraticTo(ctrl, current));
+                quad_ctrl = Some(ctrl);
+            }
+            PathSegment::SmoothQuadratic { abs: a, x, y } => {
+                let ctrl = prev_quad_ctrl.map_or(current, |ctrl| current * 2.0 - ctrl);
+                current = abs(a, x, y);
+                segments.push(Segment::QuadraticTo(ctrl, current));
+                quad_ctrl = Some(ctrl);
+            }
+            PathSegment::EllipticalArc {
+                abs: a,
+                rx,
+                ry,
+                x_axis_rotation,
+                large_arc,
+                sweep,
+                x,
+                y,
+            } => {
+                let to = abs(a, x, y);
+                let arc = SvgArc {
+                    from: point(current.x, current.y),
+                    to: point(to.x, to.y),
+                    // Negative radii are used as absolute values, as the SVG spec says
+                    radii: vector(rx.abs() as f32, ry.abs() as f32),
+                    x_rotation: Angle::degrees(x_axis_rotation as f32),
+                    flags: ArcFlags { large_arc, sweep },
+                };
+                if arc.is_straight_line() {
+                    segments.push(Segment::LineTo(to));
+                } else {
+                    arc.for_each_cubic_bezier(&mut |curve: &CubicBezierSegment<f32>| {
+                        segments.push(Segment::CubicTo(
+                            Vec2::new(curve.ctrl1.x, curve.ctrl1.y),
+                            Vec2::new(curve.ctrl2.x, curve.ctrl2.y),
+                            Vec2::new(curve.to.x, curve.to.y),
+                        ));
+                    });
+                }
+                current = to;
+            }
+            PathSegment::ClosePath { .. } => {
+                segments.push(Segment::Close);
+                current = subpath_start;
+                closed = true;
+            }
+        }
+        prev_cubic_ctrl = cubic_ctrl;
+        prev_quad_ctrl = quad_ctrl;
+    }
+    return Ok(());
+}
+
+
+/// Exact bounding box of the segments (curves are measured, not just their control points),
+/// as `(min, max)`
+fn bounding_box(segments: &[Segment]) -> Option<(Vec2, Vec2)> {
+    let to_point = |p: Vec2| point(p.x, p.y);
+    let mut bounds: Option<(Vec2, Vec2)> = None;
+    // Not using `Box2D::union`, it skips boxes with no area (like that of a straight line)
+    let mut include = |b: Box2D<f32>| {
+        let (min, max) = (Vec2::new(b.min.x, b.min.y), Vec2::new(b.max.x, b.max.y));
+        bounds = Some(bounds.map_or((min, max), |(bmin, bmax)| (bmin.min(min), bmax.max(max))));
+    };
+    let mut current: Point = point(0.0, 0.0);
+
+    for segment in segments.iter() {
+        match *segment {
+            Segment::MoveTo(to) => {
+                // A lone move draws nothing, so it only counts once something is drawn from it
+                current = to_point(to);
+            }
+            Segment::LineTo(to) => {
+                let to = to_point(to);
+                include(Box2D::from_points([current, to]));
+                current = to;
+            }
+            Segment::QuadraticTo(ctrl, to) => {
+                let curve = QuadraticBezierSegment {
+                    from: current,
+                    ctrl: to_point(ctrl),
+                    to: to_point(to),
+                };
+                include(curve.bounding_box());
+                current = curve.to;
+            }
+            Segment::CubicTo(ctrl1, ctrl2, to) => {
+                let curve = CubicBezierSegment {
+                    from: current,
+                    ctrl1: to_point(ctrl1),
+                    ctrl2: to_point(ctrl2),
+                    to: to_point(to),
+                };
+                include(curve.bounding_box());
+                current = curve.to;
+            }
+            Segment::Close => {}
+        }
+    }
+    return bounds;
+}
+
+
+/// The `d` attribute values of the drawn `<path>` elements in an SVG document.
+///
+/// A small tokenizer rather than a full XML parse, good enough for files saved by vector editors:
+/// comments, CDATA sections, processing instructions and the doctype are skipped, a `>` inside a
+/// quoted attribute value doesn't end a tag, and paths within `HIDDEN_CONTAINERS` are left out.
+fn path_data_attrs(svg: &str) -> Vec<&str> {
+    let mut data = Vec::new();
+    // Names of the elements the scan is inside of, innermost last
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = svg;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some((_, end)) = SKIPPED_MARKUP
+            .iter()
+            .find(|(start, _)| rest.starts_with(start))
+        {
+            rest = rest.find(end).map_or("", |i| &rest[i + end.len()..]);
+            continue;
+        }
+
+        let tag_end = tag_end(rest);
+        let tag = &rest[1..tag_end];
+        rest = &rest[(tag_end + 1).min(rest.len())..];
+        if let Some(name) = tag.strip_prefix('/') {
+            // Also closes elements left open by mistake
+            if let Some(index) = open.iter().rposition(|open| *open == name.trim()) {
+                open.truncate(index);
+            }
+            continue;
+        }
+
+        let tag = tag.trim_end();
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (name, attrs) = tag.split_at(tag.find(char::is_whitespace).unwrap_or(tag.len()));
+        let hidden = open.iter().any(|open| HIDDEN_CONTAINERS.contains(open));
+        if name == "path" && !hidden {
+            if let Some(d) = attr_value(attrs, "d") {
+                data.push(d);
+            }
+        }
+        if !self_closing {
+            open.push(name);
+        }
+    }
+    return data;
+}
+
+
+/// Index of the `>` ending the tag `markup` starts with (or its length, if unterminated), skipping
+/// quoted attribute values
+fn tag_end(markup: &str) -> usize {
+    let mut quote = None;
+    for (index, c) in markup.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return index,
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    return markup.len();
+}
+
+
+/// Value of the attribute `name` within the inside of a tag
+fn attr_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let attr_name = rest[..eq].trim_end();
+        let is_match = attr_name.ends_with(name)
+            && attr_name[..attr_name.len() - name.len()].ends_with(|c: char| c.is_whitespace());
+
+        let value_start = rest[eq + 1..].trim_start();
+        let quote = value_start.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value_start = &value_start[1..];
+        let value_end = value_start.find(quote)?;
+        if is_match {
+            return Some(&value_start[..value_end]);
+        }
+        rest = &value_start[value_end + 1..];
+    }
+    return None;
+}
+
+
+#[derive(Default)]
+pub struct SvgPathLoader;
+
+
+impl AssetLoader for SvgPathLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            // The asset server doesn't say which file failed, so the path goes in the error
+            let path = load_context.path().display().to_string();
+            let svg = std::str::from_utf8(bytes)
+                .map_err(|err| anyhow::anyhow!("SVG import of '{}': {}", path, err))?;
+            let shape = SvgPath::from_svg(svg)
+                .map_err(|err| anyhow::anyhow!("SVG import of '{}': {}", path, err))?;
+            load_context.set_default_asset(LoadedAsset::new(shape));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["svg"]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn normalizes_and_flips_y() {
+        let shape = SvgPath::parse("M 10 10 L 110 10 L 110 60 Z").unwrap();
+        assert_near(shape.size(), Vec2::new(2.0, 1.0));
+        assert_eq!(
+            shape.segments,
+            vec![
+                Segment::MoveTo(Vec2::new(-1.0, 0.5)),
+                Segment::LineTo(Vec2::new(1.0, 0.5)),
+                Segment::LineTo(Vec2::new(1.0, -0.5)),
+                Segment::Close,
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_relative_and_smooth_commands() {
+        let mut segments = Vec::new();
+        parse_path_data(
+            "m 1 1 h 2 v 2 c 1 0 1 1 1 1 s 1 1 1 1 q 1 0 1 1 t 1 1 z l 1 0",
+            &mut segments,
+        )
+        .unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::MoveTo(Vec2::new(1.0, 1.0)),
+                Segment::LineTo(Vec2::new(3.0, 1.0)),
+                Segment::LineTo(Vec2::new(3.0, 3.0)),
+                Segment::CubicTo(
+                    Vec2::new(4.0, 3.0),
+                    Vec2::new(4.0, 4.0),
+                    Vec2::new(4.0, 4.0)
+                ),
+                // First control point reflected from the previous curve's second
+                Segment::CubicTo(
+                    Vec2::new(4.0, 4.0),
+                    Vec2::new(5.0, 5.0),
+                    Vec2::new(5.0, 5.0)
+                ),
+                Segment::QuadraticTo(Vec2::new(6.0, 5.0), Vec2::new(6.0, 6.0)),
+                Segment::QuadraticTo(Vec2::new(6.0, 7.0), Vec2::new(7.0, 7.0)),
+                Segment::Close,
+                // Drawing after a close continues from the subpath start
+                Segment::MoveTo(Vec2::new(1.0, 1.0)),
+                Segment::LineTo(Vec2::new(2.0, 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_arcs_to_curves() {
+        // A circle of radius 1 from two half arcs
+        let shape = SvgPath::parse("M -1 0 A 1 1 0 0 0 1 0 A 1 1 0 0 0 -1 0 Z").unwrap();
+        assert_near(shape.size(), Vec2::new(2.0, 2.0));
+        assert!(shape
+            .segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::CubicTo(..))));
+        for segment in shape.segments.iter() {
+            if let Segment::CubicTo(_, _, to) = segment {
+                assert!((to.length() - 1.0).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn combines_paths_of_a_document() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+            <path id="a" fill="red" d="M 0 0 L 10 0"/>
+            <pathology d="M 100 100 L 200 200"/>
+            <path
+                d='M 0 5 L 10 5' />
+        </svg>"#;
+        assert_eq!(path_data_attrs(svg), vec!["M 0 0 L 10 0", "M 0 5 L 10 5"]);
+        assert_near(SvgPath::from_svg(svg).unwrap().size(), Vec2::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn skips_hidden_paths_and_comments() {
+        let svg = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "svg11.dtd">
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+            <!-- <path d="M 0 0 L 100 100"/> -->
+            <defs>
+                <clipPath id="clip"><path d="M 0 0 L 50 50"/></clipPath>
+                <path id="reused" d="M 0 0 L 60 60"></path>
+            </defs>
+            <mask id="m"><g><path d="M 0 0 L 70 70"/></g></mask>
+            <g clip-path="url(#clip)" data-note="a > b">
+                <path data-label='x>y' d="M 0 0 L 10 0"/>
+            </g>
+            <path d="M 0 5 L 10 5"></path>
+        </svg>"#;
+        assert_eq!(path_data_attrs(svg), vec!["M 0 0 L 10 0", "M 0 5 L 10 5"]);
+        assert_near(SvgPath::from_svg(svg).unwrap().size(), Vec2::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn bundled_shapes_parse() {
+        for svg in [
+            include_str!("../assets/shapes/blob.svg"),
+            include_str!("../assets/shapes/heart.svg"),
+            include_str!("../assets/shapes/moon.svg"),
+            include_str!("../assets/shapes/star.svg"),
+            include_str!("../assets/shapes/wave.svg"),
+        ] {
+            let shape = SvgPath::from_svg(svg).unwrap();
+            assert!((shape.size().max_element() - 2.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(matches!(
+            SvgPath::from_svg("<svg><rect/></svg>"),
+            Err(SvgImportError::NoPathData)
+        ));
+        assert!(matches!(
+            SvgPath::parse("M 5 5"),
+            Err(SvgImportError::Empty)
+        ));
+        let svg = r#"<svg><path d="M 0 0 L 1 1"/><path d="M 0 0 L 1 x"/></svg>"#;
+        assert!(matches!(
+            SvgPath::from_svg(svg),
+            Err(SvgImportError::Parse(1, _))
+        ));
+    }
+}
@@ -1,28 +1,92 @@
 use clap::Parser;
 use const_format::concatcp;
+use glob::Pattern;
 use serde_json::json;
 use serde_json::{Result, Value};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 
 const WWW_PATH: &str = "bevy_sketches/www";
 const CANVAS_HTML_TPL: &str = concatcp!(WWW_PATH, "/match_window.tpl.html");
 const WASM_CONFIG: &str = concatcp!(WWW_PATH, "/sketches.json");
-const EXAMPLES_DIR: &str = "bevy_sketches/examples";
+/// Key under `[package.metadata]` in a crate's Cargo.toml that holds per-sketch settings, e.g.
+///
+/// ```toml
+/// [package.metadata.sketches.shiftyc]
+/// tags = ["shifty", "lyon", "shader"]
+/// # Every preset in `assets/presets/shifty/` gets a page of its own, `shifty<preset>.html`
+/// variations = "shifty"
+/// ```
+const SKETCHES_METADATA_KEY: &str = "sketches";
+/// Directory of the preset files in a package, see `variations` above
+const PRESETS_PATH: &str = "assets/presets";
+const PRESET_EXTENSION: &str = ".preset.ron";
 
 
-fn gen_html_from_template(sketch: &str, template: &str) {
+/// An example target found through `cargo metadata`
+#[derive(Debug)]
+struct SketchTarget {
+    name: String,
+    package: String,
+    tags: Vec<String>,
+    /// Features declared by the package, as not every crate has `debuglog` and `framestats`
+    features: Vec<String>,
+    /// Pages of the sketch with other runtime options, built from the same wasm
+    variations: Vec<Variation>,
+}
+
+
+/// A page of a sketch with its own default runtime options (e.g. `preset=star`)
+#[derive(Debug, PartialEq)]
+struct Variation {
+    name: String,
+    opts: String,
+}
+
+
+/// Fill in the template: `{{sketch}}` is the page's name, `{{wasm}}` the sketch it loads and
+/// `{{opts}}` the default runtime options of the page
+fn gen_html_from_template(page: &str, wasm: &str, opts: &str, template: &str) {
     println!("{}", template);
     let file_contents = fs::read_to_string(template).expect("Unable to read file");
 
-    let file_contents = file_contents.replace("{{sketch}}", sketch);
-    fs::write(format!("{}/{}.html", WWW_PATH, sketch), file_contents)
+    let file_contents = file_contents
+        .replace("{{sketch}}", page)
+        .replace("{{wasm}}", wasm)
+        .replace("{{opts}}", opts);
+    fs::write(format!("{}/{}.html", WWW_PATH, page), file_contents)
         .expect("Unable to write html file");
 }
 
 
+/// A variation per preset file in `<package_dir>/assets/presets/<presets>/`, named `<presets>`
+/// followed by the preset's name (`shifty` and `star.preset.ron` make `shiftystar`), sorted by name
+fn preset_variations(package_dir: &Path, presets: &str) -> Vec<Variation> {
+    let dir = package_dir.join(PRESETS_PATH).join(presets);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("No presets in {}: {}", dir.display(), err);
+            return Vec::new();
+        }
+    };
+    let mut variations: Vec<Variation> = entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().to_string_lossy().to_string();
+            let preset = file_name.strip_suffix(PRESET_EXTENSION)?;
+            Some(Variation {
+                name: format!("{}{}", presets, preset),
+                opts: format!("preset={}", preset),
+            })
+        })
+        .collect();
+    variations.sort_by(|a, b| a.name.cmp(&b.name));
+    variations
+}
+
+
 fn add_to_sketch_to_json_cfg(sketch: &str) -> Result<()> {
     let list_path = Path::new(WASM_CONFIG);
 
@@ -44,18 +108,142 @@ fn add_to_sketch_to_json_cfg(sketch: &str) -> Result<()> {
         // println!("{}", json_cfg.to_string());
         fs::write(WASM_CONFIG, json_cfg.to_string()).expect("Unable to rewrite json list");
     }
-    return Ok(());
+    Ok(())
+}
+
+
+/// Ask cargo for every example target in the workspace.
+///
+/// Using `cargo metadata` (instead of listing the examples directory) means multi-file examples
+/// in sub-directories (`examples/foo/main.rs`) are found, stray non-Rust files are ignored, and
+/// examples from any workspace crate can be built.
+///
+/// Returns the sketches along with cargo's target directory.
+fn discover_sketches() -> (Vec<SketchTarget>, String) {
+    let output = Command::new("cargo")
+        .arg("metadata")
+        .arg("--format-version")
+        .arg("1")
+        .arg("--no-deps")
+        .stderr(Stdio::inherit())
+        .output()
+        .expect("Could not run `cargo metadata`");
+    if !output.status.success() {
+        panic!("`cargo metadata` failed");
+    }
+
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).expect("Failed to get JSON from cargo metadata");
+    let target_dir = metadata["target_directory"]
+        .as_str()
+        .expect("No target_directory in cargo metadata")
+        .to_string();
+
+    let mut sketches = Vec::new();
+    // With `--no-deps`, `packages` only contains the workspace members
+    for package in metadata["packages"].as_array().into_iter().flatten() {
+        let package_name = package["name"].as_str().unwrap_or_default();
+        let package_dir = PathBuf::from(package["manifest_path"].as_str().unwrap_or_default())
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let features: Vec<String> = package["features"]
+            .as_object()
+            .into_iter()
+            .flat_map(|features| features.keys().cloned())
+            .collect();
+        for target in package["targets"].as_array().into_iter().flatten() {
+            let is_example = target["kind"]
+                .as_array()
+                .is_some_and(|kinds| kinds.contains(&json!("example")));
+            if !is_example {
+                continue;
+            }
+            let name = target["name"].as_str().unwrap_or_default();
+            let settings = &package["metadata"][SKETCHES_METADATA_KEY][name];
+            let tags = settings["tags"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|tag| tag.as_str())
+                .map(String::from)
+                .collect();
+            let variations = match settings["variations"].as_str() {
+                Some(presets) => preset_variations(&package_dir, presets),
+                None => Vec::new(),
+            };
+            sketches.push(SketchTarget {
+                name: name.to_string(),
+                package: package_name.to_string(),
+                tags,
+                features: features.clone(),
+                variations,
+            });
+        }
+    }
+    (sketches, target_dir)
+}
+
+
+fn to_patterns(globs: &[String]) -> Vec<Pattern> {
+    globs
+        .iter()
+        .map(|g| Pattern::new(g).unwrap_or_else(|_| panic!("Invalid glob pattern: {}", g)))
+        .collect()
+}
+
+
+/// Keep sketches that match any `include` glob (or all, if there are none), match none of the
+/// `exclude` globs, and have at least one of `tags` (if any were given).
+fn filter_sketches(
+    sketches: Vec<SketchTarget>,
+    include: &[String],
+    exclude: &[String],
+    tags: &[String],
+) -> Vec<SketchTarget> {
+    let include = to_patterns(include);
+    let exclude = to_patterns(exclude);
+
+    sketches
+        .into_iter()
+        .filter(|s| include.is_empty() || include.iter().any(|p| p.matches(&s.name)))
+        .filter(|s| !exclude.iter().any(|p| p.matches(&s.name)))
+        .filter(|s| tags.is_empty() || tags.iter().any(|t| s.tags.contains(t)))
+        .collect()
+}
+
+
+/// `--features` args for the requested features the sketch's package declares. Both features only
+/// change the defaults, since log level and frame stats can also be chosen at runtime (e.g.
+/// `?log=debug&stats=1`), so the others are skipped with a note.
+fn feature_args(sketch: &SketchTarget, framestats: bool, debuglog: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    for (feature, enabled) in [("debuglog", debuglog), ("framestats", framestats)] {
+        if !enabled {
+            continue;
+        }
+        if sketch.features.iter().any(|f| f == feature) {
+            args.push(format!("--features={}", feature));
+        } else {
+            println!(
+                "{} has no `{}` feature, building without it",
+                sketch.package, feature
+            );
+        }
+    }
+    args
 }
 
 
 fn build_sketch(
-    sketch: &str,
-    template: &String,
+    sketch: &SketchTarget,
+    target_dir: &str,
+    template: &str,
     no_html: &bool,
     framestats: &bool,
     debuglog: &bool,
 ) {
-    println!("Building {}...", sketch);
+    println!("Building {} (from {})...", sketch.name, sketch.package);
     // In the previous commit I was following this example
     // https://rust-lang-nursery.github.io/rust-cookbook/os/external.html#continuously-process-child-process-outputs
     //
@@ -63,27 +251,17 @@ fn build_sketch(
     // using Stdio::inherit which I think serves this use case better:
     // https://stackoverflow.com/a/32020376/4655636
     let mut build_cmd = Command::new("cargo");
-    let mut build_folder = "release";
 
     build_cmd
         .arg("build")
+        .arg("--package")
+        .arg(&sketch.package)
         .arg("--example")
-        .arg(sketch)
+        .arg(&sketch.name)
         .arg("--target")
-        .arg("wasm32-unknown-unknown");
-    if *debuglog {
-        println!(
-            "\nWARNING: THIS IS A DEBUG BUILD! --debuglog was passed, so \
-            using `--features=debuglog` instead of `--release` flag.\n"
-        );
-        build_folder = "debug";
-        build_cmd.arg("--features=debuglog");
-    } else {
-        build_cmd.arg("--release");
-    }
-    if *framestats {
-        build_cmd.arg("--features=framestats");
-    }
+        .arg("wasm32-unknown-unknown")
+        .arg("--release")
+        .args(feature_args(sketch, *framestats, *debuglog));
     build_cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
 
     let mut build_cmd_child = build_cmd.spawn().unwrap();
@@ -91,15 +269,15 @@ fn build_sketch(
         return;
     }
 
-    println!("Running wasm-bindgen for {}...", sketch);
+    println!("Running wasm-bindgen for {}...", sketch.name);
     let mut wasm_bgen_cmd = Command::new("wasm-bindgen")
         .arg("--out-dir")
         .arg(concatcp!(WWW_PATH, "/wasms"))
         .arg("--target")
         .arg("web")
         .arg(format!(
-            "target/wasm32-unknown-unknown/{}/examples/{}.wasm",
-            build_folder, sketch
+            "{}/wasm32-unknown-unknown/release/examples/{}.wasm",
+            target_dir, sketch.name
         ))
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
@@ -110,47 +288,43 @@ fn build_sketch(
         return;
     }
 
-    if !no_html {
-        println!("Creating html from template...");
-        gen_html_from_template(sketch, template);
-    }
-
-    println!("Adding sketch to list in json...");
-    add_to_sketch_to_json_cfg(sketch).expect("Could not add sketch to json list");
+    // The sketch's own page, then one per variation
+    let pages = std::iter::once((sketch.name.as_str(), "")).chain(
+        sketch
+            .variations
+            .iter()
+            .map(|v| (v.name.as_str(), v.opts.as_str())),
+    );
+    for (page, opts) in pages {
+        if !no_html {
+            println!("Creating html for {} from template...", page);
+            gen_html_from_template(page, &sketch.name, opts, template);
+        }
 
-    if *debuglog {
-        println!(
-            "\nWARNING: THIS IS A DEBUG BUILD! --debuglog was passed, so \
-            `--features=debuglog` was used instead of `--release` flag.\n"
-        );
+        println!("Adding {} to list in json...", page);
+        add_to_sketch_to_json_cfg(page).expect("Could not add sketch to json list");
     }
 
     // @TODO: This notification method is not portable
     Command::new("./notify-send-all")
         .arg("root")
-        .arg(format!("Finished building {}", sketch))
+        .arg(format!("Finished building {}", sketch.name))
         .output()
         .expect("Could not notify");
 }
 
 
-fn build_sketches(template: &String, no_html: &bool, framestats: &bool, debuglog: &bool) {
-    let egs_dir = Path::new(EXAMPLES_DIR);
-    if egs_dir.is_dir() {
-        for entry in fs::read_dir(egs_dir).expect("Couldn't read directory") {
-            let entry = entry.expect("Couldn't get item");
-            let path = entry.path();
-            if path.is_file() {
-                println!("{}", path.file_stem().unwrap().to_str().unwrap());
-                build_sketch(
-                    path.file_stem().unwrap().to_str().unwrap(),
-                    template,
-                    no_html,
-                    framestats,
-                    debuglog,
-                );
-            }
-        }
+fn build_sketches(
+    sketches: &[SketchTarget],
+    target_dir: &str,
+    template: &str,
+    no_html: &bool,
+    framestats: &bool,
+    debuglog: &bool,
+) {
+    for sketch in sketches {
+        println!("{}", sketch.name);
+        build_sketch(sketch, target_dir, template, no_html, framestats, debuglog);
     }
 }
 
@@ -162,6 +336,18 @@ struct Args {
     #[clap(short, long)]
     sketch: Option<String>,
 
+    /// Only build sketches whose name matches this glob (can be repeated)
+    #[clap(long)]
+    include: Vec<String>,
+
+    /// Skip sketches whose name matches this glob (can be repeated)
+    #[clap(long)]
+    exclude: Vec<String>,
+
+    /// Only build sketches with this tag (can be repeated, any tag matches)
+    #[clap(long)]
+    tag: Vec<String>,
+
     /// Html template
     #[clap(short, long, default_value_t = CANVAS_HTML_TPL.to_string())]
     template: String,
@@ -169,10 +355,11 @@ struct Args {
     /// Skip generation of html file
     #[clap(long = "no-html")]
     no_html: bool,
-    /// Enable logging of frame statistics like fps
+    /// Enable logging of frame statistics like fps by default (also possible at runtime with
+    /// `?stats=1`)
     #[clap(long = "framestats")]
     framestats: bool,
-    // /// Display debug logs
+    /// Display debug logs by default (also possible at runtime with `?log=debug`)
     #[clap(long = "debuglog")]
     debuglog: bool,
 }
@@ -181,16 +368,27 @@ struct Args {
 #[allow(dead_code)]
 fn main() {
     let args = Args::parse();
+    let (sketches, target_dir) = discover_sketches();
 
     match args.sketch {
-        Some(sketch) => build_sketch(
-            &sketch,
-            &args.template,
-            &args.no_html,
-            &args.framestats,
-            &args.debuglog,
-        ),
+        // A variation is built with the sketch it belongs to
+        Some(sketch) => match sketches
+            .iter()
+            .find(|s| s.name == sketch || s.variations.iter().any(|v| v.name == sketch))
+        {
+            Some(sketch) => build_sketch(
+                sketch,
+                &target_dir,
+                &args.template,
+                &args.no_html,
+                &args.framestats,
+                &args.debuglog,
+            ),
+            None => println!("No sketch named {} found in the workspace", sketch),
+        },
         None => build_sketches(
+            &filter_sketches(sketches, &args.include, &args.exclude, &args.tag),
+            &target_dir,
             &args.template,
             &args.no_html,
             &args.framestats,
@@ -198,3 +396,146 @@ fn main() {
         ),
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch(name: &str, tags: &[&str], features: &[&str]) -> SketchTarget {
+        SketchTarget {
+            name: name.to_string(),
+            package: String::from("bevy_sketches"),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+            variations: Vec::new(),
+        }
+    }
+
+    fn sketches() -> Vec<SketchTarget> {
+        vec![
+            sketch("shiftyc", &["shifty", "lyon", "shader"], &[]),
+            sketch("cellular", &["lyon"], &[]),
+            sketch("scaling_net_shader", &["shader"], &[]),
+            sketch("erratic_wave_shader", &["shader"], &[]),
+        ]
+    }
+
+    /// Include globs, exclude globs, tags and the names expected to be kept
+    type FilterCase<'a> = (&'a [&'a str], &'a [&'a str], &'a [&'a str], &'a [&'a str]);
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn filters_sketches() {
+        let cases: [FilterCase; 6] = [
+            (
+                &[],
+                &[],
+                &[],
+                &[
+                    "shiftyc",
+                    "cellular",
+                    "scaling_net_shader",
+                    "erratic_wave_shader",
+                ],
+            ),
+            (
+                &["*_shader"],
+                &[],
+                &[],
+                &["scaling_net_shader", "erratic_wave_shader"],
+            ),
+            (&["shifty*", "cellular"], &[], &[], &["shiftyc", "cellular"]),
+            // Exclude wins over include
+            (&["*_shader"], &["erratic*"], &[], &["scaling_net_shader"]),
+            (&["shiftyc"], &["shiftyc"], &[], &[]),
+            // Any of the tags matches
+            (&[], &[], &["lyon", "nope"], &["shiftyc", "cellular"]),
+        ];
+        for (include, exclude, tags, expected) in cases {
+            let names: Vec<String> = filter_sketches(
+                sketches(),
+                &strings(include),
+                &strings(exclude),
+                &strings(tags),
+            )
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+            assert_eq!(
+                names,
+                strings(expected),
+                "{:?} {:?} {:?}",
+                include,
+                exclude,
+                tags
+            );
+        }
+    }
+
+    #[test]
+    fn tags_and_globs_combine() {
+        let names: Vec<String> = filter_sketches(
+            sketches(),
+            &strings(&["s*"]),
+            &strings(&["shiftyc"]),
+            &strings(&["shader"]),
+        )
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+        assert_eq!(names, strings(&["scaling_net_shader"]));
+    }
+
+    #[test]
+    fn passes_only_declared_features() {
+        let declared = sketch("shiftyc", &[], &["debuglog", "framestats"]);
+        assert_eq!(
+            feature_args(&declared, true, true),
+            strings(&["--features=debuglog", "--features=framestats"])
+        );
+        assert_eq!(
+            feature_args(&declared, true, false),
+            strings(&["--features=framestats"])
+        );
+        let undeclared = sketch("other", &[], &["framestats"]);
+        assert_eq!(
+            feature_args(&undeclared, true, true),
+            strings(&["--features=framestats"])
+        );
+        assert!(feature_args(&sketch("bare", &[], &[]), true, true).is_empty());
+        assert!(feature_args(&declared, false, false).is_empty());
+    }
+
+    #[test]
+    fn finds_a_variation_per_preset() {
+        let package_dir =
+            std::env::temp_dir().join(format!("build-sketches-{}", std::process::id()));
+        let presets = package_dir.join(PRESETS_PATH).join("shifty");
+        fs::create_dir_all(&presets).unwrap();
+        for file in ["star.preset.ron", "hex.preset.ron", "notes.txt"] {
+            fs::write(presets.join(file), "()").unwrap();
+        }
+        let variations = preset_variations(&package_dir, "shifty");
+        let missing = preset_variations(&package_dir, "cellular");
+        fs::remove_dir_all(&package_dir).unwrap();
+
+        assert_eq!(
+            variations,
+            vec![
+                Variation {
+                    name: String::from("shiftyhex"),
+                    opts: String::from("preset=hex"),
+                },
+                Variation {
+                    name: String::from("shiftystar"),
+                    opts: String::from("preset=star"),
+                },
+            ]
+        );
+        assert!(missing.is_empty());
+    }
+}